@@ -49,7 +49,8 @@ pub enum argonautica_error_t {
     /// Attempted to allocate memory (using malloc) and failed
     ARGONAUTICA_ERROR_MEMORY_ALLOCATION = 11,
 
-    /// Memory size invalid. Memory size must be a power of two
+    /// Memory size invalid. No longer returned, since any memory size of at least 8 times the
+    /// number of lanes is valid
     ARGONAUTICA_ERROR_MEMORY_SIZE_INVALID = 12,
 
     /// Memory size too small. Memory size must be at least 8 times the number of lanes
@@ -109,7 +110,7 @@ impl argonautica_error_t {
             ARGONAUTICA_ERROR_LANES_TOO_FEW => b"Lanes too few. Lanes must be greater than 0\0",
             ARGONAUTICA_ERROR_LANES_TOO_MANY => b"Lanes too many. Lanes must be less than 2^24\0",
             ARGONAUTICA_ERROR_MEMORY_ALLOCATION => b"Attempted to allocate memory (using malloc) and failed\0",
-            ARGONAUTICA_ERROR_MEMORY_SIZE_INVALID => b"Memory size invalid. Memory size must be a power of two\0",
+            ARGONAUTICA_ERROR_MEMORY_SIZE_INVALID => b"Memory size invalid. No longer returned, since any memory size of at least 8 times the number of lanes is valid\0",
             ARGONAUTICA_ERROR_MEMORY_SIZE_TOO_SMALL => b"Memory size too small. Memory size must be at least 8 times the number of lanes\0",
             ARGONAUTICA_ERROR_NULL_PTR => b"Null pointer error. Passed a null pointer as an argument where that is not allowed\0",
             ARGONAUTICA_ERROR_OS_RNG => b"Failed to access OS random number generator\0",
@@ -138,13 +139,18 @@ impl From<Error> for argonautica_error_t {
             BackendUnsupportedError => ARGONAUTICA_ERROR_BACKEND_UNSUPPORTED,
             Base64DecodeError => ARGONAUTICA_ERROR_BASE64_DECODE,
             Bug => ARGONAUTICA_ERROR_BUG,
+            CancelledError => ARGONAUTICA_ERROR_BUG,
             HashDecodeError => ARGONAUTICA_ERROR_HASH_DECODE,
             HashLenTooShortError => ARGONAUTICA_ERROR_HASH_LEN_TOO_SHORT,
             HashMissingError => ARGONAUTICA_ERROR_HASH_MISSING,
+            HashParamsRejectedError => ARGONAUTICA_ERROR_HASH_DECODE,
             IterationsTooFewError => ARGONAUTICA_ERROR_ITERATIONS_TOO_FEW,
             LanesTooFewError => ARGONAUTICA_ERROR_LANES_TOO_FEW,
             LanesTooManyError => ARGONAUTICA_ERROR_LANES_TOO_MANY,
+            LintError => ARGONAUTICA_ERROR_BUG,
             MemoryAllocationError => ARGONAUTICA_ERROR_MEMORY_ALLOCATION,
+            MemoryBudgetError => ARGONAUTICA_ERROR_MEMORY_ALLOCATION,
+            MemoryLockError => ARGONAUTICA_ERROR_MEMORY_ALLOCATION,
             MemorySizeInvalidError => ARGONAUTICA_ERROR_MEMORY_SIZE_INVALID,
             MemorySizeTooSmallError => ARGONAUTICA_ERROR_MEMORY_SIZE_TOO_SMALL,
             OsRngError => ARGONAUTICA_ERROR_OS_RNG,
@@ -152,14 +158,20 @@ impl From<Error> for argonautica_error_t {
             PasswordMissingError => ARGONAUTICA_ERROR_PASSWORD_MISSING,
             PasswordTooLongError => ARGONAUTICA_ERROR_PASSWORD_TOO_LONG,
             PasswordTooShortError => ARGONAUTICA_ERROR_PASSWORD_TOO_SHORT,
+            RngError => ARGONAUTICA_ERROR_OS_RNG,
+            RngMissingError => ARGONAUTICA_ERROR_BUG,
             SaltTooLongError => ARGONAUTICA_ERROR_SALT_TOO_LONG,
             SaltTooShortError => ARGONAUTICA_ERROR_SALT_TOO_SHORT,
+            SecretKeyIdInvalidError => ARGONAUTICA_ERROR_BUG,
+            SecretKeyIdUnknownError => ARGONAUTICA_ERROR_BUG,
             SecretKeyImmutableError => ARGONAUTICA_ERROR_BUG,
             SecretKeyMissingError => ARGONAUTICA_ERROR_BUG,
             SecretKeyTooLongError => ARGONAUTICA_ERROR_SECRET_KEY_TOO_LONG,
             ThreadError => ARGONAUTICA_ERROR_THREAD,
+            ThreadPoolFullError => ARGONAUTICA_ERROR_THREAD,
             ThreadsTooFewError => ARGONAUTICA_ERROR_THREADS_TOO_FEW,
             ThreadsTooManyError => ARGONAUTICA_ERROR_THREADS_TOO_MANY,
+            TimeoutError => ARGONAUTICA_ERROR_BUG,
             Utf8EncodeError => ARGONAUTICA_ERROR_UTF8_ENCODE,
             VariantEncodeError => ARGONAUTICA_ERROR_BUG,
             VersionEncodeError => ARGONAUTICA_ERROR_BUG,
@@ -54,6 +54,7 @@ use crate::{
 /// * Backend:
 ///     * `backend` = `ARGONAUTICA_C` for the C backend
 ///     * `backend` = `ARGONAUTICA_RUST` for the Rust backend
+///     * `backend` = `ARGONAUTICA_AUTO` for the C backend, unless the Rust backend uses a higher SIMD level on the current machine
 /// * Hash length:
 ///     * `hash_len` = a `uint32_t` indicating the desired hash length (in number of bytes)
 /// * Iterations:
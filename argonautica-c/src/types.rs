@@ -11,6 +11,9 @@ pub enum argonautica_backend_t {
 
     /// The Rust backend
     ARGONAUTICA_RUST = 1,
+
+    /// The C backend, unless the Rust backend uses a higher SIMD level on the current machine
+    ARGONAUTICA_AUTO = 2,
 }
 
 impl From<argonautica_backend_t> for Backend {
@@ -18,6 +21,7 @@ impl From<argonautica_backend_t> for Backend {
         match backend {
             argonautica_backend_t::ARGONAUTICA_C => Backend::C,
             argonautica_backend_t::ARGONAUTICA_RUST => Backend::Rust,
+            argonautica_backend_t::ARGONAUTICA_AUTO => Backend::Auto,
         }
     }
 }
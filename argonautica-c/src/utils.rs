@@ -6,7 +6,7 @@ use crate::argonautica_variant_t;
 
 fn base64_len(len: u32) -> usize {
     let bits = 8 * len as usize;
-    bits / 6 + if bits % 6 != 0 { 1 } else { 0 }
+    bits / 6 + if !bits.is_multiple_of(6) { 1 } else { 0 }
 }
 
 /// Function that returns the length of a string-encoded hash (in bytes and including the NULL byte).
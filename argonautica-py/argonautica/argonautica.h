@@ -14,6 +14,10 @@ typedef enum {
    * The Rust backend
    */
   ARGONAUTICA_RUST = 1,
+  /*
+   * The C backend, unless the Rust backend uses a higher SIMD level on the current machine
+   */
+  ARGONAUTICA_AUTO = 2,
 } argonautica_backend_t;
 
 /*
@@ -69,7 +73,8 @@ typedef enum {
    */
   ARGONAUTICA_ERROR_MEMORY_ALLOCATION = 11,
   /*
-   * Memory size invalid. Memory size must be a power of two
+   * Memory size invalid. No longer returned, since any memory size of at least 8 times the
+   * number of lanes is valid
    */
   ARGONAUTICA_ERROR_MEMORY_SIZE_INVALID = 12,
   /*
@@ -216,6 +221,7 @@ const char *argonautica_error_msg(argonautica_error_t err);
  * * Backend:
  *     * `backend` = `ARGONAUTICA_C` for the C backend
  *     * `backend` = `ARGONAUTICA_RUST` for the Rust backend
+ *     * `backend` = `ARGONAUTICA_AUTO` for the C backend, unless the Rust backend uses a higher SIMD level on the current machine
  * * Hash length:
  *     * `hash_len` = a `uint32_t` indicating the desired hash length (in number of bytes)
  * * Iterations:
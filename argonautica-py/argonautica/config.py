@@ -6,10 +6,13 @@ from argonautica.core.ffi import lib
 
 class Backend(Enum):
     """
-    ``Backend`` is an ``Enum`` representing the two argonautica "backends":
+    ``Backend`` is an ``Enum`` representing the two argonautica "backends" and a way to pick
+    between them:
 
     * ``Backend.C``: When using this backend, the core algorithm will be performed by C code
     * ``Backend.Rust``: When using this backend, the core algorithm will be performed by Rust code
+    * ``Backend.Auto``: Uses ``Backend.C``, unless ``Backend.Rust`` uses a higher SIMD level on
+      the current machine
 
     *Note: Backend.Rust is not yet implemented (but hopefully will be soon); so, for the
     moment, if you choose this backend your program will throw an exception when calling hash
@@ -17,6 +20,7 @@ class Backend(Enum):
     """
     C = lib.ARGONAUTICA_C
     Rust = lib.ARGONAUTICA_RUST
+    Auto = lib.ARGONAUTICA_AUTO
 
 
 class Variant(Enum):
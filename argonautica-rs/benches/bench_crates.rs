@@ -57,6 +57,19 @@ fn bench_crates(c: &mut Criterion) {
         })
     });
 
+    // argonautica (rust backend)
+    let mut hasher = argonautica::Hasher::default();
+    hasher
+        .configure_backend(argonautica::config::Backend::Rust)
+        .configure_password_clearing(false)
+        .configure_variant(argonautica::config::Variant::Argon2i)
+        .opt_out_of_secret_key(true);
+    group.bench_function("argonautica-rust", move |b| {
+        b.iter(|| {
+            let _ = hasher.with_password(PASSWORD).hash_raw().unwrap();
+        })
+    });
+
     // rust-argon2
     let config = argon2::Config {
         variant: argon2::Variant::Argon2i,
@@ -1,40 +1,55 @@
+#[cfg(feature = "backend-c")]
 extern crate bindgen;
+#[cfg(feature = "backend-c")]
 extern crate cc;
-#[macro_use]
-extern crate cfg_if;
 extern crate failure;
+#[cfg(feature = "backend-c")]
 extern crate tempfile;
 
+#[cfg(feature = "backend-c")]
 use std::{env, fs, path::Path};
 
+#[cfg(feature = "backend-c")]
 use bindgen::RustEdition;
 
-cfg_if! {
-    if #[cfg(feature = "simd")] {
-        const IS_SIMD: bool = true;
-    } else {
-        const IS_SIMD: bool = false;
+/// Instruction sets for which `opt.c` can be compiled on x86 targets, from slowest to fastest.
+/// The names match the variants of `argonautica::config::Implementation`
+const SIMD_IMPLEMENTATIONS: [&str; 4] = ["sse2", "ssse3", "avx2", "avx512f"];
+
+fn main() -> Result<(), failure::Error> {
+    println!("cargo::rustc-check-cfg=cfg(argonautica_c_portable)");
+    for name in &SIMD_IMPLEMENTATIONS {
+        println!("cargo::rustc-check-cfg=cfg(argonautica_c_{})", name);
     }
+    #[cfg(feature = "backend-c")]
+    build_c()?;
+    Ok(())
 }
 
-fn main() -> Result<(), failure::Error> {
+/// Compiles the C implementation of Argon2 into a static archive and generates bindings to it.
+/// Only needed for `Backend::C`; builds without the `backend-c` feature skip this entirely.
+///
+/// Rather than picking between `ref.c` and `opt.c` at compile time, the archive contains
+/// `ref.c` plus, on x86, one copy of `opt.c` per instruction set the compiler supports. Each
+/// copy gets its own name for `fill_segment` and `c/fill_segment.c` dispatches between them
+/// at runtime. For every copy compiled, `argonautica_c_<name>` is set as a cfg so that the
+/// Rust side knows which implementations it may select
+#[cfg(feature = "backend-c")]
+fn build_c() -> Result<(), failure::Error> {
     let temp = tempfile::tempdir()?;
     let temp_dir = temp.path();
     let temp_dir_str = temp_dir.to_str().unwrap();
 
-    let blamka_header = if IS_SIMD {
-        "phc-winner-argon2/src/blake2/blamka-round-opt.h"
-    } else {
-        "phc-winner-argon2/src/blake2/blamka-round-ref.h"
-    };
     for header_path_str in &[
+        "c/fill_segment.h",
         "phc-winner-argon2/include/argon2.h",
         "phc-winner-argon2/src/core.h",
         "phc-winner-argon2/src/encoding.h",
         "phc-winner-argon2/src/thread.h",
         "phc-winner-argon2/src/blake2/blake2-impl.h",
         "phc-winner-argon2/src/blake2/blake2.h",
-        blamka_header,
+        "phc-winner-argon2/src/blake2/blamka-round-opt.h",
+        "phc-winner-argon2/src/blake2/blamka-round-ref.h",
     ] {
         let header_path = Path::new(*header_path_str);
         let header_filename = header_path.file_name().unwrap();
@@ -43,48 +58,84 @@ fn main() -> Result<(), failure::Error> {
         fs::copy(from, to)?;
     }
 
-    let blamka_code = if IS_SIMD {
-        "phc-winner-argon2/src/opt.c"
-    } else {
-        "phc-winner-argon2/src/ref.c"
+    let opt_level = env::var("OPT_LEVEL")?.parse::<usize>()?;
+    let out_dir_string = env::var("OUT_DIR")?;
+    let out_dir = Path::new(&out_dir_string);
+    let new_builder = || {
+        let mut builder = cc::Build::new();
+        builder
+            .include(temp_dir)
+            .flag_if_supported("-pthread")
+            .flag_if_supported("-std=c89")
+            .warnings(false)
+            .extra_warnings(false);
+        if opt_level < 3 {
+            builder.flag_if_supported("-g");
+        }
+        builder
     };
-    let mut builder = cc::Build::new();
+
+    let mut implementations = vec![("portable", "phc-winner-argon2/src/ref.c", None)];
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH")?;
+    if target_arch == "x86" || target_arch == "x86_64" {
+        let is_msvc = new_builder().get_compiler().is_like_msvc();
+        for name in &SIMD_IMPLEMENTATIONS {
+            let flag = match simd_flag(name, is_msvc, &target_arch) {
+                Some(flag) => flag,
+                None => continue,
+            };
+            if let Some(flag) = flag
+                && !new_builder().is_flag_supported(flag)?
+            {
+                continue;
+            }
+            implementations.push((*name, "phc-winner-argon2/src/opt.c", flag));
+        }
+    }
+
+    let mut builder = new_builder();
+    for (name, file, flag) in &implementations {
+        let mut implementation_builder = new_builder();
+        implementation_builder
+            .file(file)
+            .define(
+                "fill_segment",
+                Some(format!("argonautica_fill_segment_{}", name).as_str()),
+            )
+            .out_dir(out_dir.join(format!("fill_segment_{}", name)));
+        if let Some(flag) = flag {
+            implementation_builder.flag(flag);
+        }
+        builder.objects(implementation_builder.compile_intermediates());
+        builder.define(&format!("ARGONAUTICA_HAVE_{}", name.to_uppercase()), None);
+        println!("cargo:rustc-cfg=argonautica_c_{}", name);
+    }
     builder
         .files(&[
+            "c/fill_segment.c",
             "phc-winner-argon2/src/argon2.c",
             "phc-winner-argon2/src/core.c",
             "phc-winner-argon2/src/blake2/blake2b.c",
             "phc-winner-argon2/src/encoding.c",
             "phc-winner-argon2/src/thread.c",
-            blamka_code,
         ])
-        .include(temp_dir)
-        .flag_if_supported("-pthread")
-        .flag_if_supported("-std=c89")
-        .warnings(false)
-        .extra_warnings(false);
-    if IS_SIMD {
-        builder.flag_if_supported("-march=native");
-    }
-    let opt_level = env::var("OPT_LEVEL")?.parse::<usize>()?;
-    if opt_level < 3 {
-        builder.flag_if_supported("-g");
-    }
-    builder.compile("argon2");
+        .compile("argon2");
 
-    let out_dir_string = env::var("OUT_DIR")?;
-    let out_dir = Path::new(&out_dir_string);
     let file_path = out_dir.join("bindings.rs");
     let bindings = bindgen::Builder::default()
         .header(format!("{}/argon2.h", temp_dir_str))
         .header(format!("{}/encoding.h", temp_dir_str))
+        .header(format!("{}/fill_segment.h", temp_dir_str))
         .allowlist_function("argon2_ctx")
         .allowlist_function("argon2_encodedlen")
         .allowlist_function("argon2_error_message")
         .allowlist_function("argon2_verify_ctx")
+        .allowlist_function("argonautica_select_fill_segment")
+        .allowlist_function("argonautica_set_cancel_callback")
         .allowlist_function("decode_string")
         .allowlist_function("encode_string")
         .allowlist_type("Argon2_ErrorCodes")
+        .allowlist_type("argonautica_fill_segment_t")
         .ctypes_prefix("libc")
         .layout_tests(true)
         .wrap_unsafe_ops(true)
@@ -102,3 +153,28 @@ fn main() -> Result<(), failure::Error> {
 
     Ok(())
 }
+
+/// Returns the compiler flag needed to build `opt.c` for the given instruction set, `Some(None)`
+/// if no flag is needed, or `None` if the instruction set cannot be targeted with this compiler
+#[cfg(feature = "backend-c")]
+fn simd_flag(name: &str, is_msvc: bool, target_arch: &str) -> Option<Option<&'static str>> {
+    if is_msvc {
+        // MSVC has no switch for SSSE3 and never defines `__SSSE3__`, so that copy of `opt.c`
+        // would be identical to the SSE2 one
+        match (name, target_arch) {
+            ("sse2", "x86_64") => Some(None),
+            ("sse2", _) => Some(Some("/arch:SSE2")),
+            ("avx2", _) => Some(Some("/arch:AVX2")),
+            ("avx512f", _) => Some(Some("/arch:AVX512")),
+            _ => None,
+        }
+    } else {
+        match name {
+            "sse2" => Some(Some("-msse2")),
+            "ssse3" => Some(Some("-mssse3")),
+            "avx2" => Some(Some("-mavx2")),
+            "avx512f" => Some(Some("-mavx512f")),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/*
+ * Runtime dispatch for fill_segment. build.rs compiles ref.c and, on x86,
+ * opt.c once per supported instruction set, renaming fill_segment in each
+ * copy to argonautica_fill_segment_<name> and defining
+ * ARGONAUTICA_HAVE_<NAME> for every copy it built. This file provides the
+ * fill_segment that core.c calls and forwards to the selected copy, unless
+ * the cancel callback says the hash was cancelled
+ */
+
+#include "core.h"
+#include "fill_segment.h"
+
+typedef void (*fill_segment_fn)(const argon2_instance_t *instance,
+                                argon2_position_t position);
+
+void argonautica_fill_segment_portable(const argon2_instance_t *instance,
+                                       argon2_position_t position);
+#if defined(ARGONAUTICA_HAVE_SSE2)
+void argonautica_fill_segment_sse2(const argon2_instance_t *instance,
+                                   argon2_position_t position);
+#endif
+#if defined(ARGONAUTICA_HAVE_SSSE3)
+void argonautica_fill_segment_ssse3(const argon2_instance_t *instance,
+                                    argon2_position_t position);
+#endif
+#if defined(ARGONAUTICA_HAVE_AVX2)
+void argonautica_fill_segment_avx2(const argon2_instance_t *instance,
+                                   argon2_position_t position);
+#endif
+#if defined(ARGONAUTICA_HAVE_AVX512F)
+void argonautica_fill_segment_avx512f(const argon2_instance_t *instance,
+                                      argon2_position_t position);
+#endif
+
+static fill_segment_fn selected = argonautica_fill_segment_portable;
+static argonautica_cancel_cbk cancel_cbk = NULL;
+
+int argonautica_select_fill_segment(argonautica_fill_segment_t implementation) {
+    switch (implementation) {
+    case ARGONAUTICA_FILL_SEGMENT_PORTABLE:
+        selected = argonautica_fill_segment_portable;
+        return 1;
+#if defined(ARGONAUTICA_HAVE_SSE2)
+    case ARGONAUTICA_FILL_SEGMENT_SSE2:
+        selected = argonautica_fill_segment_sse2;
+        return 1;
+#endif
+#if defined(ARGONAUTICA_HAVE_SSSE3)
+    case ARGONAUTICA_FILL_SEGMENT_SSSE3:
+        selected = argonautica_fill_segment_ssse3;
+        return 1;
+#endif
+#if defined(ARGONAUTICA_HAVE_AVX2)
+    case ARGONAUTICA_FILL_SEGMENT_AVX2:
+        selected = argonautica_fill_segment_avx2;
+        return 1;
+#endif
+#if defined(ARGONAUTICA_HAVE_AVX512F)
+    case ARGONAUTICA_FILL_SEGMENT_AVX512F:
+        selected = argonautica_fill_segment_avx512f;
+        return 1;
+#endif
+    default:
+        return 0;
+    }
+}
+
+void argonautica_set_cancel_callback(argonautica_cancel_cbk callback) {
+    cancel_cbk = callback;
+}
+
+void fill_segment(const argon2_instance_t *instance,
+                  argon2_position_t position) {
+    if (cancel_cbk != NULL && cancel_cbk(instance->context_ptr)) {
+        return;
+    }
+    selected(instance, position);
+}
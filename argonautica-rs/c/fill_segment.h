@@ -0,0 +1,44 @@
+/*
+ * Runtime selection between the fill_segment implementations compiled into
+ * the argon2 static archive by argonautica's build.rs
+ */
+
+#ifndef ARGONAUTICA_FILL_SEGMENT_H
+#define ARGONAUTICA_FILL_SEGMENT_H
+
+/*
+ * Implementations of fill_segment. The discriminants must match those of
+ * argonautica::config::Implementation
+ */
+typedef enum Argonautica_FillSegment {
+    ARGONAUTICA_FILL_SEGMENT_PORTABLE = 1,
+    ARGONAUTICA_FILL_SEGMENT_SSE2 = 2,
+    ARGONAUTICA_FILL_SEGMENT_SSSE3 = 3,
+    ARGONAUTICA_FILL_SEGMENT_AVX2 = 4,
+    ARGONAUTICA_FILL_SEGMENT_AVX512F = 5
+} argonautica_fill_segment_t;
+
+/*
+ * Selects the implementation used by all subsequent calls to fill_segment.
+ * Must be called before any hashing starts, as the selection is not
+ * synchronized. Returns 1 on success or 0 if the requested implementation was
+ * not compiled in, in which case the selection is left unchanged
+ */
+int argonautica_select_fill_segment(argonautica_fill_segment_t implementation);
+
+/*
+ * Asked before each segment whether the hash it belongs to was cancelled.
+ * Receives the argon2_context that was passed to argon2_ctx and returns
+ * nonzero to skip the segment, in which case the hash is garbage and the
+ * caller must discard it
+ */
+typedef int (*argonautica_cancel_cbk)(const void *context);
+
+/*
+ * Sets the callback fill_segment asks before each segment, or NULL for none.
+ * Like argonautica_select_fill_segment, must be called before any hashing
+ * starts
+ */
+void argonautica_set_cancel_callback(argonautica_cancel_cbk callback);
+
+#endif
@@ -2,56 +2,33 @@ extern crate argonautica;
 extern crate failure;
 extern crate num_cpus;
 
-use std::time::Instant;
+use std::time::Duration;
 
-use argonautica::{
-    Hasher,
-    config::{Variant, Version},
-    input::{Salt, SecretKey},
-};
+use argonautica::{Hasher, config::HasherConfig};
 
-pub const HASH_LEN: u32 = 32;
-pub const ITERATIONS: [u32; 7] = [64, 128, 192, 256, 320, 384, 448];
-pub const MEMORY_SIZES: [u32; 7] = [256, 512, 1_024, 2_048, 4_096, 8_192, 16_384];
+pub const MAX_MEMORY_SIZE: u32 = 262_144;
 pub const PASSWORD: &str = "P@ssw0rd";
-pub const SALT_LEN: u32 = 32;
-pub const VARIANT: Variant = Variant::Argon2id;
-pub const VERSION: Version = Version::_0x13;
+pub const TARGET: Duration = Duration::from_millis(400);
 
 fn main() -> Result<(), failure::Error> {
-    let salt = Salt::random(SALT_LEN);
-    let secret_key =
-        SecretKey::from_base64_encoded("t9nGEsDxjWtJYdYeExdB6/HU0vg+rT6czv6HSjVjZng=")?;
-    let threads = num_cpus::get();
-    for memory_size in &MEMORY_SIZES {
-        for iterations in &ITERATIONS {
-            let mut hasher = Hasher::default();
-            hasher
-                .configure_hash_len(HASH_LEN)
-                .configure_iterations(*iterations)
-                .configure_lanes(threads as u32)
-                .configure_memory_size(*memory_size)
-                .configure_threads(threads as u32)
-                .configure_variant(VARIANT)
-                .configure_version(VERSION)
-                .with_password(PASSWORD)
-                .with_salt(&salt)
-                .with_secret_key(&secret_key);
-            let now = Instant::now();
-            let _ = hasher.hash()?;
-            let elapsed = now.elapsed();
-            let millis =
-                elapsed.as_secs() as f32 * 1_000.0 + elapsed.subsec_nanos() as f32 / 1_000_000.0;
-            let ok = if millis > 300.0 && millis < 500.0 {
-                "👍"
-            } else {
-                "💩"
-            };
-            println!(
-                "{} threads {}, memory_size: {}, iterations: {}, milliseconds: {:.0}",
-                ok, threads, memory_size, iterations, millis,
-            );
-        }
-    }
+    let threads = num_cpus::get() as u32;
+    let calibration = HasherConfig::calibrate(TARGET, MAX_MEMORY_SIZE, threads)?;
+    let config = calibration.config();
+    println!(
+        "threads: {}, memory_size: {}, iterations: {}, milliseconds (min / median / max): {} / {} / {}",
+        config.threads(),
+        config.memory_size(),
+        config.iterations(),
+        calibration.min().as_millis(),
+        calibration.median().as_millis(),
+        calibration.max().as_millis(),
+    );
+
+    let mut hasher = Hasher::from_calibration(&calibration);
+    let hash = hasher
+        .with_password(PASSWORD)
+        .with_secret_key("t9nGEsDxjWtJYdYeExdB6/HU0vg+rT6czv6HSjVjZng=")
+        .hash()?;
+    println!("{}", &hash);
     Ok(())
 }
@@ -1,7 +1,86 @@
 #![cfg(test)]
 
-use crate::{Error, output::HashRaw};
+use std::ffi::{CStr, CString};
 
-pub(crate) fn decode_c(_hash: &str) -> Result<HashRaw, Error> {
-    unimplemented!(); // TODO:
+use crate::{
+    Error, ErrorKind,
+    config::{Variant, Version},
+    ffi,
+    output::HashRaw,
+};
+
+pub(crate) fn decode_c(hash: &str) -> Result<HashRaw, Error> {
+    let variant = [Variant::Argon2d, Variant::Argon2i, Variant::Argon2id]
+        .iter()
+        .cloned()
+        .find(|variant| hash.starts_with(&format!("${}$", variant.as_str())))
+        .ok_or_else(|| {
+            Error::new(ErrorKind::HashDecodeError).add_context(format!("Hash: {}", hash))
+        })?;
+    let hash_cstring = CString::new(hash).map_err(|_| {
+        Error::new(ErrorKind::HashDecodeError).add_context(format!("Hash: {}", hash))
+    })?;
+    // The decoded salt and hash are always shorter than the string they were decoded from
+    let mut raw_hash_bytes = vec![0u8; hash.len()];
+    let mut raw_salt_bytes = vec![0u8; hash.len()];
+    let mut context = ffi::Argon2_Context {
+        out: raw_hash_bytes.as_mut_ptr(),
+        outlen: raw_hash_bytes.len() as u32,
+        pwd: ::std::ptr::null_mut(),
+        pwdlen: 0,
+        salt: raw_salt_bytes.as_mut_ptr(),
+        saltlen: raw_salt_bytes.len() as u32,
+        secret: ::std::ptr::null_mut(),
+        secretlen: 0,
+        ad: ::std::ptr::null_mut(),
+        adlen: 0,
+        t_cost: 0,
+        m_cost: 0,
+        lanes: 0,
+        threads: 0,
+        version: 0,
+        allocate_cbk: None,
+        free_cbk: None,
+        flags: 0,
+    };
+    let context_ptr = &mut context as *mut ffi::argon2_context;
+    let err = unsafe {
+        ffi::decode_string(
+            context_ptr,
+            hash_cstring.as_ptr(),
+            variant as ffi::argon2_type,
+        )
+    };
+    check_error(err, hash)?;
+    raw_hash_bytes.truncate(context.outlen as usize);
+    raw_salt_bytes.truncate(context.saltlen as usize);
+    Ok(HashRaw {
+        iterations: context.t_cost,
+        key_id: None,
+        lanes: context.lanes,
+        memory_size: context.m_cost,
+        omit_version: !hash[variant.as_str().len() + 1..].starts_with("$v="),
+        raw_hash_bytes,
+        raw_salt_bytes,
+        variant,
+        version: Version::from_u32(context.version)?,
+    })
+}
+
+fn check_error(err: ffi::Argon2_ErrorCodes, hash: &str) -> Result<(), Error> {
+    match err {
+        ffi::Argon2_ErrorCodes_ARGON2_OK => Ok(()),
+        ffi::Argon2_ErrorCodes_ARGON2_DECODING_FAIL => {
+            Err(Error::new(ErrorKind::HashDecodeError).add_context(format!("Hash: {}", hash)))
+        }
+        _ => {
+            let err_msg_ptr = unsafe { ffi::argon2_error_message(err) };
+            let err_msg_cstr = unsafe { CStr::from_ptr(err_msg_ptr) };
+            let err_msg = err_msg_cstr.to_str().unwrap(); // Safe; see argon2_error_message
+            Err(Error::new(ErrorKind::Bug).add_context(format!(
+                "Unhandled error from C. Error code: {}. Error {}",
+                err, err_msg,
+            )))
+        }
+    }
 }
@@ -1,9 +1,109 @@
-use std::ffi::CStr;
+use std::{
+    cell::RefCell,
+    ffi::CStr,
+    ptr::{self, NonNull},
+    sync::{
+        Mutex, Once, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
-use crate::{Error, ErrorKind, Hasher, ffi, output::HashRaw};
+use crate::{
+    Error, ErrorKind, Hasher, allocator::SharedAllocator, backend::c::implementation_c,
+    cancellation::Cancellation, ffi, input::SecretKey, output::HashRaw, secure_memory,
+};
+
+static CANCEL_CALLBACK: Once = Once::new();
+
+thread_local! {
+    /// Allocation settings of the hash the current thread is computing, for [`allocate`] and
+    /// [`deallocate`], which get no context pointer. `argon2_ctx` allocates and frees the
+    /// block matrix on the thread that calls it
+    static ALLOCATION: RefCell<Option<Allocation>> = const { RefCell::new(None) };
+}
+
+struct Allocation {
+    allocator: SharedAllocator,
+    /// Why [`allocate`] failed, if it did, since `argon2_ctx` only reports a generic
+    /// allocation error
+    error: Option<Error>,
+    secure: bool,
+}
+
+/// `allocate_cbk` of the `Argon2_Context` of hashes with an allocator or in secure-memory mode
+unsafe extern "C" fn allocate(memory: *mut *mut u8, size: usize) -> libc::c_int {
+    let ptr = ALLOCATION.with(|allocation| {
+        let mut allocation = allocation.borrow_mut();
+        let allocation = allocation.as_mut()?;
+        secure_memory::allocate(&allocation.allocator, size, allocation.secure)
+            .map_err(|error| allocation.error = Some(error))
+            .ok()
+    });
+    let ptr = ptr.map_or(ptr::null_mut(), NonNull::as_ptr);
+    unsafe { *memory = ptr };
+    if ptr.is_null() { -1 } else { 0 }
+}
+
+/// `free_cbk` of the `Argon2_Context` of hashes with an allocator or in secure-memory mode.
+/// The C implementation has already wiped the memory
+unsafe extern "C" fn deallocate(memory: *mut u8, size: usize) {
+    let Some(ptr) = NonNull::new(memory) else {
+        return;
+    };
+    ALLOCATION.with(|allocation| {
+        if let Some(ref allocation) = *allocation.borrow() {
+            unsafe {
+                if allocation.secure {
+                    secure_memory::unlock(ptr, size);
+                }
+                allocation.allocator.deallocate(ptr, size);
+            }
+        }
+    });
+}
+
+/// Addresses of the `Context`s of the hashes that `hash_raw_c` is computing. The cancel callback
+/// is installed for the whole process, so other callers of `argon2_ctx` linked into the same
+/// binary (e.g. C code using the phc API directly) invoke [`is_cancelled`] with plain
+/// `Argon2_Context`s, which it must not cast
+static CONTEXTS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+/// `Argon2_Context` plus what [`is_cancelled`] needs. `argon2_ctx` hands the pointer to `inner`
+/// back to [`is_cancelled`], which may cast it to a `Context` if it is registered in
+/// [`CONTEXTS`], because `inner` is the first field
+#[repr(C)]
+struct Context<'a> {
+    inner: ffi::Argon2_Context,
+    cancellation: &'a Cancellation,
+    cancelled: AtomicBool,
+}
+
+/// Called by `fill_segment` before each segment (on the threads of the C implementation).
+/// Returns `1` to skip the segment if the hash was cancelled or timed out
+unsafe extern "C" fn is_cancelled(context: *const libc::c_void) -> libc::c_int {
+    let registered = CONTEXTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&(context as usize));
+    if !registered {
+        return 0;
+    }
+    // Safe, as a registered context stays alive until `argon2_ctx`, and with it every call to
+    // this function for that context, has returned
+    let context = unsafe { &*(context as *const Context) };
+    if context.cancellation.check().is_err() {
+        context.cancelled.store(true, Ordering::Relaxed);
+        return 1;
+    }
+    0
+}
 
 impl Hasher<'_> {
     pub(crate) fn hash_raw_c(&mut self) -> Result<HashRaw, Error> {
+        let _ = implementation_c(); // Selects the fill_segment implementation on first use
+        CANCEL_CALLBACK.call_once(|| unsafe {
+            ffi::argonautica_set_cancel_callback(Some(is_cancelled));
+        });
         let (ad, adlen) = match self.additional_data {
             Some(ref additional_data) => (
                 additional_data.as_bytes().as_ptr() as *mut u8,
@@ -18,42 +118,90 @@ impl Hasher<'_> {
             ),
             None => return Err(Error::new(ErrorKind::PasswordMissingError)),
         };
-        let (secret, secretlen) = match self.secret_key {
-            Some(ref mut secret_key) => (
-                secret_key.as_bytes().as_ptr() as *mut u8,
-                secret_key.len() as u32,
-            ),
+        // Keeps the bytes of a protected secret key readable until the hash is done
+        let secret_key = self
+            .secret_key
+            .as_ref()
+            .map(SecretKey::try_as_bytes)
+            .transpose()?;
+        let (secret, secretlen) = match secret_key {
+            Some(ref bytes) => (bytes.as_ptr() as *mut u8, bytes.len() as u32),
             None => (::std::ptr::null_mut(), 0),
         };
+        let secure = self.config.secure_memory();
+        let allocation = match self.config.allocator() {
+            Some(allocator) => Some(allocator.clone()),
+            None if secure => Some(secure_memory::default_allocator()),
+            None => None,
+        }
+        .map(|allocator| Allocation {
+            allocator,
+            error: None,
+            secure,
+        });
+        let (allocate_cbk, free_cbk): (ffi::allocate_fptr, ffi::deallocate_fptr) = match allocation
+        {
+            Some(_) => (Some(allocate), Some(deallocate)),
+            None => (None, None),
+        };
         let mut buffer = vec![0u8; self.config.hash_len() as usize];
-        let mut context = ffi::Argon2_Context {
-            out: buffer.as_mut_ptr(),
-            outlen: buffer.len() as u32,
-            pwd,
-            pwdlen,
-            salt: self.salt.as_bytes().as_ptr() as *mut u8,
-            saltlen: self.salt.len() as u32,
-            secret,
-            secretlen,
-            ad,
-            adlen,
-            t_cost: self.config.iterations(),
-            m_cost: self.config.memory_size(),
-            lanes: self.config.lanes(),
-            threads: self.config.threads(),
-            version: self.config.version() as u32,
-            allocate_cbk: None,
-            free_cbk: None,
-            flags: 0,
+        let mut context = Context {
+            inner: ffi::Argon2_Context {
+                out: buffer.as_mut_ptr(),
+                outlen: buffer.len() as u32,
+                pwd,
+                pwdlen,
+                salt: self.salt.as_bytes().as_ptr() as *mut u8,
+                saltlen: self.salt.len() as u32,
+                secret,
+                secretlen,
+                ad,
+                adlen,
+                t_cost: self.config.iterations(),
+                m_cost: self.config.memory_size(),
+                lanes: self.config.lanes(),
+                threads: self.config.threads(),
+                version: self.config.version() as u32,
+                allocate_cbk,
+                free_cbk,
+                flags: 0,
+            },
+            cancellation: &self.cancellation,
+            cancelled: AtomicBool::new(false),
         };
-        let context_ptr = &mut context as *mut ffi::Argon2_Context;
+        // Derived from the whole `Context` so that `is_cancelled` may read its other fields
+        let context_ptr = &mut context as *mut Context as *mut ffi::Argon2_Context;
         let variant = self.config.variant() as ffi::argon2_type;
+        let previous_allocation = ALLOCATION.replace(allocation);
+        let address = context_ptr as usize;
+        CONTEXTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(address);
         let err = unsafe { ffi::argon2_ctx(context_ptr, variant) };
+        CONTEXTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|&context| context != address);
+        let allocation = ALLOCATION.replace(previous_allocation);
+        if let Some(error) = allocation.and_then(|allocation| allocation.error) {
+            return Err(error);
+        }
         check_error(err)?;
+        if context.cancelled.load(Ordering::Relaxed) {
+            // Segments were skipped, so the hash is garbage
+            return Err(self
+                .cancellation
+                .check()
+                .err()
+                .unwrap_or_else(|| Error::new(ErrorKind::Bug)));
+        }
         Ok(HashRaw {
             iterations: self.config.iterations(),
+            key_id: self.config.key_id().map(|key_id| key_id.to_vec()),
             lanes: self.config.lanes(),
             memory_size: self.config.memory_size(),
+            omit_version: false,
             raw_hash_bytes: buffer,
             raw_salt_bytes: self.salt.as_bytes().to_vec(),
             variant: self.config.variant(),
@@ -84,3 +232,45 @@ fn check_error(err: ffi::Argon2_ErrorCodes) -> Result<(), Error> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_foreign_context() {
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher.with_password("P@ssw0rd");
+        // Installs the cancel callback
+        let expected = hasher.hash_raw_c().unwrap();
+
+        // A plain `Argon2_Context`, like the ones of C code calling `argon2_ctx` directly
+        let config = &hasher.config;
+        let mut password = *b"P@ssw0rd";
+        let mut salt = [0u8; 8];
+        let mut out = [0u8; 32];
+        let mut context = ffi::Argon2_Context {
+            out: out.as_mut_ptr(),
+            outlen: out.len() as u32,
+            pwd: password.as_mut_ptr(),
+            pwdlen: password.len() as u32,
+            salt: salt.as_mut_ptr(),
+            saltlen: salt.len() as u32,
+            secret: ptr::null_mut(),
+            secretlen: 0,
+            ad: ptr::null_mut(),
+            adlen: 0,
+            t_cost: config.iterations(),
+            m_cost: config.memory_size(),
+            lanes: config.lanes(),
+            threads: config.threads(),
+            version: config.version() as u32,
+            allocate_cbk: None,
+            free_cbk: None,
+            flags: 0,
+        };
+        let err = unsafe { ffi::argon2_ctx(&mut context, config.variant() as ffi::argon2_type) };
+        check_error(err).unwrap();
+        assert_eq!(&out[..], expected.raw_hash_bytes());
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::OnceLock;
+
+use crate::{config::Implementation, ffi};
+
+static IMPLEMENTATION: OnceLock<Implementation> = OnceLock::new();
+
+/// Returns the `fill_segment` implementation used by the C backend, selecting it on first call.
+/// Must be called before any call into `argon2_ctx`, since the selection on the C side is a
+/// plain global that is only safe to write while no hashing is in progress
+pub(crate) fn implementation_c() -> Implementation {
+    *IMPLEMENTATION.get_or_init(|| {
+        let implementation = detect();
+        let ok = unsafe {
+            ffi::argonautica_select_fill_segment(implementation as ffi::argonautica_fill_segment_t)
+        };
+        if ok == 1 {
+            implementation
+        } else {
+            Implementation::Portable
+        }
+    })
+}
+
+/// Returns the fastest implementation that was compiled into the C library (see build.rs)
+/// and that the current CPU supports
+fn detect() -> Implementation {
+    #[cfg(all(
+        argonautica_c_avx512f,
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    {
+        if is_x86_feature_detected!("avx512f") {
+            return Implementation::Avx512F;
+        }
+    }
+    #[cfg(all(argonautica_c_avx2, any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return Implementation::Avx2;
+        }
+    }
+    #[cfg(all(argonautica_c_ssse3, any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if is_x86_feature_detected!("ssse3") {
+            return Implementation::Ssse3;
+        }
+    }
+    #[cfg(all(argonautica_c_sse2, any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if is_x86_feature_detected!("sse2") {
+            return Implementation::Sse2;
+        }
+    }
+    Implementation::Portable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_implementation_c() {
+        let implementation = implementation_c();
+        assert_eq!(implementation, detect());
+        assert_eq!(implementation, implementation_c());
+        #[cfg(all(argonautica_c_sse2, target_arch = "x86_64"))]
+        assert_ne!(implementation, Implementation::Portable);
+    }
+}
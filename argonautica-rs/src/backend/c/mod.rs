@@ -1,8 +1,10 @@
 mod decode;
 mod encode;
 mod hash_raw;
+mod implementation;
 
 #[cfg(test)]
 pub(crate) use self::decode::decode_c;
 #[cfg(test)]
 pub(crate) use self::encode::encode_c;
+pub(crate) use self::implementation::implementation_c;
@@ -1,6 +1,128 @@
+#[cfg(feature = "backend-c")]
 mod c;
 mod rust;
+#[cfg(feature = "backend-c")]
+mod shadow;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "backend-c"))]
 pub(crate) use self::c::encode_c;
-pub(crate) use self::rust::decode_rust;
+pub(crate) use self::rust::{decode_rust, decode_rust_lenient};
+
+#[cfg(feature = "backend-c")]
+use crate::config::LaneScheduling;
+use crate::config::{Backend, HasherConfig, Implementation};
+
+/// Returns the implementation of the compression function that `backend` uses on this machine
+pub(crate) fn implementation(backend: Backend) -> Implementation {
+    match resolve(backend) {
+        #[cfg(feature = "backend-c")]
+        Backend::C => self::c::implementation_c(),
+        #[cfg(not(feature = "backend-c"))]
+        Backend::C => Implementation::Portable,
+        Backend::Rust => self::rust::implementation_rust(),
+        Backend::Auto => unreachable!(),
+    }
+}
+
+/// Returns the SIMD level of `implementation`, higher meaning faster. Only implementations for
+/// the same architecture are ever compared, so NEON shares a level with SSE2. This is used
+/// instead of the derived `Ord` of `Implementation`, which only follows declaration order
+#[cfg(feature = "backend-c")]
+fn simd_level(implementation: Implementation) -> u8 {
+    match implementation {
+        Implementation::Portable => 0,
+        Implementation::Sse2 | Implementation::Neon => 1,
+        Implementation::Ssse3 => 2,
+        Implementation::Avx2 => 3,
+        Implementation::Avx512F => 4,
+    }
+}
+
+/// Returns the backend that will actually compute hashes for `backend`, i.e. `backend` itself
+/// unless it is `Backend::Auto`, in which case the C backend, or the Rust backend if its
+/// implementation has a higher [`simd_level`] on this machine
+pub(crate) fn resolve(backend: Backend) -> Backend {
+    match backend {
+        #[cfg(feature = "backend-c")]
+        Backend::Auto => {
+            let c = simd_level(self::c::implementation_c());
+            let rust = simd_level(self::rust::implementation_rust());
+            if rust > c { Backend::Rust } else { Backend::C }
+        }
+        #[cfg(not(feature = "backend-c"))]
+        Backend::Auto => Backend::Rust,
+        backend => backend,
+    }
+}
+
+/// Returns the backend that will actually compute hashes with `config`, i.e. [`resolve`] of its
+/// backend, except that the C backend leaves hashes whose lanes run on a thread pool (see
+/// [`LaneScheduling`](../config/enum.LaneScheduling.html)) to the Rust backend, since the C
+/// implementation spawns threads of its own for every slice
+pub(crate) fn resolve_config(config: &HasherConfig) -> Backend {
+    match resolve(config.backend()) {
+        #[cfg(feature = "backend-c")]
+        Backend::C
+            if config.lane_scheduling() != LaneScheduling::Threads
+                && config.threads().min(config.lanes()) > 1 =>
+        {
+            Backend::Rust
+        }
+        backend => backend,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(Backend::C), Backend::C);
+        assert_eq!(resolve(Backend::Rust), Backend::Rust);
+        let backend = resolve(Backend::Auto);
+        assert_ne!(backend, Backend::Auto);
+        assert_eq!(implementation(Backend::Auto), implementation(backend));
+        #[cfg(not(feature = "backend-c"))]
+        assert_eq!(backend, Backend::Rust);
+        #[cfg(feature = "backend-c")]
+        assert_eq!(
+            backend == Backend::Rust,
+            simd_level(implementation(Backend::Rust)) > simd_level(implementation(Backend::C))
+        );
+    }
+
+    #[cfg(feature = "backend-c")]
+    #[test]
+    fn test_simd_level() {
+        let x86 = [
+            Implementation::Portable,
+            Implementation::Sse2,
+            Implementation::Ssse3,
+            Implementation::Avx2,
+            Implementation::Avx512F,
+        ];
+        assert!(x86.windows(2).all(|w| simd_level(w[0]) < simd_level(w[1])));
+        assert!(simd_level(Implementation::Portable) < simd_level(Implementation::Neon));
+    }
+
+    #[test]
+    fn test_resolve_config() {
+        use crate::config::LaneScheduling;
+
+        let mut config = HasherConfig::default();
+        config.set_backend(Backend::C);
+        config.set_lanes(2);
+        config.set_threads(2);
+        assert_eq!(resolve_config(&config), Backend::C);
+        config.set_lane_scheduling(LaneScheduling::Pool);
+        #[cfg(feature = "backend-c")]
+        assert_eq!(resolve_config(&config), Backend::Rust);
+        assert_eq!(
+            config.implementation(),
+            implementation(resolve_config(&config))
+        );
+        config.set_threads(1);
+        assert_eq!(resolve_config(&config), Backend::C);
+    }
+}
@@ -0,0 +1,301 @@
+//! AVX2 implementation of the compression function `G`. Port of the AVX2 path of `opt.c` and
+//! `blamka-round-opt.h` in the C implementation. A block is processed as 32 256-bit vectors of
+//! four 64-bit words each
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::{Instance, Position, View, block::Block, sse::mm_shuffle};
+
+/// Number of 256-bit vectors in a block
+const HWORDS_IN_BLOCK: usize = 32;
+
+/// Fills a segment using AVX2 instructions
+///
+/// # Safety
+///
+/// The CPU must support AVX2
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn fill_segment_avx2(
+    instance: &Instance,
+    view: &View,
+    segment: &mut [Block],
+    position: Position,
+) {
+    instance.fill_segment_with(
+        view,
+        segment,
+        position,
+        |prev, reference, next, with_xor| unsafe { fill_block(prev, reference, next, with_xor) },
+    );
+}
+
+/// Computes `G(prev, reference)` into `next` (see [`block::fill_block`])
+#[inline(always)]
+unsafe fn fill_block(prev: &Block, reference: &Block, next: &mut Block, with_xor: bool) {
+    unsafe {
+        let prev_ptr = prev.as_ptr() as *const __m256i;
+        let reference_ptr = reference.as_ptr() as *const __m256i;
+        let next_ptr = next.as_mut_ptr() as *mut __m256i;
+
+        let mut state = [_mm256_setzero_si256(); HWORDS_IN_BLOCK];
+        let mut block_xy = [_mm256_setzero_si256(); HWORDS_IN_BLOCK];
+        for i in 0..HWORDS_IN_BLOCK {
+            state[i] = _mm256_xor_si256(
+                _mm256_loadu_si256(prev_ptr.add(i)),
+                _mm256_loadu_si256(reference_ptr.add(i)),
+            );
+            block_xy[i] = if with_xor {
+                _mm256_xor_si256(state[i], _mm256_loadu_si256(next_ptr.add(i)))
+            } else {
+                state[i]
+            };
+        }
+
+        for i in 0..4 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round_1([
+                state[8 * i],
+                state[8 * i + 4],
+                state[8 * i + 1],
+                state[8 * i + 5],
+                state[8 * i + 2],
+                state[8 * i + 6],
+                state[8 * i + 3],
+                state[8 * i + 7],
+            ]);
+            state[8 * i] = a0;
+            state[8 * i + 4] = a1;
+            state[8 * i + 1] = b0;
+            state[8 * i + 5] = b1;
+            state[8 * i + 2] = c0;
+            state[8 * i + 6] = c1;
+            state[8 * i + 3] = d0;
+            state[8 * i + 7] = d1;
+        }
+
+        for i in 0..4 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round_2([
+                state[i],
+                state[4 + i],
+                state[8 + i],
+                state[12 + i],
+                state[16 + i],
+                state[20 + i],
+                state[24 + i],
+                state[28 + i],
+            ]);
+            state[i] = a0;
+            state[4 + i] = a1;
+            state[8 + i] = b0;
+            state[12 + i] = b1;
+            state[16 + i] = c0;
+            state[20 + i] = c1;
+            state[24 + i] = d0;
+            state[28 + i] = d1;
+        }
+
+        for i in 0..HWORDS_IN_BLOCK {
+            _mm256_storeu_si256(next_ptr.add(i), _mm256_xor_si256(state[i], block_xy[i]));
+        }
+    }
+}
+
+/// Equivalent of `BLAKE2_ROUND_1` in `blamka-round-opt.h`, which applies BlaMka to two
+/// columns of sixteen 64-bit words at once
+#[inline(always)]
+unsafe fn blake2_round_1(v: [__m256i; 8]) -> [__m256i; 8] {
+    unsafe {
+        let [
+            mut a0,
+            mut a1,
+            mut b0,
+            mut b1,
+            mut c0,
+            mut c1,
+            mut d0,
+            mut d1,
+        ] = v;
+
+        g1(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+        g2(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+
+        b0 = _mm256_permute4x64_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(b0);
+        c0 = _mm256_permute4x64_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(c0);
+        d0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(d0);
+        b1 = _mm256_permute4x64_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(b1);
+        c1 = _mm256_permute4x64_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(c1);
+        d1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(d1);
+
+        g1(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+        g2(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+
+        b0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(b0);
+        c0 = _mm256_permute4x64_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(c0);
+        d0 = _mm256_permute4x64_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(d0);
+        b1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(b1);
+        c1 = _mm256_permute4x64_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(c1);
+        d1 = _mm256_permute4x64_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(d1);
+
+        [a0, a1, b0, b1, c0, c1, d0, d1]
+    }
+}
+
+/// Equivalent of `BLAKE2_ROUND_2` in `blamka-round-opt.h`, which applies BlaMka to two
+/// rows of sixteen 64-bit words at once
+#[inline(always)]
+unsafe fn blake2_round_2(v: [__m256i; 8]) -> [__m256i; 8] {
+    unsafe {
+        let [
+            mut a0,
+            mut a1,
+            mut b0,
+            mut b1,
+            mut c0,
+            mut c1,
+            mut d0,
+            mut d1,
+        ] = v;
+
+        g1(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+        g2(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+
+        let tmp1 = _mm256_blend_epi32::<0xCC>(b0, b1);
+        let tmp2 = _mm256_blend_epi32::<0x33>(b0, b1);
+        b1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp1);
+        b0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp2);
+        ::core::mem::swap(&mut c0, &mut c1);
+        let tmp1 = _mm256_blend_epi32::<0xCC>(d0, d1);
+        let tmp2 = _mm256_blend_epi32::<0x33>(d0, d1);
+        d0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp1);
+        d1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp2);
+
+        g1(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+        g2(
+            &mut a0, &mut a1, &mut b0, &mut b1, &mut c0, &mut c1, &mut d0, &mut d1,
+        );
+
+        let tmp1 = _mm256_blend_epi32::<0xCC>(b0, b1);
+        let tmp2 = _mm256_blend_epi32::<0x33>(b0, b1);
+        b0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp1);
+        b1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp2);
+        ::core::mem::swap(&mut c0, &mut c1);
+        let tmp1 = _mm256_blend_epi32::<0x33>(d0, d1);
+        let tmp2 = _mm256_blend_epi32::<0xCC>(d0, d1);
+        d0 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp1);
+        d1 = _mm256_permute4x64_epi64::<{ mm_shuffle(2, 3, 0, 1) }>(tmp2);
+
+        [a0, a1, b0, b1, c0, c1, d0, d1]
+    }
+}
+
+#[inline(always)]
+unsafe fn blamka(x: __m256i, y: __m256i) -> __m256i {
+    unsafe {
+        let ml = _mm256_mul_epu32(x, y);
+        _mm256_add_epi64(x, _mm256_add_epi64(y, _mm256_add_epi64(ml, ml)))
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: __m256i) -> __m256i {
+    unsafe { _mm256_shuffle_epi32::<{ mm_shuffle(2, 3, 0, 1) }>(x) }
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: __m256i) -> __m256i {
+    unsafe {
+        _mm256_shuffle_epi8(
+            x,
+            _mm256_setr_epi8(
+                3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10, 3, 4, 5, 6, 7, 0, 1, 2, 11,
+                12, 13, 14, 15, 8, 9, 10,
+            ),
+        )
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: __m256i) -> __m256i {
+    unsafe {
+        _mm256_shuffle_epi8(
+            x,
+            _mm256_setr_epi8(
+                2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9, 2, 3, 4, 5, 6, 7, 0, 1, 10,
+                11, 12, 13, 14, 15, 8, 9,
+            ),
+        )
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: __m256i) -> __m256i {
+    unsafe { _mm256_xor_si256(_mm256_srli_epi64::<63>(x), _mm256_add_epi64(x, x)) }
+}
+
+/// Equivalent of `G1_AVX2` in `blamka-round-opt.h`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g1(
+    a0: &mut __m256i,
+    a1: &mut __m256i,
+    b0: &mut __m256i,
+    b1: &mut __m256i,
+    c0: &mut __m256i,
+    c1: &mut __m256i,
+    d0: &mut __m256i,
+    d1: &mut __m256i,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *d0 = rotr32(_mm256_xor_si256(*d0, *a0));
+        *c0 = blamka(*c0, *d0);
+        *b0 = rotr24(_mm256_xor_si256(*b0, *c0));
+
+        *a1 = blamka(*a1, *b1);
+        *d1 = rotr32(_mm256_xor_si256(*d1, *a1));
+        *c1 = blamka(*c1, *d1);
+        *b1 = rotr24(_mm256_xor_si256(*b1, *c1));
+    }
+}
+
+/// Equivalent of `G2_AVX2` in `blamka-round-opt.h`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g2(
+    a0: &mut __m256i,
+    a1: &mut __m256i,
+    b0: &mut __m256i,
+    b1: &mut __m256i,
+    c0: &mut __m256i,
+    c1: &mut __m256i,
+    d0: &mut __m256i,
+    d1: &mut __m256i,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *d0 = rotr16(_mm256_xor_si256(*d0, *a0));
+        *c0 = blamka(*c0, *d0);
+        *b0 = rotr63(_mm256_xor_si256(*b0, *c0));
+
+        *a1 = blamka(*a1, *b1);
+        *d1 = rotr16(_mm256_xor_si256(*d1, *a1));
+        *c1 = blamka(*c1, *d1);
+        *b1 = rotr63(_mm256_xor_si256(*b1, *c1));
+    }
+}
@@ -0,0 +1,247 @@
+//! AVX-512F implementation of the compression function `G`. Port of the AVX-512F path of
+//! `opt.c` and `blamka-round-opt.h` in the C implementation. A block is processed as 16
+//! 512-bit vectors of eight 64-bit words each
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::{Instance, Position, View, block::Block, sse::mm_shuffle};
+
+/// Number of 512-bit vectors in a block
+const WORDS_512_IN_BLOCK: usize = 16;
+
+/// Fills a segment using AVX-512F instructions
+///
+/// # Safety
+///
+/// The CPU must support AVX-512F
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn fill_segment_avx512f(
+    instance: &Instance,
+    view: &View,
+    segment: &mut [Block],
+    position: Position,
+) {
+    instance.fill_segment_with(
+        view,
+        segment,
+        position,
+        |prev, reference, next, with_xor| unsafe { fill_block(prev, reference, next, with_xor) },
+    );
+}
+
+/// Computes `G(prev, reference)` into `next` (see [`block::fill_block`])
+#[inline(always)]
+unsafe fn fill_block(prev: &Block, reference: &Block, next: &mut Block, with_xor: bool) {
+    unsafe {
+        let prev_ptr = prev.as_ptr() as *const __m512i;
+        let reference_ptr = reference.as_ptr() as *const __m512i;
+        let next_ptr = next.as_mut_ptr() as *mut __m512i;
+
+        let mut state = [_mm512_setzero_si512(); WORDS_512_IN_BLOCK];
+        let mut block_xy = [_mm512_setzero_si512(); WORDS_512_IN_BLOCK];
+        for i in 0..WORDS_512_IN_BLOCK {
+            state[i] = _mm512_xor_si512(
+                _mm512_loadu_si512(prev_ptr.add(i)),
+                _mm512_loadu_si512(reference_ptr.add(i)),
+            );
+            block_xy[i] = if with_xor {
+                _mm512_xor_si512(state[i], _mm512_loadu_si512(next_ptr.add(i)))
+            } else {
+                state[i]
+            };
+        }
+
+        for i in 0..2 {
+            // Argument order of `BLAKE2_ROUND_1` is (A0, C0, B0, D0, A1, C1, B1, D1)
+            let [
+                mut a0,
+                mut c0,
+                mut b0,
+                mut d0,
+                mut a1,
+                mut c1,
+                mut b1,
+                mut d1,
+            ] = [
+                state[8 * i],
+                state[8 * i + 1],
+                state[8 * i + 2],
+                state[8 * i + 3],
+                state[8 * i + 4],
+                state[8 * i + 5],
+                state[8 * i + 6],
+                state[8 * i + 7],
+            ];
+            swap_halves(&mut a0, &mut b0);
+            swap_halves(&mut c0, &mut d0);
+            swap_halves(&mut a1, &mut b1);
+            swap_halves(&mut c1, &mut d1);
+            blake2_round(
+                &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+            );
+            swap_halves(&mut a0, &mut b0);
+            swap_halves(&mut c0, &mut d0);
+            swap_halves(&mut a1, &mut b1);
+            swap_halves(&mut c1, &mut d1);
+            state[8 * i] = a0;
+            state[8 * i + 1] = c0;
+            state[8 * i + 2] = b0;
+            state[8 * i + 3] = d0;
+            state[8 * i + 4] = a1;
+            state[8 * i + 5] = c1;
+            state[8 * i + 6] = b1;
+            state[8 * i + 7] = d1;
+        }
+
+        for i in 0..2 {
+            let [
+                mut a0,
+                mut a1,
+                mut b0,
+                mut b1,
+                mut c0,
+                mut c1,
+                mut d0,
+                mut d1,
+            ] = [
+                state[i],
+                state[2 + i],
+                state[4 + i],
+                state[6 + i],
+                state[8 + i],
+                state[10 + i],
+                state[12 + i],
+                state[14 + i],
+            ];
+            swap_quarters(&mut a0, &mut a1);
+            swap_quarters(&mut b0, &mut b1);
+            swap_quarters(&mut c0, &mut c1);
+            swap_quarters(&mut d0, &mut d1);
+            blake2_round(
+                &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+            );
+            unswap_quarters(&mut a0, &mut a1);
+            unswap_quarters(&mut b0, &mut b1);
+            unswap_quarters(&mut c0, &mut c1);
+            unswap_quarters(&mut d0, &mut d1);
+            state[i] = a0;
+            state[2 + i] = a1;
+            state[4 + i] = b0;
+            state[6 + i] = b1;
+            state[8 + i] = c0;
+            state[10 + i] = c1;
+            state[12 + i] = d0;
+            state[14 + i] = d1;
+        }
+
+        for i in 0..WORDS_512_IN_BLOCK {
+            _mm512_storeu_si512(next_ptr.add(i), _mm512_xor_si512(state[i], block_xy[i]));
+        }
+    }
+}
+
+/// Equivalent of `BLAKE2_ROUND` in the AVX-512F part of `blamka-round-opt.h`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn blake2_round(
+    a0: &mut __m512i,
+    b0: &mut __m512i,
+    c0: &mut __m512i,
+    d0: &mut __m512i,
+    a1: &mut __m512i,
+    b1: &mut __m512i,
+    c1: &mut __m512i,
+    d1: &mut __m512i,
+) {
+    unsafe {
+        g::<32, 24>(a0, b0, c0, d0, a1, b1, c1, d1);
+        g::<16, 63>(a0, b0, c0, d0, a1, b1, c1, d1);
+
+        *b0 = _mm512_permutex_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(*b0);
+        *b1 = _mm512_permutex_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(*b1);
+        *c0 = _mm512_permutex_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(*c0);
+        *c1 = _mm512_permutex_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(*c1);
+        *d0 = _mm512_permutex_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(*d0);
+        *d1 = _mm512_permutex_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(*d1);
+
+        g::<32, 24>(a0, b0, c0, d0, a1, b1, c1, d1);
+        g::<16, 63>(a0, b0, c0, d0, a1, b1, c1, d1);
+
+        *b0 = _mm512_permutex_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(*b0);
+        *b1 = _mm512_permutex_epi64::<{ mm_shuffle(2, 1, 0, 3) }>(*b1);
+        *c0 = _mm512_permutex_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(*c0);
+        *c1 = _mm512_permutex_epi64::<{ mm_shuffle(1, 0, 3, 2) }>(*c1);
+        *d0 = _mm512_permutex_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(*d0);
+        *d1 = _mm512_permutex_epi64::<{ mm_shuffle(0, 3, 2, 1) }>(*d1);
+    }
+}
+
+/// Half of a BlaMka quarter-round: `G1` in `blamka-round-opt.h` is `g::<32, 24>` and `G2`
+/// is `g::<16, 63>`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g<const R1: i32, const R2: i32>(
+    a0: &mut __m512i,
+    b0: &mut __m512i,
+    c0: &mut __m512i,
+    d0: &mut __m512i,
+    a1: &mut __m512i,
+    b1: &mut __m512i,
+    c1: &mut __m512i,
+    d1: &mut __m512i,
+) {
+    unsafe {
+        *a0 = muladd(*a0, *b0);
+        *a1 = muladd(*a1, *b1);
+        *d0 = _mm512_ror_epi64::<R1>(_mm512_xor_si512(*d0, *a0));
+        *d1 = _mm512_ror_epi64::<R1>(_mm512_xor_si512(*d1, *a1));
+        *c0 = muladd(*c0, *d0);
+        *c1 = muladd(*c1, *d1);
+        *b0 = _mm512_ror_epi64::<R2>(_mm512_xor_si512(*b0, *c0));
+        *b1 = _mm512_ror_epi64::<R2>(_mm512_xor_si512(*b1, *c1));
+    }
+}
+
+#[inline(always)]
+unsafe fn muladd(x: __m512i, y: __m512i) -> __m512i {
+    unsafe {
+        let z = _mm512_mul_epu32(x, y);
+        _mm512_add_epi64(_mm512_add_epi64(x, y), _mm512_add_epi64(z, z))
+    }
+}
+
+/// Equivalent of `SWAP_HALVES` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn swap_halves(a0: &mut __m512i, a1: &mut __m512i) {
+    unsafe {
+        let t0 = _mm512_shuffle_i64x2::<{ mm_shuffle(1, 0, 1, 0) }>(*a0, *a1);
+        let t1 = _mm512_shuffle_i64x2::<{ mm_shuffle(3, 2, 3, 2) }>(*a0, *a1);
+        *a0 = t0;
+        *a1 = t1;
+    }
+}
+
+/// Equivalent of `SWAP_QUARTERS` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn swap_quarters(a0: &mut __m512i, a1: &mut __m512i) {
+    unsafe {
+        swap_halves(a0, a1);
+        let index = _mm512_setr_epi64(0, 1, 4, 5, 2, 3, 6, 7);
+        *a0 = _mm512_permutexvar_epi64(index, *a0);
+        *a1 = _mm512_permutexvar_epi64(index, *a1);
+    }
+}
+
+/// Equivalent of `UNSWAP_QUARTERS` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn unswap_quarters(a0: &mut __m512i, a1: &mut __m512i) {
+    unsafe {
+        let index = _mm512_setr_epi64(0, 1, 4, 5, 2, 3, 6, 7);
+        *a0 = _mm512_permutexvar_epi64(index, *a0);
+        *a1 = _mm512_permutexvar_epi64(index, *a1);
+        swap_halves(a0, a1);
+    }
+}
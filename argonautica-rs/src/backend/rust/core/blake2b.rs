@@ -0,0 +1,44 @@
+use blake2_rfc::blake2b::{Blake2b, blake2b};
+use zeroize::Zeroize;
+
+/// Output length of a single Blake2b invocation (in bytes)
+const BLAKE2B_OUTBYTES: usize = 64;
+
+/// The variable-length hash function `H'` from the Argon2 specification. Writes `out.len()`
+/// bytes of output derived from the concatenation of `inputs`. Equivalent of `blake2b_long`
+/// in the C implementation
+pub(crate) fn blake2b_long(out: &mut [u8], inputs: &[&[u8]]) {
+    let outlen_bytes = (out.len() as u32).to_le_bytes();
+    if out.len() <= BLAKE2B_OUTBYTES {
+        let mut state = Blake2b::new(out.len());
+        state.update(&outlen_bytes);
+        for input in inputs {
+            state.update(input);
+        }
+        out.copy_from_slice(state.finalize().as_bytes());
+        return;
+    }
+
+    let mut state = Blake2b::new(BLAKE2B_OUTBYTES);
+    state.update(&outlen_bytes);
+    for input in inputs {
+        state.update(input);
+    }
+    let mut buffer = [0u8; BLAKE2B_OUTBYTES];
+    buffer.copy_from_slice(state.finalize().as_bytes());
+
+    let half = BLAKE2B_OUTBYTES / 2;
+    out[..half].copy_from_slice(&buffer[..half]);
+    let mut offset = half;
+    let mut to_produce = out.len() - half;
+    while to_produce > BLAKE2B_OUTBYTES {
+        let next = blake2b(BLAKE2B_OUTBYTES, &[], &buffer);
+        buffer.copy_from_slice(next.as_bytes());
+        out[offset..offset + half].copy_from_slice(&buffer[..half]);
+        offset += half;
+        to_produce -= half;
+    }
+    let last = blake2b(to_produce, &[], &buffer);
+    out[offset..].copy_from_slice(last.as_bytes());
+    buffer.zeroize();
+}
@@ -0,0 +1,180 @@
+use core::ops::{BitXorAssign, Index, IndexMut};
+
+use zeroize::DefaultIsZeroes;
+
+/// Size of a single Argon2 memory block (in bytes)
+pub(crate) const BLOCK_SIZE: usize = 1024;
+
+/// Number of 64-bit words in a single Argon2 memory block
+pub(crate) const QWORDS_IN_BLOCK: usize = BLOCK_SIZE / 8;
+
+/// A single 1 KiB Argon2 memory block, viewed as 128 little-endian 64-bit words
+#[derive(Clone, Copy)]
+#[repr(align(64))]
+pub(crate) struct Block([u64; QWORDS_IN_BLOCK]);
+
+impl Default for Block {
+    fn default() -> Block {
+        Block::ZERO
+    }
+}
+
+impl DefaultIsZeroes for Block {}
+
+impl Index<usize> for Block {
+    type Output = u64;
+    fn index(&self, index: usize) -> &u64 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Block {
+    fn index_mut(&mut self, index: usize) -> &mut u64 {
+        &mut self.0[index]
+    }
+}
+
+impl BitXorAssign<&Block> for Block {
+    fn bitxor_assign(&mut self, rhs: &Block) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *a ^= *b;
+        }
+    }
+}
+
+impl Block {
+    pub(crate) const ZERO: Block = Block([0u64; QWORDS_IN_BLOCK]);
+
+    /// Equivalent of `load_block` in the C implementation
+    pub(crate) fn from_bytes(bytes: &[u8; BLOCK_SIZE]) -> Block {
+        let mut block = Block::ZERO;
+        for (word, chunk) in block.0.iter_mut().zip(bytes.chunks_exact(8)) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(buf);
+        }
+        block
+    }
+
+    /// Pointer to the first 64-bit word of the block, for use by the SIMD implementations
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(crate) fn as_ptr(&self) -> *const u64 {
+        self.0.as_ptr()
+    }
+
+    /// Mutable pointer to the first 64-bit word of the block, for use by the SIMD
+    /// implementations
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u64 {
+        self.0.as_mut_ptr()
+    }
+
+    /// Equivalent of `store_block` in the C implementation
+    pub(crate) fn to_bytes(self) -> [u8; BLOCK_SIZE] {
+        let mut bytes = [0u8; BLOCK_SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// The Argon2 compression function `G`. Computes `G(prev, reference)` and either overwrites
+/// `next` with the result or, if `with_xor` is `true` (version 0x13 after the first pass),
+/// XORs the result into `next`. Portable equivalent of `fill_block` in the C implementation
+#[inline(always)]
+pub(crate) fn fill_block(prev: &Block, reference: &Block, next: &mut Block, with_xor: bool) {
+    let mut r = *reference;
+    r ^= prev;
+    let mut tmp = r;
+    if with_xor {
+        tmp ^= next;
+    }
+
+    // Apply BlaMka on columns of 64-bit words: (0..16), then (16..32), ... finally (112..128)
+    for i in 0..8 {
+        let b = 16 * i;
+        round(
+            &mut r.0,
+            [
+                b,
+                b + 1,
+                b + 2,
+                b + 3,
+                b + 4,
+                b + 5,
+                b + 6,
+                b + 7,
+                b + 8,
+                b + 9,
+                b + 10,
+                b + 11,
+                b + 12,
+                b + 13,
+                b + 14,
+                b + 15,
+            ],
+        );
+    }
+
+    // Apply BlaMka on rows of 64-bit words: (0,1,16,17,...112,113), then
+    // (2,3,18,19,...,114,115), ... finally (14,15,30,31,...,126,127)
+    for i in 0..8 {
+        let b = 2 * i;
+        round(
+            &mut r.0,
+            [
+                b,
+                b + 1,
+                b + 16,
+                b + 17,
+                b + 32,
+                b + 33,
+                b + 48,
+                b + 49,
+                b + 64,
+                b + 65,
+                b + 80,
+                b + 81,
+                b + 96,
+                b + 97,
+                b + 112,
+                b + 113,
+            ],
+        );
+    }
+
+    *next = tmp;
+    *next ^= &r;
+}
+
+#[inline(always)]
+fn blamka(x: u64, y: u64) -> u64 {
+    let xy = (x & 0xffff_ffff).wrapping_mul(y & 0xffff_ffff);
+    x.wrapping_add(y).wrapping_add(xy.wrapping_mul(2))
+}
+
+#[inline(always)]
+fn g(v: &mut [u64; QWORDS_IN_BLOCK], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = blamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = blamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = blamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = blamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Equivalent of `BLAKE2_ROUND_NOMSG` in the C implementation
+#[inline(always)]
+fn round(v: &mut [u64; QWORDS_IN_BLOCK], i: [usize; 16]) {
+    g(v, i[0], i[4], i[8], i[12]);
+    g(v, i[1], i[5], i[9], i[13]);
+    g(v, i[2], i[6], i[10], i[14]);
+    g(v, i[3], i[7], i[11], i[15]);
+    g(v, i[0], i[5], i[10], i[15]);
+    g(v, i[1], i[6], i[11], i[12]);
+    g(v, i[2], i[7], i[8], i[13]);
+    g(v, i[3], i[4], i[9], i[14]);
+}
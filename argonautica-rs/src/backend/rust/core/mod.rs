@@ -1,8 +1,825 @@
-#![allow(dead_code)]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod avx2;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod avx512f;
+mod blake2b;
+mod block;
+#[cfg(target_arch = "aarch64")]
+mod neon;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod sse;
 
-use Error;
-use Hasher;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::{
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
 
-fn h0(_hasher: &mut Hasher) -> Result<[u8; 72], Error> {
-    unimplemented!();
+use blake2_rfc::blake2b::Blake2b;
+use zeroize::{Zeroize, Zeroizing};
+
+use self::blake2b::blake2b_long;
+use self::block::{BLOCK_SIZE, Block, QWORDS_IN_BLOCK, fill_block};
+use crate::{
+    Error, ErrorKind, Hasher,
+    config::{Implementation, Variant, Version},
+    input::SecretKey,
+};
+#[cfg(feature = "std")]
+use crate::{
+    allocator::SharedAllocator, cancellation::Cancellation, config::LaneScheduling,
+    executor::SharedExecutor, pool, secure_memory,
+};
+
+/// Number of slices (synchronization points) per pass
+const SYNC_POINTS: u32 = 4;
+
+/// Number of pseudo-random addresses produced by a single address block
+const ADDRESSES_IN_BLOCK: u32 = QWORDS_IN_BLOCK as u32;
+
+/// Length of the Blake2b digest `H0` (in bytes)
+const PREHASH_DIGEST_LENGTH: usize = 64;
+
+/// Length of `H0` plus room for the block index and lane index used to derive the
+/// first two blocks of each lane (in bytes)
+const PREHASH_SEED_LENGTH: usize = 72;
+
+/// Whether the current CPU supports an x86 instruction set. Without the `std` feature there
+/// is no runtime detection, so this falls back to the target features enabled at compile time
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+macro_rules! x86_feature_detected {
+    ($feature:tt) => {
+        is_x86_feature_detected!($feature)
+    };
+}
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "std")))]
+macro_rules! x86_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+/// Same as `x86_feature_detected` for aarch64
+#[cfg(all(target_arch = "aarch64", feature = "std"))]
+macro_rules! aarch64_feature_detected {
+    ($feature:tt) => {
+        ::std::arch::is_aarch64_feature_detected!($feature)
+    };
+}
+#[cfg(all(target_arch = "aarch64", not(feature = "std")))]
+macro_rules! aarch64_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+#[cfg(feature = "std")]
+static IMPLEMENTATION: OnceLock<Implementation> = OnceLock::new();
+
+/// Returns the implementation of the compression function used by the Rust backend, i.e. the
+/// fastest one the current CPU supports. Detection only runs on the first call
+#[cfg(feature = "std")]
+pub(crate) fn implementation_rust() -> Implementation {
+    *IMPLEMENTATION.get_or_init(detect)
+}
+
+/// Returns the implementation of the compression function used by the Rust backend, i.e. the
+/// fastest one enabled at compile time (e.g. with `-C target-feature=+avx2`)
+#[cfg(not(feature = "std"))]
+pub(crate) fn implementation_rust() -> Implementation {
+    detect()
+}
+
+fn detect() -> Implementation {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if x86_feature_detected!("avx512f") {
+            return Implementation::Avx512F;
+        }
+        if x86_feature_detected!("avx2") {
+            return Implementation::Avx2;
+        }
+        if x86_feature_detected!("ssse3") {
+            return Implementation::Ssse3;
+        }
+        if x86_feature_detected!("sse2") {
+            return Implementation::Sse2;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if aarch64_feature_detected!("neon") {
+            return Implementation::Neon;
+        }
+    }
+    Implementation::Portable
+}
+
+/// Computes the Argon2 hash of the password, salt, secret key, and additional data held by
+/// `hasher` using `hasher`'s configuration, writing the raw hash bytes to `out`
+pub(crate) fn hash(hasher: &Hasher, out: &mut [u8]) -> Result<(), Error> {
+    hash_with(hasher, out, implementation_rust())
+}
+
+/// Same as [`hash`] but with an explicit implementation of the compression function, which the
+/// current CPU must support
+fn hash_with(hasher: &Hasher, out: &mut [u8], implementation: Implementation) -> Result<(), Error> {
+    let mut seed = Zeroizing::new(h0(hasher)?);
+    let instance = Instance::new(hasher, implementation);
+    let mut memory = instance.allocate()?;
+    instance.fill_first_blocks(&mut memory, &mut seed);
+    instance.fill_memory_blocks(&mut memory)?;
+    instance.finalize(&memory, out);
+    Ok(())
+}
+
+/// Computes the initial 64-byte digest `H0` from all the inputs and parameters, leaving
+/// the trailing 8 bytes of the returned seed zeroed. Equivalent of `initial_hash` in the
+/// C implementation
+fn h0(hasher: &Hasher) -> Result<[u8; PREHASH_SEED_LENGTH], Error> {
+    let password = match hasher.password {
+        Some(ref password) => password.as_bytes(),
+        None => return Err(Error::new(ErrorKind::PasswordMissingError)),
+    };
+    let secret_key = hasher
+        .secret_key
+        .as_ref()
+        .map(SecretKey::try_as_bytes)
+        .transpose()?;
+    let secret_key = secret_key.as_deref().unwrap_or(&[]);
+    let additional_data = match hasher.additional_data {
+        Some(ref additional_data) => additional_data.as_bytes(),
+        None => &[],
+    };
+    let salt = hasher.salt.as_bytes();
+    let config = &hasher.config;
+
+    let mut state = Blake2b::new(PREHASH_DIGEST_LENGTH);
+    state.update(&config.lanes().to_le_bytes());
+    state.update(&config.hash_len().to_le_bytes());
+    state.update(&config.memory_size().to_le_bytes());
+    state.update(&config.iterations().to_le_bytes());
+    state.update(&(config.version() as u32).to_le_bytes());
+    state.update(&(config.variant() as u32).to_le_bytes());
+    state.update(&(password.len() as u32).to_le_bytes());
+    state.update(password);
+    state.update(&(salt.len() as u32).to_le_bytes());
+    state.update(salt);
+    state.update(&(secret_key.len() as u32).to_le_bytes());
+    state.update(secret_key);
+    state.update(&(additional_data.len() as u32).to_le_bytes());
+    state.update(additional_data);
+
+    let mut seed = [0u8; PREHASH_SEED_LENGTH];
+    seed[..PREHASH_DIGEST_LENGTH].copy_from_slice(state.finalize().as_bytes());
+    Ok(seed)
+}
+
+/// The thread pool that runs the lanes of a hash with
+/// [`LaneScheduling::Pool`](../../../config/enum.LaneScheduling.html#variant.Pool) or
+/// [`LaneScheduling::Adaptive`](../../../config/enum.LaneScheduling.html#variant.Adaptive)
+#[cfg(feature = "std")]
+struct LanePool {
+    adaptive: bool,
+    executor: SharedExecutor,
+    /// Number of tasks to spawn on `executor` for the next slice, in addition to the hashing
+    /// thread
+    helpers: AtomicUsize,
+    max_helpers: usize,
+}
+
+#[cfg(feature = "std")]
+impl LanePool {
+    /// Returns the pool for `hasher`, i.e. its executor, its thread pool or the global thread
+    /// pool, or `None` if its lanes run on threads of their own
+    fn new(hasher: &Hasher, threads: u32) -> Option<LanePool> {
+        let config = &hasher.config;
+        let adaptive = match config.lane_scheduling() {
+            LaneScheduling::Threads => return None,
+            LaneScheduling::Pool => false,
+            LaneScheduling::Adaptive => true,
+        };
+        if threads <= 1 {
+            return None;
+        }
+        let executor = match (config.executor(), config.thread_pool()) {
+            (Some(executor), _) => executor.clone(),
+            (None, Some(thread_pool)) => SharedExecutor::new(thread_pool),
+            (None, None) => SharedExecutor::new(pool::thread_pool()),
+        };
+        let max_helpers = threads as usize - 1;
+        Some(LanePool {
+            adaptive,
+            executor,
+            helpers: AtomicUsize::new(max_helpers),
+            max_helpers,
+        })
+    }
+}
+
+/// The block matrix of a hash
+enum Memory {
+    Vec(Vec<Block>),
+    /// Allocated by the [`MemoryAllocator`](../../../allocator/trait.MemoryAllocator.html)
+    /// configured on the [`Hasher`], which guarantees that it is aligned and initialized, or
+    /// by the default allocator of secure-memory mode. Locked if `secure`
+    #[cfg(feature = "std")]
+    Allocated {
+        allocator: SharedAllocator,
+        ptr: NonNull<Block>,
+        len: usize,
+        secure: bool,
+    },
+}
+
+impl Deref for Memory {
+    type Target = [Block];
+    fn deref(&self) -> &[Block] {
+        match *self {
+            Memory::Vec(ref memory) => memory,
+            #[cfg(feature = "std")]
+            Memory::Allocated { ptr, len, .. } => unsafe {
+                ::core::slice::from_raw_parts(ptr.as_ptr(), len)
+            },
+        }
+    }
+}
+
+impl DerefMut for Memory {
+    fn deref_mut(&mut self) -> &mut [Block] {
+        match *self {
+            Memory::Vec(ref mut memory) => memory,
+            #[cfg(feature = "std")]
+            Memory::Allocated { ptr, len, .. } => unsafe {
+                ::core::slice::from_raw_parts_mut(ptr.as_ptr(), len)
+            },
+        }
+    }
+}
+
+impl Drop for Memory {
+    fn drop(&mut self) {
+        match *self {
+            Memory::Vec(ref mut memory) => memory.zeroize(),
+            #[cfg(feature = "std")]
+            Memory::Allocated {
+                ref allocator,
+                ptr,
+                len,
+                secure,
+            } => {
+                // The allocator may hand the memory to another hash, so wipe it first like the
+                // C implementation does
+                let size = len * BLOCK_SIZE;
+                unsafe {
+                    if secure {
+                        secure_memory::unlock(ptr.cast(), size);
+                    } else {
+                        secure_memory::wipe(ptr.cast(), size);
+                    }
+                    allocator.deallocate(ptr.cast(), size);
+                }
+            }
+        }
+    }
+}
+
+/// Position of the segment currently being filled
+#[derive(Copy, Clone, Debug)]
+struct Position {
+    pass: u32,
+    lane: u32,
+    slice: u32,
+}
+
+/// Derived parameters of a single Argon2 computation.
+///
+/// The block matrix handed around by `Instance` is stored <i>slice-major</i> (the segments of
+/// every lane for slice 0, then the segments of every lane for slice 1, and so on) instead of
+/// lane-major. During a slice each lane only writes to its own segment of that slice and only
+/// reads from other slices (or from the already-filled part of its own segment), so storing
+/// slices contiguously lets each lane borrow its segment mutably while the rest of the matrix is
+/// shared read-only across threads, without any `unsafe`
+struct Instance {
+    #[cfg(feature = "std")]
+    allocator: Option<SharedAllocator>,
+    #[cfg(feature = "std")]
+    cancellation: Cancellation,
+    implementation: Implementation,
+    /// Where to run the lanes of each slice, unless they run on threads of their own
+    #[cfg(feature = "std")]
+    lane_pool: Option<LanePool>,
+    lane_length: u32,
+    lanes: u32,
+    memory_blocks: u32,
+    passes: u32,
+    /// Whether to lock the block matrix (see
+    /// [`Hasher::configure_secure_memory`](../../../struct.Hasher.html#method.configure_secure_memory))
+    #[cfg(feature = "std")]
+    secure_memory: bool,
+    segment_length: u32,
+    threads: u32,
+    variant: Variant,
+    version: Version,
+}
+
+impl Instance {
+    fn new(hasher: &Hasher, implementation: Implementation) -> Instance {
+        let config = &hasher.config;
+        let lanes = config.lanes();
+        let mut memory_blocks = config.memory_size();
+        if memory_blocks < 2 * SYNC_POINTS * lanes {
+            memory_blocks = 2 * SYNC_POINTS * lanes;
+        }
+        let segment_length = memory_blocks / (lanes * SYNC_POINTS);
+        let threads = if cfg!(feature = "std") {
+            ::core::cmp::min(config.threads(), lanes)
+        } else {
+            1
+        };
+        Instance {
+            #[cfg(feature = "std")]
+            allocator: match config.allocator() {
+                Some(allocator) => Some(allocator.clone()),
+                None if config.secure_memory() => Some(secure_memory::default_allocator()),
+                None => None,
+            },
+            #[cfg(feature = "std")]
+            cancellation: hasher.cancellation.clone(),
+            implementation,
+            #[cfg(feature = "std")]
+            lane_pool: LanePool::new(hasher, threads),
+            lane_length: segment_length * SYNC_POINTS,
+            lanes,
+            memory_blocks: segment_length * lanes * SYNC_POINTS,
+            passes: config.iterations(),
+            #[cfg(feature = "std")]
+            secure_memory: config.secure_memory(),
+            segment_length,
+            threads,
+            variant: config.variant(),
+            version: config.version(),
+        }
+    }
+
+    fn allocate(&self) -> Result<Memory, Error> {
+        #[cfg(feature = "std")]
+        if let Some(ref allocator) = self.allocator {
+            let len = self.memory_blocks as usize;
+            let ptr = secure_memory::allocate(allocator, len * BLOCK_SIZE, self.secure_memory)?;
+            return Ok(Memory::Allocated {
+                allocator: allocator.clone(),
+                ptr: ptr.cast(),
+                len,
+                secure: self.secure_memory,
+            });
+        }
+        let mut memory = Vec::new();
+        memory
+            .try_reserve_exact(self.memory_blocks as usize)
+            .map_err(|_| {
+                Error::new(ErrorKind::MemoryAllocationError)
+                    .add_context(format!("Blocks: {}", self.memory_blocks))
+            })?;
+        memory.resize(self.memory_blocks as usize, Block::ZERO);
+        Ok(Memory::Vec(memory))
+    }
+
+    /// Number of blocks in a single slice (across all lanes)
+    fn slice_length(&self) -> usize {
+        (self.lanes * self.segment_length) as usize
+    }
+
+    /// Offset into the slice-major block matrix of block `index` of `lane`
+    fn offset(&self, lane: u32, index: u32) -> usize {
+        let slice = index / self.segment_length;
+        slice as usize * self.slice_length()
+            + (lane * self.segment_length + index % self.segment_length) as usize
+    }
+
+    /// Makes the first and second block of each lane as `H'(H0 || 0 || lane)` and
+    /// `H'(H0 || 1 || lane)`. Equivalent of `fill_first_blocks` in the C implementation
+    fn fill_first_blocks(&self, memory: &mut [Block], seed: &mut [u8; PREHASH_SEED_LENGTH]) {
+        let mut bytes = [0u8; BLOCK_SIZE];
+        for lane in 0..self.lanes {
+            seed[PREHASH_DIGEST_LENGTH + 4..].copy_from_slice(&lane.to_le_bytes());
+            for index in 0..2u32 {
+                seed[PREHASH_DIGEST_LENGTH..PREHASH_DIGEST_LENGTH + 4]
+                    .copy_from_slice(&index.to_le_bytes());
+                blake2b_long(&mut bytes, &[&seed[..]]);
+                memory[self.offset(lane, index)] = Block::from_bytes(&bytes);
+            }
+        }
+        bytes.zeroize();
+    }
+
+    /// Runs every pass over the block matrix, filling the segments of each slice in parallel
+    /// across up to `threads` threads. Equivalent of `fill_memory_blocks` in the C implementation
+    fn fill_memory_blocks(&self, memory: &mut [Block]) -> Result<(), Error> {
+        let slice_length = self.slice_length();
+        let segment_length = self.segment_length as usize;
+        for pass in 0..self.passes {
+            for slice in 0..SYNC_POINTS {
+                #[cfg(feature = "std")]
+                self.cancellation.check()?;
+                let (before, rest) = memory.split_at_mut(slice as usize * slice_length);
+                let (current, after) = rest.split_at_mut(slice_length);
+                let view = View {
+                    instance: self,
+                    before,
+                    after,
+                    slice,
+                };
+                if self.threads == 1 {
+                    for (lane, segment) in current.chunks_mut(segment_length).enumerate() {
+                        let position = Position {
+                            pass,
+                            lane: lane as u32,
+                            slice,
+                        };
+                        self.fill_segment(&view, segment, position);
+                    }
+                    continue;
+                }
+                #[cfg(feature = "std")]
+                match self.lane_pool {
+                    Some(ref lane_pool) => {
+                        self.fill_slice_on_pool(lane_pool, &view, current, pass, slice)?
+                    }
+                    None => self.fill_slice_in_threads(&view, current, pass, slice)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills the segments of every lane for the current slice, splitting the lanes between
+    /// `threads` scoped threads
+    #[cfg(feature = "std")]
+    fn fill_slice_in_threads(
+        &self,
+        view: &View,
+        current: &mut [Block],
+        pass: u32,
+        slice: u32,
+    ) -> Result<(), Error> {
+        let segment_length = self.segment_length as usize;
+        let lanes_per_thread = self.lanes.div_ceil(self.threads) as usize;
+        thread::scope(|scope| -> Result<(), Error> {
+            let mut handles = Vec::with_capacity(self.threads as usize);
+            for (i, segments) in current
+                .chunks_mut(lanes_per_thread * segment_length)
+                .enumerate()
+            {
+                let handle = thread::Builder::new()
+                    .spawn_scoped(scope, move || {
+                        for (j, segment) in segments.chunks_mut(segment_length).enumerate() {
+                            let position = Position {
+                                pass,
+                                lane: (i * lanes_per_thread + j) as u32,
+                                slice,
+                            };
+                            self.fill_segment(view, segment, position);
+                        }
+                    })
+                    .map_err(|e| {
+                        Error::new(ErrorKind::ThreadError).add_context(format!("{}", e))
+                    })?;
+                handles.push(handle);
+            }
+            for handle in handles {
+                handle
+                    .join()
+                    .map_err(|_| Error::new(ErrorKind::ThreadError))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Fills the segments of every lane for the current slice on the calling thread and on
+    /// `lane_pool`
+    #[cfg(feature = "std")]
+    fn fill_slice_on_pool(
+        &self,
+        lane_pool: &LanePool,
+        view: &View,
+        current: &mut [Block],
+        pass: u32,
+        slice: u32,
+    ) -> Result<(), Error> {
+        // Each lane locks only its own segment, so the locks are never contended
+        let segments = current
+            .chunks_mut(self.segment_length as usize)
+            .map(Mutex::new)
+            .collect::<Vec<_>>();
+        let fill_lane = |lane: usize| {
+            let mut segment = segments[lane].lock().unwrap();
+            let position = Position {
+                pass,
+                lane: lane as u32,
+                slice,
+            };
+            self.fill_segment(view, &mut segment, position);
+        };
+        let helpers = lane_pool.helpers.load(Ordering::Relaxed);
+        let arrived = lane_pool
+            .executor
+            .for_each(segments.len(), helpers, &fill_lane)?;
+        if lane_pool.adaptive {
+            // Helpers that did not get a thread in time mean the pool is busy, so use fewer of
+            // them; if they all did, probe whether the pool can spare one more
+            let helpers = if arrived < helpers {
+                arrived
+            } else {
+                (helpers + 1).min(lane_pool.max_helpers)
+            };
+            lane_pool.helpers.store(helpers, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Fills a single segment of a single lane with the selected implementation of the
+    /// compression function. Equivalent of `fill_segment` in the C implementation
+    fn fill_segment(&self, view: &View, segment: &mut [Block], position: Position) {
+        // Safety: `implementation` is only ever one the CPU supports (see `detect`)
+        match self.implementation {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Implementation::Avx512F => unsafe {
+                avx512f::fill_segment_avx512f(self, view, segment, position)
+            },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Implementation::Avx2 => unsafe {
+                avx2::fill_segment_avx2(self, view, segment, position)
+            },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Implementation::Ssse3 => unsafe {
+                sse::fill_segment_ssse3(self, view, segment, position)
+            },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Implementation::Sse2 => unsafe {
+                sse::fill_segment_sse2(self, view, segment, position)
+            },
+            #[cfg(target_arch = "aarch64")]
+            Implementation::Neon => unsafe {
+                neon::fill_segment_neon(self, view, segment, position)
+            },
+            _ => self.fill_segment_with(view, segment, position, fill_block),
+        }
+    }
+
+    /// Fills a single segment of a single lane using `fill_block` as the compression function.
+    /// Always inlined so that each caller gets a copy compiled for its own target features
+    #[inline(always)]
+    fn fill_segment_with<F>(
+        &self,
+        view: &View,
+        segment: &mut [Block],
+        position: Position,
+        fill_block: F,
+    ) where
+        F: Fn(&Block, &Block, &mut Block, bool),
+    {
+        let Position { pass, lane, slice } = position;
+        let data_independent_addressing = match self.variant {
+            Variant::Argon2d => false,
+            Variant::Argon2i => true,
+            Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        let zero_block = Block::ZERO;
+        let mut address_block = Block::ZERO;
+        let mut input_block = Block::ZERO;
+        if data_independent_addressing {
+            input_block[0] = u64::from(pass);
+            input_block[1] = u64::from(lane);
+            input_block[2] = u64::from(slice);
+            input_block[3] = u64::from(self.memory_blocks);
+            input_block[4] = u64::from(self.passes);
+            input_block[5] = self.variant as u64;
+        }
+
+        let mut starting_index = 0;
+        if pass == 0 && slice == 0 {
+            // The first two blocks of each lane have already been generated
+            starting_index = 2;
+            if data_independent_addressing {
+                next_addresses(
+                    &mut address_block,
+                    &mut input_block,
+                    &zero_block,
+                    &fill_block,
+                );
+            }
+        }
+
+        let with_xor = self.version == Version::_0x13 && pass != 0;
+        for index in starting_index..self.segment_length {
+            let index_in_lane = slice * self.segment_length + index;
+            let (filled, rest) = segment.split_at_mut(index as usize);
+
+            let prev_block = if index > 0 {
+                &filled[index as usize - 1]
+            } else if index_in_lane == 0 {
+                view.block(lane, self.lane_length - 1)
+            } else {
+                view.block(lane, index_in_lane - 1)
+            };
+
+            let pseudo_rand = if data_independent_addressing {
+                if index % ADDRESSES_IN_BLOCK == 0 {
+                    next_addresses(
+                        &mut address_block,
+                        &mut input_block,
+                        &zero_block,
+                        &fill_block,
+                    );
+                }
+                address_block[(index % ADDRESSES_IN_BLOCK) as usize]
+            } else {
+                prev_block[0]
+            };
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                // Cannot reference other lanes yet
+                lane
+            } else {
+                ((pseudo_rand >> 32) % u64::from(self.lanes)) as u32
+            };
+            let ref_index = self.index_alpha(position, index, pseudo_rand as u32, ref_lane == lane);
+            let ref_block = if ref_lane == lane && ref_index / self.segment_length == slice {
+                &filled[(ref_index % self.segment_length) as usize]
+            } else {
+                view.block(ref_lane, ref_index)
+            };
+
+            fill_block(prev_block, ref_block, &mut rest[0], with_xor);
+        }
+    }
+
+    /// Maps a pseudo-random value to the index (within its lane) of the reference block.
+    /// Equivalent of `index_alpha` in the C implementation
+    fn index_alpha(
+        &self,
+        position: Position,
+        index: u32,
+        pseudo_rand: u32,
+        same_lane: bool,
+    ) -> u32 {
+        let Position { pass, slice, .. } = position;
+        let reference_area_size = if pass == 0 {
+            if slice == 0 {
+                // All but the previous block
+                index - 1
+            } else if same_lane {
+                slice * self.segment_length + index - 1
+            } else {
+                slice * self.segment_length - u32::from(index == 0)
+            }
+        } else if same_lane {
+            self.lane_length - self.segment_length + index - 1
+        } else {
+            self.lane_length - self.segment_length - u32::from(index == 0)
+        };
+        let reference_area_size = u64::from(reference_area_size);
+
+        let mut relative_position = u64::from(pseudo_rand);
+        relative_position = (relative_position * relative_position) >> 32;
+        let relative_position =
+            reference_area_size - 1 - ((reference_area_size * relative_position) >> 32);
+
+        let start_position = if pass != 0 && slice != SYNC_POINTS - 1 {
+            (slice + 1) * self.segment_length
+        } else {
+            0
+        };
+
+        ((u64::from(start_position) + relative_position) % u64::from(self.lane_length)) as u32
+    }
+
+    /// XORs the last block of every lane together and hashes the result into `out`.
+    /// Equivalent of `finalize` in the C implementation
+    fn finalize(&self, memory: &[Block], out: &mut [u8]) {
+        let mut block_hash = memory[self.offset(0, self.lane_length - 1)];
+        for lane in 1..self.lanes {
+            block_hash ^= &memory[self.offset(lane, self.lane_length - 1)];
+        }
+        let mut bytes = block_hash.to_bytes();
+        blake2b_long(out, &[&bytes[..]]);
+        bytes.zeroize();
+        block_hash.zeroize();
+    }
+}
+
+/// Read-only view of every slice of the block matrix except the one currently being filled
+struct View<'a> {
+    instance: &'a Instance,
+    before: &'a [Block],
+    after: &'a [Block],
+    slice: u32,
+}
+
+impl View<'_> {
+    /// Block `index` of `lane`, which must not lie in the slice currently being filled
+    fn block(&self, lane: u32, index: u32) -> &Block {
+        let instance = self.instance;
+        let slice = index / instance.segment_length;
+        let offset = (lane * instance.segment_length + index % instance.segment_length) as usize;
+        if slice < self.slice {
+            &self.before[slice as usize * instance.slice_length() + offset]
+        } else {
+            debug_assert!(slice > self.slice);
+            &self.after[(slice - self.slice - 1) as usize * instance.slice_length() + offset]
+        }
+    }
+}
+
+/// Generates the next block of pseudo-random addresses for data-independent addressing
+#[inline(always)]
+fn next_addresses<F>(
+    address_block: &mut Block,
+    input_block: &mut Block,
+    zero_block: &Block,
+    fill_block: &F,
+) where
+    F: Fn(&Block, &Block, &mut Block, bool),
+{
+    input_block[6] += 1;
+    fill_block(zero_block, input_block, address_block, false);
+    let tmp = *address_block;
+    fill_block(zero_block, &tmp, address_block, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Variant, Version};
+
+    /// Implementations that the current CPU supports, from slowest to fastest
+    fn supported_implementations() -> Vec<Implementation> {
+        let mut implementations = vec![Implementation::Portable];
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if x86_feature_detected!("sse2") {
+                implementations.push(Implementation::Sse2);
+            }
+            if x86_feature_detected!("ssse3") {
+                implementations.push(Implementation::Ssse3);
+            }
+            if x86_feature_detected!("avx2") {
+                implementations.push(Implementation::Avx2);
+            }
+            if x86_feature_detected!("avx512f") {
+                implementations.push(Implementation::Avx512F);
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if aarch64_feature_detected!("neon") {
+                implementations.push(Implementation::Neon);
+            }
+        }
+        implementations
+    }
+
+    #[test]
+    fn test_implementations_agree() {
+        let implementations = supported_implementations();
+        assert_eq!(implementation_rust(), *implementations.last().unwrap());
+        for variant in &[Variant::Argon2d, Variant::Argon2i, Variant::Argon2id] {
+            for version in &[Version::_0x10, Version::_0x13] {
+                for &(lanes, threads) in &[(1, 1), (4, 2)] {
+                    let mut hasher = Hasher::default();
+                    hasher
+                        .configure_iterations(3)
+                        .configure_lanes(lanes)
+                        .configure_memory_size(256)
+                        .configure_password_clearing(false)
+                        .configure_threads(threads)
+                        .configure_variant(*variant)
+                        .configure_version(*version)
+                        .opt_out_of_secret_key(true)
+                        .with_password("P@ssw0rd")
+                        .with_salt("somesalt");
+                    let mut expected = [0u8; 32];
+                    hash_with(&hasher, &mut expected, Implementation::Portable).unwrap();
+                    for implementation in &implementations {
+                        let mut out = [0u8; 32];
+                        hash_with(&hasher, &mut out, *implementation).unwrap();
+                        assert_eq!(
+                            out, expected,
+                            "{:?} {:?} {:?} lanes={}",
+                            implementation, variant, version, lanes
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
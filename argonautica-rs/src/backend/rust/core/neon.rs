@@ -0,0 +1,226 @@
+//! NEON implementation of the compression function `G` for aarch64. Mirrors the SSSE3
+//! implementation in [`sse`](../sse/index.html): a block is processed as 64 128-bit vectors of
+//! two 64-bit words each
+
+use core::arch::aarch64::*;
+
+use super::{Instance, Position, View, block::Block};
+
+/// Number of 128-bit vectors in a block
+const OWORDS_IN_BLOCK: usize = 64;
+
+/// Fills a segment using NEON instructions
+///
+/// # Safety
+///
+/// The CPU must support NEON
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn fill_segment_neon(
+    instance: &Instance,
+    view: &View,
+    segment: &mut [Block],
+    position: Position,
+) {
+    instance.fill_segment_with(
+        view,
+        segment,
+        position,
+        |prev, reference, next, with_xor| unsafe { fill_block(prev, reference, next, with_xor) },
+    );
+}
+
+/// Computes `G(prev, reference)` into `next` (see [`block::fill_block`])
+#[inline(always)]
+unsafe fn fill_block(prev: &Block, reference: &Block, next: &mut Block, with_xor: bool) {
+    unsafe {
+        let prev_ptr = prev.as_ptr();
+        let reference_ptr = reference.as_ptr();
+        let next_ptr = next.as_mut_ptr();
+
+        let mut state = [vdupq_n_u64(0); OWORDS_IN_BLOCK];
+        let mut block_xy = [vdupq_n_u64(0); OWORDS_IN_BLOCK];
+        for i in 0..OWORDS_IN_BLOCK {
+            state[i] = veorq_u64(
+                vld1q_u64(prev_ptr.add(2 * i)),
+                vld1q_u64(reference_ptr.add(2 * i)),
+            );
+            block_xy[i] = if with_xor {
+                veorq_u64(state[i], vld1q_u64(next_ptr.add(2 * i)))
+            } else {
+                state[i]
+            };
+        }
+
+        for i in 0..8 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round([
+                state[8 * i],
+                state[8 * i + 1],
+                state[8 * i + 2],
+                state[8 * i + 3],
+                state[8 * i + 4],
+                state[8 * i + 5],
+                state[8 * i + 6],
+                state[8 * i + 7],
+            ]);
+            state[8 * i] = a0;
+            state[8 * i + 1] = a1;
+            state[8 * i + 2] = b0;
+            state[8 * i + 3] = b1;
+            state[8 * i + 4] = c0;
+            state[8 * i + 5] = c1;
+            state[8 * i + 6] = d0;
+            state[8 * i + 7] = d1;
+        }
+
+        for i in 0..8 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round([
+                state[i],
+                state[8 + i],
+                state[16 + i],
+                state[24 + i],
+                state[32 + i],
+                state[40 + i],
+                state[48 + i],
+                state[56 + i],
+            ]);
+            state[i] = a0;
+            state[8 + i] = a1;
+            state[16 + i] = b0;
+            state[24 + i] = b1;
+            state[32 + i] = c0;
+            state[40 + i] = c1;
+            state[48 + i] = d0;
+            state[56 + i] = d1;
+        }
+
+        for i in 0..OWORDS_IN_BLOCK {
+            vst1q_u64(next_ptr.add(2 * i), veorq_u64(state[i], block_xy[i]));
+        }
+    }
+}
+
+/// Applies BlaMka to sixteen 64-bit words held in eight vectors, in the same layout as
+/// `BLAKE2_ROUND` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn blake2_round(v: [uint64x2_t; 8]) -> [uint64x2_t; 8] {
+    unsafe {
+        let [
+            mut a0,
+            mut a1,
+            mut b0,
+            mut b1,
+            mut c0,
+            mut c1,
+            mut d0,
+            mut d1,
+        ] = v;
+
+        g1(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        g2(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+
+        // Diagonalize: `vextq_u64(x, y, 1)` is `(x[1], y[0])`
+        let (t0, t1) = (b0, d0);
+        b0 = vextq_u64::<1>(t0, b1);
+        b1 = vextq_u64::<1>(b1, t0);
+        ::core::mem::swap(&mut c0, &mut c1);
+        d0 = vextq_u64::<1>(d1, t1);
+        d1 = vextq_u64::<1>(t1, d1);
+
+        g1(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        g2(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+
+        // Undiagonalize
+        let (t0, t1) = (b0, d0);
+        b0 = vextq_u64::<1>(b1, t0);
+        b1 = vextq_u64::<1>(t0, b1);
+        ::core::mem::swap(&mut c0, &mut c1);
+        d0 = vextq_u64::<1>(t1, d1);
+        d1 = vextq_u64::<1>(d1, t1);
+
+        [a0, a1, b0, b1, c0, c1, d0, d1]
+    }
+}
+
+#[inline(always)]
+unsafe fn blamka(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    unsafe {
+        let z = vmull_u32(vmovn_u64(x), vmovn_u64(y));
+        vaddq_u64(vaddq_u64(x, y), vaddq_u64(z, z))
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: uint64x2_t) -> uint64x2_t {
+    unsafe { vreinterpretq_u64_u32(vrev64q_u32(vreinterpretq_u32_u64(x))) }
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: uint64x2_t) -> uint64x2_t {
+    unsafe { vsriq_n_u64::<24>(vshlq_n_u64::<40>(x), x) }
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: uint64x2_t) -> uint64x2_t {
+    unsafe { vsriq_n_u64::<16>(vshlq_n_u64::<48>(x), x) }
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: uint64x2_t) -> uint64x2_t {
+    unsafe { vsriq_n_u64::<63>(vshlq_n_u64::<1>(x), x) }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g1(
+    a0: &mut uint64x2_t,
+    b0: &mut uint64x2_t,
+    c0: &mut uint64x2_t,
+    d0: &mut uint64x2_t,
+    a1: &mut uint64x2_t,
+    b1: &mut uint64x2_t,
+    c1: &mut uint64x2_t,
+    d1: &mut uint64x2_t,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *a1 = blamka(*a1, *b1);
+        *d0 = rotr32(veorq_u64(*d0, *a0));
+        *d1 = rotr32(veorq_u64(*d1, *a1));
+        *c0 = blamka(*c0, *d0);
+        *c1 = blamka(*c1, *d1);
+        *b0 = rotr24(veorq_u64(*b0, *c0));
+        *b1 = rotr24(veorq_u64(*b1, *c1));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g2(
+    a0: &mut uint64x2_t,
+    b0: &mut uint64x2_t,
+    c0: &mut uint64x2_t,
+    d0: &mut uint64x2_t,
+    a1: &mut uint64x2_t,
+    b1: &mut uint64x2_t,
+    c1: &mut uint64x2_t,
+    d1: &mut uint64x2_t,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *a1 = blamka(*a1, *b1);
+        *d0 = rotr16(veorq_u64(*d0, *a0));
+        *d1 = rotr16(veorq_u64(*d1, *a1));
+        *c0 = blamka(*c0, *d0);
+        *c1 = blamka(*c1, *d1);
+        *b0 = rotr63(veorq_u64(*b0, *c0));
+        *b1 = rotr63(veorq_u64(*b1, *c1));
+    }
+}
@@ -0,0 +1,322 @@
+//! SSE2 and SSSE3 implementations of the compression function `G`. Port of the SSE path of
+//! `opt.c` and `blamka-round-opt.h` in the C implementation. A block is processed as 64
+//! 128-bit vectors of two 64-bit words each
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::{Instance, Position, View, block::Block};
+
+/// Equivalent of the `_MM_SHUFFLE` macro, which is not yet stable in `std::arch`
+pub(super) const fn mm_shuffle(z: i32, y: i32, x: i32, w: i32) -> i32 {
+    (z << 6) | (y << 4) | (x << 2) | w
+}
+
+/// Number of 128-bit vectors in a block
+const OWORDS_IN_BLOCK: usize = 64;
+
+/// Fills a segment using SSE2 instructions
+///
+/// # Safety
+///
+/// The CPU must support SSE2
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn fill_segment_sse2(
+    instance: &Instance,
+    view: &View,
+    segment: &mut [Block],
+    position: Position,
+) {
+    instance.fill_segment_with(
+        view,
+        segment,
+        position,
+        |prev, reference, next, with_xor| unsafe {
+            fill_block::<false>(prev, reference, next, with_xor)
+        },
+    );
+}
+
+/// Fills a segment using SSSE3 instructions
+///
+/// # Safety
+///
+/// The CPU must support SSSE3
+#[target_feature(enable = "ssse3")]
+pub(super) unsafe fn fill_segment_ssse3(
+    instance: &Instance,
+    view: &View,
+    segment: &mut [Block],
+    position: Position,
+) {
+    instance.fill_segment_with(
+        view,
+        segment,
+        position,
+        |prev, reference, next, with_xor| unsafe {
+            fill_block::<true>(prev, reference, next, with_xor)
+        },
+    );
+}
+
+/// Computes `G(prev, reference)` into `next` (see [`block::fill_block`]), using SSSE3 shuffles
+/// for the rotations and diagonalization if `SSSE3` is `true` and only SSE2 otherwise
+#[inline(always)]
+unsafe fn fill_block<const SSSE3: bool>(
+    prev: &Block,
+    reference: &Block,
+    next: &mut Block,
+    with_xor: bool,
+) {
+    unsafe {
+        let prev_ptr = prev.as_ptr() as *const __m128i;
+        let reference_ptr = reference.as_ptr() as *const __m128i;
+        let next_ptr = next.as_mut_ptr() as *mut __m128i;
+
+        let mut state = [_mm_setzero_si128(); OWORDS_IN_BLOCK];
+        let mut block_xy = [_mm_setzero_si128(); OWORDS_IN_BLOCK];
+        for i in 0..OWORDS_IN_BLOCK {
+            state[i] = _mm_xor_si128(
+                _mm_loadu_si128(prev_ptr.add(i)),
+                _mm_loadu_si128(reference_ptr.add(i)),
+            );
+            block_xy[i] = if with_xor {
+                _mm_xor_si128(state[i], _mm_loadu_si128(next_ptr.add(i)))
+            } else {
+                state[i]
+            };
+        }
+
+        for i in 0..8 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round::<SSSE3>([
+                state[8 * i],
+                state[8 * i + 1],
+                state[8 * i + 2],
+                state[8 * i + 3],
+                state[8 * i + 4],
+                state[8 * i + 5],
+                state[8 * i + 6],
+                state[8 * i + 7],
+            ]);
+            state[8 * i] = a0;
+            state[8 * i + 1] = a1;
+            state[8 * i + 2] = b0;
+            state[8 * i + 3] = b1;
+            state[8 * i + 4] = c0;
+            state[8 * i + 5] = c1;
+            state[8 * i + 6] = d0;
+            state[8 * i + 7] = d1;
+        }
+
+        for i in 0..8 {
+            let [a0, a1, b0, b1, c0, c1, d0, d1] = blake2_round::<SSSE3>([
+                state[i],
+                state[8 + i],
+                state[16 + i],
+                state[24 + i],
+                state[32 + i],
+                state[40 + i],
+                state[48 + i],
+                state[56 + i],
+            ]);
+            state[i] = a0;
+            state[8 + i] = a1;
+            state[16 + i] = b0;
+            state[24 + i] = b1;
+            state[32 + i] = c0;
+            state[40 + i] = c1;
+            state[48 + i] = d0;
+            state[56 + i] = d1;
+        }
+
+        for i in 0..OWORDS_IN_BLOCK {
+            _mm_storeu_si128(next_ptr.add(i), _mm_xor_si128(state[i], block_xy[i]));
+        }
+    }
+}
+
+/// Equivalent of `BLAKE2_ROUND` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn blake2_round<const SSSE3: bool>(v: [__m128i; 8]) -> [__m128i; 8] {
+    unsafe {
+        let [
+            mut a0,
+            mut a1,
+            mut b0,
+            mut b1,
+            mut c0,
+            mut c1,
+            mut d0,
+            mut d1,
+        ] = v;
+
+        g1::<SSSE3>(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        g2::<SSSE3>(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        diagonalize::<SSSE3>(&mut b0, &mut c0, &mut d0, &mut b1, &mut c1, &mut d1);
+        g1::<SSSE3>(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        g2::<SSSE3>(
+            &mut a0, &mut b0, &mut c0, &mut d0, &mut a1, &mut b1, &mut c1, &mut d1,
+        );
+        undiagonalize::<SSSE3>(&mut b0, &mut c0, &mut d0, &mut b1, &mut c1, &mut d1);
+
+        [a0, a1, b0, b1, c0, c1, d0, d1]
+    }
+}
+
+#[inline(always)]
+unsafe fn blamka(x: __m128i, y: __m128i) -> __m128i {
+    unsafe {
+        let z = _mm_mul_epu32(x, y);
+        _mm_add_epi64(_mm_add_epi64(x, y), _mm_add_epi64(z, z))
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: __m128i) -> __m128i {
+    unsafe { _mm_shuffle_epi32::<{ mm_shuffle(2, 3, 0, 1) }>(x) }
+}
+
+#[inline(always)]
+unsafe fn rotr24<const SSSE3: bool>(x: __m128i) -> __m128i {
+    unsafe {
+        if SSSE3 {
+            _mm_shuffle_epi8(
+                x,
+                _mm_setr_epi8(3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10),
+            )
+        } else {
+            _mm_xor_si128(_mm_srli_epi64::<24>(x), _mm_slli_epi64::<40>(x))
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr16<const SSSE3: bool>(x: __m128i) -> __m128i {
+    unsafe {
+        if SSSE3 {
+            _mm_shuffle_epi8(
+                x,
+                _mm_setr_epi8(2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9),
+            )
+        } else {
+            _mm_xor_si128(_mm_srli_epi64::<16>(x), _mm_slli_epi64::<48>(x))
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: __m128i) -> __m128i {
+    unsafe { _mm_xor_si128(_mm_srli_epi64::<63>(x), _mm_add_epi64(x, x)) }
+}
+
+/// Equivalent of `G1` in `blamka-round-opt.h`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g1<const SSSE3: bool>(
+    a0: &mut __m128i,
+    b0: &mut __m128i,
+    c0: &mut __m128i,
+    d0: &mut __m128i,
+    a1: &mut __m128i,
+    b1: &mut __m128i,
+    c1: &mut __m128i,
+    d1: &mut __m128i,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *a1 = blamka(*a1, *b1);
+        *d0 = rotr32(_mm_xor_si128(*d0, *a0));
+        *d1 = rotr32(_mm_xor_si128(*d1, *a1));
+        *c0 = blamka(*c0, *d0);
+        *c1 = blamka(*c1, *d1);
+        *b0 = rotr24::<SSSE3>(_mm_xor_si128(*b0, *c0));
+        *b1 = rotr24::<SSSE3>(_mm_xor_si128(*b1, *c1));
+    }
+}
+
+/// Equivalent of `G2` in `blamka-round-opt.h`
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+unsafe fn g2<const SSSE3: bool>(
+    a0: &mut __m128i,
+    b0: &mut __m128i,
+    c0: &mut __m128i,
+    d0: &mut __m128i,
+    a1: &mut __m128i,
+    b1: &mut __m128i,
+    c1: &mut __m128i,
+    d1: &mut __m128i,
+) {
+    unsafe {
+        *a0 = blamka(*a0, *b0);
+        *a1 = blamka(*a1, *b1);
+        *d0 = rotr16::<SSSE3>(_mm_xor_si128(*d0, *a0));
+        *d1 = rotr16::<SSSE3>(_mm_xor_si128(*d1, *a1));
+        *c0 = blamka(*c0, *d0);
+        *c1 = blamka(*c1, *d1);
+        *b0 = rotr63(_mm_xor_si128(*b0, *c0));
+        *b1 = rotr63(_mm_xor_si128(*b1, *c1));
+    }
+}
+
+/// Equivalent of `DIAGONALIZE` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn diagonalize<const SSSE3: bool>(
+    b0: &mut __m128i,
+    c0: &mut __m128i,
+    d0: &mut __m128i,
+    b1: &mut __m128i,
+    c1: &mut __m128i,
+    d1: &mut __m128i,
+) {
+    unsafe {
+        ::core::mem::swap(c0, c1);
+        let (t0, t1) = (*b0, *d0);
+        if SSSE3 {
+            *b0 = _mm_alignr_epi8::<8>(*b1, t0);
+            *b1 = _mm_alignr_epi8::<8>(t0, *b1);
+            *d0 = _mm_alignr_epi8::<8>(t1, *d1);
+            *d1 = _mm_alignr_epi8::<8>(*d1, t1);
+        } else {
+            *b0 = _mm_unpackhi_epi64(t0, _mm_unpacklo_epi64(*b1, *b1));
+            *b1 = _mm_unpackhi_epi64(*b1, _mm_unpacklo_epi64(t0, t0));
+            *d0 = _mm_unpackhi_epi64(*d1, _mm_unpacklo_epi64(t1, t1));
+            *d1 = _mm_unpackhi_epi64(t1, _mm_unpacklo_epi64(*d1, *d1));
+        }
+    }
+}
+
+/// Equivalent of `UNDIAGONALIZE` in `blamka-round-opt.h`
+#[inline(always)]
+unsafe fn undiagonalize<const SSSE3: bool>(
+    b0: &mut __m128i,
+    c0: &mut __m128i,
+    d0: &mut __m128i,
+    b1: &mut __m128i,
+    c1: &mut __m128i,
+    d1: &mut __m128i,
+) {
+    unsafe {
+        ::core::mem::swap(c0, c1);
+        let (t0, t1) = (*b0, *d0);
+        if SSSE3 {
+            *b0 = _mm_alignr_epi8::<8>(t0, *b1);
+            *b1 = _mm_alignr_epi8::<8>(*b1, t0);
+            *d0 = _mm_alignr_epi8::<8>(*d1, t1);
+            *d1 = _mm_alignr_epi8::<8>(t1, *d1);
+        } else {
+            *b0 = _mm_unpackhi_epi64(*b1, _mm_unpacklo_epi64(t0, t0));
+            *b1 = _mm_unpackhi_epi64(t0, _mm_unpacklo_epi64(*b1, *b1));
+            *d0 = _mm_unpackhi_epi64(t1, _mm_unpacklo_epi64(*d1, *d1));
+            *d1 = _mm_unpackhi_epi64(*d1, _mm_unpacklo_epi64(t1, t1));
+        }
+    }
+}
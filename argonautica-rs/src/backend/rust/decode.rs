@@ -1,94 +1,258 @@
-use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
-use nom::{
-    IResult, Parser,
-    bytes::{take, take_until},
-    combinator::{map, map_res},
-    sequence::{preceded, terminated},
+use alloc::vec::Vec;
+
+use base64::{
+    DecodeError, Engine,
+    alphabet::STANDARD,
+    engine::{
+        DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig, general_purpose::STANDARD_NO_PAD,
+    },
 };
 
 use crate::{
     Error, ErrorKind,
     config::{Variant, Version},
+    input::MAX_KEY_ID_LEN,
     output::HashRaw,
 };
 
+/// Accepts `=` padding and non-zero trailing bits, both of which the strict parser rejects
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Parses a hash in the [PHC string format](https://github.com/P-H-C/phc-string-format),
+/// i.e. `$<variant>[$v=<version>]$m=<memory_size>,t=<iterations>,p=<lanes>[,keyid=<key_id>]$<salt>$<hash>`,
+/// rejecting anything that is not the canonical encoding of a [`HashRaw`]: `=` padding,
+/// non-zero trailing bits, leading zeros, unknown or out-of-order parameters, and any
+/// characters before or after the hash
 pub(crate) fn decode_rust(hash: &str) -> Result<HashRaw, Error> {
-    let (rest, intermediate) = parse_hash(hash).map_err(|_| {
-        Error::new(ErrorKind::HashDecodeError).add_context(format!("Hash: {}", &hash))
-    })?;
-    let raw_hash_bytes = STANDARD_NO_PAD.decode(rest).map_err(|_| {
-        Error::new(ErrorKind::HashDecodeError).add_context(format!("Hash: {}", &hash))
-    })?;
-    let hash_raw = HashRaw {
-        iterations: intermediate.iterations,
-        lanes: intermediate.lanes,
-        memory_size: intermediate.memory_size,
-        raw_hash_bytes,
-        raw_salt_bytes: intermediate.raw_salt_bytes,
-        variant: intermediate.variant,
-        version: intermediate.version,
-    };
-    Ok(hash_raw)
+    Parser::new(hash, /* lenient */ false).parse()
 }
 
-struct IntermediateStruct {
-    variant: Variant,
-    version: Version,
+/// Same as [`decode_rust`] except that it accepts the non-canonical encodings written by
+/// older or less careful encoders: surrounding whitespace, `=` padding, non-zero trailing
+/// bits, leading zeros, parameters in any order and unknown parameters (which are ignored)
+pub(crate) fn decode_rust_lenient(hash: &str) -> Result<HashRaw, Error> {
+    Parser::new(hash, /* lenient */ true).parse()
+}
+
+struct Params {
     memory_size: u32,
     iterations: u32,
     lanes: u32,
-    raw_salt_bytes: Vec<u8>,
+    key_id: Option<Vec<u8>>,
 }
 
-fn parse_hash(input: &str) -> IResult<&str, IntermediateStruct> {
-    map(
-        terminated(
-            (
-                preceded(
-                    (take_until("$"), take(1usize)),
-                    map_res(take_until("$"), |x: &str| x.parse::<Variant>()),
-                ),
-                preceded(
-                    (take_until("$v="), take(3usize)),
-                    map_res(take_until("$"), |x: &str| x.parse::<Version>()),
-                ),
-                preceded(
-                    (take_until("$m="), take(3usize)),
-                    map_res(take_until(","), |x: &str| x.parse::<u32>()),
-                ),
-                preceded(
-                    (take_until(",t="), take(3usize)),
-                    map_res(take_until(","), |x: &str| x.parse::<u32>()),
-                ),
-                preceded(
-                    (take_until(",p="), take(3usize)),
-                    map_res(take_until("$"), |x: &str| x.parse::<u32>()),
-                ),
-                preceded(
-                    (take_until("$"), take(1usize)),
-                    map_res(take_until("$"), |x: &str| STANDARD_NO_PAD.decode(x)),
-                ),
-            ),
-            (take_until("$"), take(1usize)),
-        ),
-        |(variant, version, memory_size, iterations, lanes, raw_salt_bytes)| IntermediateStruct {
+struct Parser<'a> {
+    input: &'a str,
+    lenient: bool,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(hash: &'a str, lenient: bool) -> Parser<'a> {
+        let (input, pos) = if lenient {
+            let trimmed = hash.trim_end();
+            let start = trimmed.len() - trimmed.trim_start().len();
+            (trimmed, start)
+        } else {
+            (hash, 0)
+        };
+        Parser {
+            input,
+            lenient,
+            pos,
+        }
+    }
+
+    fn parse(mut self) -> Result<HashRaw, Error> {
+        let variant = self.parse_variant()?;
+        let (version, omit_version) = self.parse_version()?;
+        let params = self.parse_params()?;
+        let raw_salt_bytes = self.parse_base64("salt")?;
+        let raw_hash_bytes = self.parse_base64("hash")?;
+        if self.pos != self.input.len() {
+            return Err(error("hash", self.pos, "Unexpected trailing data"));
+        }
+        Ok(HashRaw {
+            iterations: params.iterations,
+            key_id: params.key_id,
+            lanes: params.lanes,
+            memory_size: params.memory_size,
+            omit_version,
+            raw_hash_bytes,
+            raw_salt_bytes,
             variant,
             version,
-            memory_size,
-            iterations,
-            lanes,
-            raw_salt_bytes,
-        },
-    )
-    .parse(input)
+        })
+    }
+
+    fn parse_variant(&mut self) -> Result<Variant, Error> {
+        let (start, segment) = self.next_segment("variant")?;
+        segment
+            .parse::<Variant>()
+            .map_err(|_| error("variant", start, "Unknown variant"))
+    }
+
+    /// The `$v=` segment is optional; hashes without it predate version 0x13 and use 0x10
+    fn parse_version(&mut self) -> Result<(Version, bool), Error> {
+        if !self.input[self.pos..].starts_with("$v=") {
+            return Ok((Version::_0x10, true));
+        }
+        let (start, segment) = self.next_segment("v")?;
+        let version = self.parse_decimal("v", start + 2, &segment[2..])?;
+        let version =
+            Version::from_u32(version).map_err(|_| error("v", start + 2, "Unsupported version"))?;
+        Ok((version, false))
+    }
+
+    fn parse_params(&mut self) -> Result<Params, Error> {
+        const NAMES: [&str; 4] = ["m", "t", "p", "keyid"];
+        let (start, segment) = self.next_segment("m")?;
+        let mut values: [Option<u32>; 3] = [None; 3];
+        let mut key_id = None;
+        let mut offset = start;
+        for (i, param) in segment.split(',').enumerate() {
+            let param_offset = offset;
+            offset += param.len() + 1;
+            let (name, value) = match param.split_once('=') {
+                Some((name, value)) if !name.is_empty() => (name, value),
+                _ => return Err(error("params", param_offset, "Expected \"<name>=<value>\"")),
+            };
+            let index = match NAMES.iter().position(|&n| n == name) {
+                Some(index) => index,
+                None if self.lenient => continue,
+                None => return Err(error(name, param_offset, "Unknown parameter")),
+            };
+            if !self.lenient && index != i {
+                let msg = "Parameter out of order; expected m, t, p, keyid";
+                return Err(error(name, param_offset, msg));
+            }
+            let value_offset = param_offset + name.len() + 1;
+            if index == 3 {
+                if key_id.is_some() {
+                    return Err(error(name, param_offset, "Duplicate parameter"));
+                }
+                key_id = Some(self.parse_key_id(value_offset, value)?);
+                continue;
+            }
+            if values[index].is_some() {
+                return Err(error(name, param_offset, "Duplicate parameter"));
+            }
+            values[index] = Some(self.parse_decimal(name, value_offset, value)?);
+        }
+        let end = start + segment.len();
+        let mut params = [0u32; 3];
+        for (index, name) in NAMES[..3].iter().enumerate() {
+            params[index] = values[index].ok_or_else(|| error(name, end, "Missing parameter"))?;
+        }
+        Ok(Params {
+            memory_size: params[0],
+            iterations: params[1],
+            lanes: params[2],
+            key_id,
+        })
+    }
+
+    fn parse_key_id(&self, offset: usize, value: &str) -> Result<Vec<u8>, Error> {
+        let key_id = self.decode_base64("keyid", offset, value)?;
+        if key_id.len() > MAX_KEY_ID_LEN {
+            let msg = format!("Key ID must be at most {} bytes long", MAX_KEY_ID_LEN);
+            return Err(error("keyid", offset, &msg));
+        }
+        Ok(key_id)
+    }
+
+    fn parse_decimal(&self, field: &str, offset: usize, value: &str) -> Result<u32, Error> {
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error(field, offset, "Expected a decimal number"));
+        }
+        if !self.lenient && value.len() > 1 && value.starts_with('0') {
+            return Err(error(field, offset, "Leading zeros are not allowed"));
+        }
+        value
+            .parse::<u32>()
+            .map_err(|_| error(field, offset, "Number does not fit in a u32"))
+    }
+
+    fn parse_base64(&mut self, field: &str) -> Result<Vec<u8>, Error> {
+        let (start, segment) = self.next_segment(field)?;
+        self.decode_base64(field, start, segment)
+    }
+
+    fn decode_base64(&self, field: &str, start: usize, value: &str) -> Result<Vec<u8>, Error> {
+        if value.is_empty() {
+            return Err(error(field, start, "Expected a non-empty base64 value"));
+        }
+        let result = if self.lenient {
+            LENIENT_BASE64.decode(value)
+        } else {
+            STANDARD_NO_PAD.decode(value)
+        };
+        result.map_err(|e| match e {
+            DecodeError::InvalidByte(_, b'=') | DecodeError::InvalidPadding => {
+                let offset = value.find('=').unwrap_or(value.len());
+                error(field, start + offset, "Padding is not allowed")
+            }
+            DecodeError::InvalidByte(offset, _) => {
+                error(field, start + offset, "Invalid base64 character")
+            }
+            DecodeError::InvalidLastSymbol(offset, _) => error(
+                field,
+                start + offset,
+                "Non-canonical base64 (trailing bits must be zero)",
+            ),
+            DecodeError::InvalidLength(_) => error(field, start, "Invalid base64 length"),
+        })
+    }
+
+    /// Consumes a `$` followed by everything up to (but not including) the next `$` or the end
+    /// of the input, returning the offset at which the segment starts along with the segment
+    fn next_segment(&mut self, field: &str) -> Result<(usize, &'a str), Error> {
+        let rest = &self.input[self.pos..];
+        if !rest.starts_with('$') {
+            let msg = if rest.is_empty() {
+                "Unexpected end of hash"
+            } else {
+                "Expected \"$\""
+            };
+            return Err(error(field, self.pos, msg));
+        }
+        let start = self.pos + 1;
+        let len = rest[1..].find('$').unwrap_or(rest.len() - 1);
+        self.pos = start + len;
+        Ok((start, &self.input[start..self.pos]))
+    }
+}
+
+fn error(field: &str, offset: usize, msg: &str) -> Error {
+    Error::new(ErrorKind::HashDecodeError)
+        .add_context(format!("{}. Field: {}. Offset: {}", msg, field, offset))
 }
 
 #[cfg(test)]
 mod tests {
-    use rand::{RngCore, SeedableRng, rngs::StdRng};
-
     use super::*;
-    use crate::{backend::c::decode_c, hasher::Hasher};
+
+    const SALT: &str = "c29tZXNhbHRzb21lc2FsdA";
+    const HASH: &str = "v6V587B9qbKraulhK/6vFUq93BGWugdzgRhtyap9tDM";
+
+    fn assert_error(result: Result<HashRaw, Error>, field: &str, offset: usize) {
+        let e = result.unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::HashDecodeError);
+        let display = format!("{}", e);
+        let expected = format!("Field: {}. Offset: {}", field, offset);
+        assert!(
+            display.ends_with(&expected),
+            "{} does not end with {}",
+            display,
+            expected
+        );
+    }
 
     #[test]
     fn test_decode() {
@@ -118,8 +282,159 @@ mod tests {
     }
 
     #[test]
-    #[ignore] // TODO: Turn back on once implemented decode_c
+    fn test_decode_legacy() {
+        let legacy = format!("$argon2i$m=4096,t=3,p=1${}${}", SALT, HASH);
+        for decode in &[decode_rust, decode_rust_lenient] {
+            let hash_raw = decode(&legacy).unwrap();
+            assert_eq!(hash_raw.variant(), Variant::Argon2i);
+            assert_eq!(hash_raw.version(), Version::_0x10);
+            assert_eq!(hash_raw.memory_size(), 4096);
+            assert!(hash_raw.omits_version());
+            assert_eq!(hash_raw.encode_rust(), legacy);
+        }
+
+        let explicit = format!("$argon2i$v=16$m=4096,t=3,p=1${}${}", SALT, HASH);
+        let hash_raw = decode_rust(&explicit).unwrap();
+        assert!(!hash_raw.omits_version());
+        assert_eq!(hash_raw.encode_rust(), explicit);
+    }
+
+    #[test]
+    fn test_decode_key_id() {
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,p=1,keyid=MjAyNQ${}${}",
+            SALT, HASH
+        );
+        let hash_raw = decode_rust(&hash).unwrap();
+        assert_eq!(hash_raw.key_id(), Some(&b"2025"[..]));
+        assert_eq!(hash_raw.encode_rust(), hash);
+
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,keyid=MjAyNQ,p=1${}${}",
+            SALT, HASH
+        );
+        assert_error(decode_rust(&hash), "keyid", 26);
+        let hash_raw = decode_rust_lenient(&hash).unwrap();
+        assert_eq!(hash_raw.key_id(), Some(&b"2025"[..]));
+
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,p=1,keyid=MTIzNDU2Nzg5${}${}",
+            SALT, HASH
+        );
+        assert_error(decode_rust(&hash), "keyid", 36);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1,keyid=${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "keyid", 36);
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,p=1,keyid=MjAyNQ==${}${}",
+            SALT, HASH
+        );
+        assert_error(decode_rust(&hash), "keyid", 42);
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let canonical = format!("$argon2id$v=19$m=4096,t=3,p=1${}${}", SALT, HASH);
+        let hash_raw = decode_rust(&canonical).unwrap();
+        assert_eq!(hash_raw.raw_salt_bytes(), b"somesaltsomesalt");
+        assert_eq!(hash_raw.encode_rust(), canonical);
+
+        // Leading garbage
+        assert_error(decode_rust(&format!("x{}", canonical)), "variant", 0);
+        assert_error(decode_rust(&format!(" {}", canonical)), "variant", 0);
+        // Unknown variant
+        let hash = format!("$argon2$v=19$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "variant", 1);
+        // Malformed version
+        let hash = format!("$argon2id$v=$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "v", 12);
+        let hash = format!("$argon2id$v19$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "params", 10);
+        let hash = format!("$argon2id$v=20$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "v", 12);
+        let hash = format!("$argon2id$v=019$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "v", 12);
+        // Leading zeros, signs and overflow
+        let hash = format!("$argon2id$v=19$m=04096,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "m", 17);
+        let hash = format!("$argon2id$v=19$m=4096,t=+3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "t", 24);
+        let hash = format!("$argon2id$v=19$m=4294967296,t=3,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "m", 17);
+        // Wrong order, unknown, duplicate and missing parameters
+        let hash = format!("$argon2id$v=19$t=3,m=4096,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "t", 15);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1,x=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "x", 30);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,t=3${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "t", 26);
+        let hash = format!("$argon2id$v=19$m=4096,t=3${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "p", 25);
+        let hash = format!("$argon2id$v=19$m=4096,,p=1${}${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "params", 22);
+        // Padding and non-canonical base64
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1${}==${}", SALT, HASH);
+        assert_error(decode_rust(&hash), "salt", 52);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1${}${}=", SALT, HASH);
+        assert_error(decode_rust(&hash), "hash", 96);
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHRzb21lc2FsdB${}",
+            HASH
+        );
+        assert_error(decode_rust(&hash), "salt", 51);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1$c29t*XNhbHQ${}", HASH);
+        assert_error(decode_rust(&hash), "salt", 34);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1$${}", HASH);
+        assert_error(decode_rust(&hash), "salt", 30);
+        // Missing and trailing data
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1${}", SALT);
+        assert_error(decode_rust(&hash), "hash", 52);
+        assert_error(decode_rust(&format!("{}$", canonical)), "hash", 96);
+        assert_error(decode_rust(&format!("{}$x", canonical)), "hash", 96);
+        assert_error(decode_rust(&format!("{}\n", canonical)), "hash", 96);
+    }
+
+    #[test]
+    fn test_decode_lenient() {
+        let canonical = format!("$argon2id$v=19$m=4096,t=3,p=1${}${}", SALT, HASH);
+        let expected = decode_rust(&canonical).unwrap();
+        for hash in &[
+            canonical.clone(),
+            format!("  {}\n", canonical),
+            format!("$argon2id$v=19$m=4096,t=3,p=1${}==${}=", SALT, HASH),
+            format!("$argon2id$v=19$m=04096,t=003,p=01${}${}", SALT, HASH),
+            format!("$argon2id$v=19$p=1,t=3,m=4096${}${}", SALT, HASH),
+            format!("$argon2id$v=19$m=4096,t=3,p=1,data=abc${}${}", SALT, HASH),
+        ] {
+            assert!(decode_rust(hash).is_err() || hash == &canonical);
+            assert_eq!(decode_rust_lenient(hash).unwrap(), expected);
+        }
+
+        // Trailing bits are accepted and dropped
+        let hash = format!(
+            "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHRzb21lc2FsdB${}",
+            HASH
+        );
+        assert_eq!(decode_rust_lenient(&hash).unwrap(), expected);
+
+        // Structural errors are still errors, with offsets into the original string
+        let hash = format!("  $argon2id$v=19$m=4096,t=3,t=3${}${}", SALT, HASH);
+        assert_error(decode_rust_lenient(&hash), "t", 28);
+        let hash = format!("$argon2id$v=19$m=4096,t=3${}${}", SALT, HASH);
+        assert_error(decode_rust_lenient(&hash), "p", 25);
+        let hash = format!("$argon2id$v=19$m=4096,t=3,p=1${}${}$x", SALT, HASH);
+        assert_error(decode_rust_lenient(&hash), "hash", 96);
+    }
+
+    #[cfg(feature = "backend-c")]
+    #[test]
     fn test_decode_against_c() {
+        use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+        use crate::{backend::c::decode_c, hasher::Hasher};
+
+        let legacy = format!("$argon2i$m=4096,t=3,p=1${}${}", SALT, HASH);
+        assert_eq!(decode_rust(&legacy).unwrap(), decode_c(&legacy).unwrap());
+
         let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
         let mut password = vec![0u8; 12];
         let mut secret_key = vec![0u8; 32];
@@ -144,4 +459,28 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "backend-c")]
+    #[test]
+    fn test_decode_rejections_against_c() {
+        use crate::backend::c::decode_c;
+
+        for hash in &[
+            format!("x$argon2id$v=19$m=4096,t=3,p=1${}${}", SALT, HASH),
+            format!("$argon2id$v=19$m=04096,t=3,p=1${}${}", SALT, HASH),
+            format!("$argon2id$v=19$t=3,m=4096,p=1${}${}", SALT, HASH),
+            format!("$argon2id$v=19$m=4096,t=3,p=1,x=1${}${}", SALT, HASH),
+            format!("$argon2id$v=19$m=4096,t=3,p=1${}==${}", SALT, HASH),
+            format!(
+                "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHRzb21lc2FsdB${}",
+                HASH
+            ),
+            format!("$argon2id$v=19$m=4096,t=3,p=1${}${}$", SALT, HASH),
+            format!("$argon2id$v=19$m=4096,t=3,p=1${}${}\n", SALT, HASH),
+            format!("$argon2id$v=$m=4096,t=3,p=1${}${}", SALT, HASH),
+        ] {
+            assert!(decode_rust(hash).is_err(), "{}", hash);
+            assert!(decode_c(hash).is_err(), "{}", hash);
+        }
+    }
 }
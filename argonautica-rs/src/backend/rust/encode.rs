@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
 
 use crate::output::HashRaw;
@@ -6,20 +8,30 @@ impl HashRaw {
     pub(crate) fn encode_rust(&self) -> String {
         let hash_encoded = STANDARD_NO_PAD.encode(self.raw_hash_bytes());
         let salt_encoded = STANDARD_NO_PAD.encode(self.raw_salt_bytes());
+        let version_encoded = if self.omits_version() {
+            String::new()
+        } else {
+            format!("$v={}", self.version().as_str())
+        };
+        let key_id_encoded = match self.key_id() {
+            Some(key_id) => format!(",keyid={}", STANDARD_NO_PAD.encode(key_id)),
+            None => String::new(),
+        };
         format!(
-            "${}$v={}$m={},t={},p={}${}${}",
+            "${}{}$m={},t={},p={}{}${}${}",
             self.variant().as_str(),
-            self.version().as_str(),
+            version_encoded,
             self.memory_size(),
             self.iterations(),
             self.lanes(),
+            key_id_encoded,
             salt_encoded,
             hash_encoded,
         )
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "backend-c"))]
 mod tests {
     use rand::{RngCore, SeedableRng, rngs::StdRng};
 
@@ -1,10 +1,87 @@
-#![allow(dead_code)]
+use crate::{Error, Hasher, backend::rust::core, output::HashRaw};
 
-use Error;
-use hasher::Hasher;
-use output::HashRaw;
+impl Hasher<'_> {
+    pub(crate) fn hash_raw_rust(&mut self) -> Result<HashRaw, Error> {
+        let mut buffer = vec![0u8; self.config.hash_len() as usize];
+        core::hash(self, &mut buffer)?;
+        Ok(HashRaw {
+            iterations: self.config.iterations(),
+            key_id: self.config.key_id().map(|key_id| key_id.to_vec()),
+            lanes: self.config.lanes(),
+            memory_size: self.config.memory_size(),
+            omit_version: false,
+            raw_hash_bytes: buffer,
+            raw_salt_bytes: self.salt.as_bytes().to_vec(),
+            variant: self.config.variant(),
+            version: self.config.version(),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "backend-c"))]
+mod tests {
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+    use crate::config::{Variant, Version};
+    use crate::hasher::Hasher;
 
-pub(crate) fn hash_raw_rust(hasher: &mut Hasher) -> Result<HashRaw, Error> {
-    let _ = hasher;
-    unimplemented!()
+    #[test]
+    fn test_hash_raw_against_c() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let mut additional_data = vec![0u8; 16];
+        let mut password = vec![0u8; 12];
+        let mut salt = vec![0u8; 16];
+        let mut secret_key = vec![0u8; 32];
+        for variant in &[Variant::Argon2d, Variant::Argon2i, Variant::Argon2id] {
+            for version in &[Version::_0x10, Version::_0x13] {
+                for &(lanes, threads) in &[(1, 1), (4, 4), (4, 1), (3, 2)] {
+                    for &(memory_size, iterations) in &[(32, 1), (64, 3), (256, 2)] {
+                        for &hash_len in &[4, 32, 64, 65, 128] {
+                            for &with_extras in &[false, true] {
+                                rng.fill_bytes(&mut additional_data);
+                                rng.fill_bytes(&mut password);
+                                rng.fill_bytes(&mut salt);
+                                rng.fill_bytes(&mut secret_key);
+                                let mut hasher = Hasher::default();
+                                hasher
+                                    .configure_hash_len(hash_len)
+                                    .configure_iterations(iterations)
+                                    .configure_lanes(lanes)
+                                    .configure_memory_size(memory_size)
+                                    .configure_password_clearing(false)
+                                    .configure_secret_key_clearing(false)
+                                    .configure_threads(threads)
+                                    .configure_variant(*variant)
+                                    .configure_version(*version)
+                                    .with_password(&password[..])
+                                    .with_salt(&salt[..]);
+                                if with_extras {
+                                    hasher
+                                        .with_additional_data(&additional_data[..])
+                                        .with_secret_key(&secret_key[..]);
+                                } else {
+                                    hasher.opt_out_of_secret_key(true);
+                                }
+                                let hash_raw1 = hasher.hash_raw_c().unwrap();
+                                let hash_raw2 = hasher.hash_raw_rust().unwrap();
+                                assert_eq!(
+                                    hash_raw1,
+                                    hash_raw2,
+                                    "{:?} {:?} lanes={} threads={} m={} t={} len={} extras={}",
+                                    variant,
+                                    version,
+                                    lanes,
+                                    threads,
+                                    memory_size,
+                                    iterations,
+                                    hash_len,
+                                    with_extras,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,8 +1,7 @@
-#[cfg(feature = "development")]
 mod core;
 mod decode;
 mod encode;
-#[cfg(feature = "development")]
 mod hash_raw;
 
-pub(crate) use self::decode::decode_rust;
+pub(crate) use self::core::implementation_rust;
+pub(crate) use self::decode::{decode_rust, decode_rust_lenient};
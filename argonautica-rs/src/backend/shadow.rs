@@ -0,0 +1,137 @@
+use crate::{
+    Error, Hasher,
+    backend::{c::implementation_c, rust::implementation_rust},
+    config::HasherConfig,
+    output::{HashRaw, ShadowMismatch},
+};
+
+impl Hasher<'_> {
+    /// Computes the hash with both backends and returns the result of the C backend, which
+    /// serves as the reference. Any disagreement from the Rust backend is reported rather than
+    /// returned
+    pub(crate) fn hash_raw_shadow(&mut self) -> Result<HashRaw, Error> {
+        let c_hash_raw = self.hash_raw_c()?;
+        let rust_result = self.hash_raw_rust();
+        if let Some(mismatch) = compare(c_hash_raw.clone(), rust_result) {
+            report(&self.config, &mismatch);
+        }
+        Ok(c_hash_raw)
+    }
+}
+
+fn compare(c_hash_raw: HashRaw, rust_result: Result<HashRaw, Error>) -> Option<ShadowMismatch> {
+    if let Ok(ref rust_hash_raw) = rust_result
+        && *rust_hash_raw == c_hash_raw
+    {
+        return None;
+    }
+    Some(ShadowMismatch {
+        c_hash_raw,
+        c_implementation: implementation_c(),
+        rust_result,
+        rust_implementation: implementation_rust(),
+    })
+}
+
+fn report(config: &HasherConfig, mismatch: &ShadowMismatch) {
+    match config.shadow_callback() {
+        Some(shadow_callback) => shadow_callback.call(mismatch),
+        None => {
+            // Deliberately leaves out the hash and salt bytes
+            let hash_raw = mismatch.c_hash_raw();
+            error!(
+                "Shadow mode mismatch between the C backend ({}) and the Rust backend ({}): {}. \
+                 Variant: {:?}. Version: {:?}. Iterations: {}. Lanes: {}. Memory size: {}. \
+                 Threads: {}. Hash len: {}. The C result was returned.",
+                mismatch.c_implementation().as_str(),
+                mismatch.rust_implementation().as_str(),
+                match mismatch.rust_result() {
+                    Ok(_) => "different hash bytes".to_string(),
+                    Err(e) => format!("{}", e),
+                },
+                hash_raw.variant(),
+                hash_raw.version(),
+                hash_raw.iterations(),
+                hash_raw.lanes(),
+                hash_raw.memory_size(),
+                config.threads(),
+                hash_raw.raw_hash_bytes().len(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{ErrorKind, config::Backend};
+
+    fn hasher_with_counter(backend: Backend) -> (Hasher<'static>, Arc<AtomicUsize>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = counter.clone();
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_backend(backend)
+            .configure_iterations(2)
+            .configure_lanes(4)
+            .configure_memory_size(64)
+            .configure_shadow_mode(true)
+            .configure_shadow_callback(move |_| {
+                counter_clone.fetch_add(1, Ordering::SeqCst);
+            })
+            .configure_threads(2)
+            .opt_out_of_secret_key(true)
+            .with_salt("somesalt");
+        (hasher, counter)
+    }
+
+    #[test]
+    fn test_shadow_mode_agrees() {
+        for &backend in &[Backend::C, Backend::Rust, Backend::Auto] {
+            let (mut hasher, counter) = hasher_with_counter(backend);
+            let hash_raw = hasher.with_password("P@ssw0rd").hash_raw().unwrap();
+            assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+            let mut hasher = Hasher::default();
+            let expected = hasher
+                .configure_backend(Backend::C)
+                .configure_iterations(2)
+                .configure_lanes(4)
+                .configure_memory_size(64)
+                .configure_threads(2)
+                .opt_out_of_secret_key(true)
+                .with_password("P@ssw0rd")
+                .with_salt("somesalt")
+                .hash_raw()
+                .unwrap();
+            assert_eq!(hash_raw, expected);
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let (mut hasher, counter) = hasher_with_counter(Backend::C);
+        let c_hash_raw = hasher.with_password("P@ssw0rd").hash_raw().unwrap();
+
+        assert!(compare(c_hash_raw.clone(), Ok(c_hash_raw.clone())).is_none());
+
+        let mut rust_hash_raw = c_hash_raw.clone();
+        rust_hash_raw.raw_hash_bytes[0] ^= 1;
+        let mismatch = compare(c_hash_raw.clone(), Ok(rust_hash_raw.clone())).unwrap();
+        assert_eq!(mismatch.c_hash_raw(), &c_hash_raw);
+        assert_eq!(mismatch.rust_result(), Ok(&rust_hash_raw));
+        report(&hasher.config, &mismatch);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        let error = Error::new(ErrorKind::MemoryAllocationError);
+        let mismatch = compare(c_hash_raw, Err(error.clone())).unwrap();
+        assert_eq!(mismatch.rust_result(), Err(&error));
+        report(&hasher.config, &mismatch);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+}
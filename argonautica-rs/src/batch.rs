@@ -0,0 +1,97 @@
+use std::future::Future;
+
+use futures::{Stream, StreamExt};
+
+use crate::{
+    Error,
+    config::{BatchOrder, default_threads},
+};
+
+/// Runs `f` on each item of `items` and yields the results tagged with the index of their
+/// item, keeping only as many hashes in flight as [`parallelism`] allows. `f` receives the
+/// number of threads each hash should use
+pub(crate) fn run<S, F, Fut, T>(
+    items: S,
+    order: BatchOrder,
+    threads: u32,
+    mut f: F,
+) -> impl Stream<Item = (usize, Result<T, Error>)>
+where
+    S: Stream,
+    F: FnMut(S::Item, u32) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let (concurrency, threads) = parallelism(items.size_hint().1, threads);
+    let futures = items.enumerate().map(move |(index, item)| {
+        let future = f(item, threads);
+        async move { (index, future.await) }
+    });
+    match order {
+        BatchOrder::Input => futures.buffered(concurrency).left_stream(),
+        BatchOrder::Completion => futures.buffer_unordered(concurrency).right_stream(),
+    }
+}
+
+/// Returns how many hashes to run at once and how many threads each of them should use for a
+/// batch of (at most) `len` items. Hashes of a batch mostly wait on memory, so one hash per
+/// logical core with a single thread each gives the best throughput; only batches too small
+/// to occupy every core get fewer hashes with more threads each, up to `threads`
+fn parallelism(len: Option<usize>, threads: u32) -> (usize, u32) {
+    let cores = default_threads().max(1) as usize;
+    let concurrency = match len {
+        Some(len) if len < cores => len.max(1),
+        _ => cores,
+    };
+    let threads = ((cores / concurrency) as u32).clamp(1, threads.max(1));
+    (concurrency, threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, stream};
+
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_parallelism() {
+        let cores = default_threads().max(1) as usize;
+        assert_eq!(parallelism(None, 4), (cores, 1));
+        assert_eq!(parallelism(Some(10 * cores), 4), (cores, 1));
+        assert_eq!(parallelism(Some(0), 1), (1, 1));
+        assert_eq!(parallelism(Some(1), u32::MAX), (1, cores as u32));
+        assert_eq!(parallelism(Some(1), 1), (1, 1));
+    }
+
+    #[test]
+    fn test_run() {
+        let items = stream::iter(vec![3u64, 0, 2, 1]);
+        let results = block_on(
+            run(items, BatchOrder::Input, 1, |item, _| async move {
+                if item == 0 {
+                    Err(Error::new(ErrorKind::Bug))
+                } else {
+                    Ok(item * 10)
+                }
+            })
+            .collect::<Vec<_>>(),
+        );
+        let indices = results.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+        assert_eq!(*results[0].1.as_ref().unwrap(), 30);
+        assert_eq!(results[1].1.as_ref().unwrap_err().kind(), ErrorKind::Bug);
+        assert_eq!(*results[3].1.as_ref().unwrap(), 10);
+
+        let items = stream::iter(vec![1u64, 2, 3]);
+        let mut results = block_on(
+            run(items, BatchOrder::Completion, 1, |item, _| async move {
+                Ok(item)
+            })
+            .collect::<Vec<_>>(),
+        );
+        results.sort_by_key(|(index, _)| *index);
+        for (index, result) in results {
+            assert_eq!(result.unwrap(), index as u64 + 1);
+        }
+    }
+}
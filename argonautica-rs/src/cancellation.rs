@@ -0,0 +1,86 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{Error, ErrorKind};
+
+/// Lets a hash stop before it is finished, either because nobody is waiting for it anymore
+/// (the future returned by one of the non-blocking methods was dropped) or because it ran
+/// past its deadline. Both backends call [`check`](#method.check) before each slice of each
+/// pass, so a cancelled hash stops within a quarter of a pass and frees its memory
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cancellation {
+    cancelled: Option<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Returns a [`Cancellation`] that can be triggered with [`cancel`](#method.cancel) (also
+    /// through its clones) and whose deadline is `timeout` from now, if any
+    pub(crate) fn new(timeout: Option<Duration>) -> Cancellation {
+        Cancellation {
+            cancelled: Some(Arc::new(AtomicBool::new(false))),
+            deadline: deadline(timeout),
+        }
+    }
+    pub(crate) fn cancel(&self) {
+        if let Some(ref cancelled) = self.cancelled {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+    /// Sets the deadline to `timeout` from now, unless there already is one, e.g. because the
+    /// timeout started counting when a non-blocking method was called
+    pub(crate) fn start(&mut self, timeout: Option<Duration>) {
+        if self.deadline.is_none() {
+            self.deadline = deadline(timeout);
+        }
+    }
+    /// Returns an [`ErrorKind::CancelledError`](../enum.ErrorKind.html#variant.CancelledError)
+    /// or an [`ErrorKind::TimeoutError`](../enum.ErrorKind.html#variant.TimeoutError) if
+    /// hashing should stop
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if let Some(ref cancelled) = self.cancelled
+            && cancelled.load(Ordering::Relaxed)
+        {
+            return Err(Error::new(ErrorKind::CancelledError));
+        }
+        if let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            return Err(Error::new(ErrorKind::TimeoutError));
+        }
+        Ok(())
+    }
+}
+
+fn deadline(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.and_then(|timeout| Instant::now().checked_add(timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let mut cancellation = Cancellation::default();
+        assert!(cancellation.check().is_ok());
+        cancellation.cancel();
+        assert!(cancellation.check().is_ok());
+        cancellation.start(Some(Duration::from_secs(0)));
+        assert_eq!(
+            cancellation.check().unwrap_err().kind(),
+            ErrorKind::TimeoutError
+        );
+
+        let cancellation = Cancellation::new(Some(Duration::from_secs(3600)));
+        let clone = cancellation.clone();
+        assert!(clone.check().is_ok());
+        cancellation.cancel();
+        assert_eq!(clone.check().unwrap_err().kind(), ErrorKind::CancelledError);
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{Error, ErrorKind, config::defaults::DEFAULT_BACKEND};
 
 impl Default for Backend {
-    /// Returns [`Backend::C`](enum.Backend.html#variant.C)
+    /// Returns [`Backend::C`](enum.Backend.html#variant.C), or
+    /// [`Backend::Rust`](enum.Backend.html#variant.Rust) if argonautica was built without the
+    /// `backend-c` feature
     fn default() -> Backend {
         DEFAULT_BACKEND
     }
@@ -9,9 +11,13 @@ impl Default for Backend {
 
 /// Enum representing the choice between a
 /// [C implementation](https://github.com/P-H-C/phc-winner-argon2/tree/20171227)
-/// of the Argon2 algorithm or a Rust implementation. *Currently only a C
-/// implemenation is supported. You will get an error if you choose the Rust backend.
-/// The intention is to write a Rust implementation in the future.*
+/// of the Argon2 algorithm or a Rust implementation. Both produce identical hashes. You can
+/// also let argonautica pick between the two at runtime with
+/// [`Backend::Auto`](enum.Backend.html#variant.Auto).
+///
+/// The C backend is compiled in by the `backend-c` feature, which is on by default. If you
+/// cannot build C code for your target (e.g. when cross-compiling), you can turn off default
+/// features and use the Rust backend only; choosing the C backend will then result in an error
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
@@ -20,20 +26,29 @@ pub enum Backend {
     /// [C implementation](https://github.com/P-H-C/phc-winner-argon2/tree/20171227)
     /// of the Argon2 algorithm
     C = 1,
-    /// Placeholder for a currently-unavailable but planned-for-the-future Rust backend.
-    /// *You will get an error if you use this backend now.*
+    /// Backend using a pure Rust implementation of the Argon2 algorithm, which does not
+    /// require a C compiler to build
     Rust = 2,
+    /// Picks a backend for the current machine the first time it is used, following a fixed
+    /// rule: the C backend, unless the Rust backend's
+    /// [`Implementation`](enum.Implementation.html) uses a higher SIMD level on your CPU
+    /// (AVX-512F above AVX2 above SSSE3 above SSE2 above none on x86, NEON above none on
+    /// ARM). Ties, which are the usual case, go to the C backend. No benchmark is run. If
+    /// argonautica was built without the `backend-c` feature, this is always the Rust backend
+    Auto = 3,
 }
 
 impl Backend {
     /// Performs the following mapping:
     /// * `1_u32` => `Ok(Backend::C)`<br/>
     /// * `2_u32` => `Ok(Backend::Rust)`<br/>
+    /// * `3_u32` => `Ok(Backend::Auto)`<br/>
     /// * anything else => an error
     pub fn from_u32(x: u32) -> Result<Backend, Error> {
         match x {
             1 => Ok(Backend::C),
             2 => Ok(Backend::Rust),
+            3 => Ok(Backend::Auto),
             _ => Err(Error::new(ErrorKind::BackendEncodeError).add_context(format!("Int: {}", x))),
         }
     }
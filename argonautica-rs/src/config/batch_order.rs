@@ -0,0 +1,54 @@
+impl Default for BatchOrder {
+    /// Returns [`BatchOrder::Input`](enum.BatchOrder.html#variant.Input)
+    fn default() -> BatchOrder {
+        BatchOrder::Input
+    }
+}
+
+/// Enum representing the order in which
+/// [`Hasher::hash_many`](../struct.Hasher.html#method.hash_many) and
+/// [`Verifier::verify_many`](../struct.Verifier.html#method.verify_many) yield their results.
+/// Either way, each result comes with the index of its input
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum BatchOrder {
+    /// Yields results in the order of the inputs. A slow item holds back the results after it,
+    /// though the items after it keep being hashed in the meantime
+    Input,
+    /// Yields results as soon as they are ready
+    Completion,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<BatchOrder>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<BatchOrder>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<BatchOrder>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<BatchOrder>();
+    }
+}
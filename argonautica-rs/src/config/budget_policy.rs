@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use crate::config::defaults::DEFAULT_BUDGET_POLICY;
+
+impl Default for BudgetPolicy {
+    /// Returns [`BudgetPolicy::Wait`](enum.BudgetPolicy.html#variant.Wait)
+    fn default() -> BudgetPolicy {
+        DEFAULT_BUDGET_POLICY
+    }
+}
+
+/// Enum representing what a hash does when the process-wide
+/// [memory budget](../memory_budget/index.html) cannot cover its memory yet. Either way, a
+/// hash that needs more memory than the whole budget fails right away with an
+/// [`ErrorKind::MemoryBudgetError`](../enum.ErrorKind.html#variant.MemoryBudgetError)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum BudgetPolicy {
+    /// Waits until other hashes free enough memory, or until the hash is cancelled or runs
+    /// past its [timeout](../struct.Hasher.html#method.configure_timeout)
+    Wait,
+    /// Fails with an
+    /// [`ErrorKind::MemoryBudgetError`](../enum.ErrorKind.html#variant.MemoryBudgetError)
+    /// right away
+    FailFast,
+    /// Waits like [`BudgetPolicy::Wait`](enum.BudgetPolicy.html#variant.Wait), but for at
+    /// most the given duration, after which it fails with an
+    /// [`ErrorKind::MemoryBudgetError`](../enum.ErrorKind.html#variant.MemoryBudgetError)
+    Timeout(Duration),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<BudgetPolicy>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<BudgetPolicy>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<BudgetPolicy>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<BudgetPolicy>();
+    }
+}
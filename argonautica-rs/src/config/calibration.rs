@@ -0,0 +1,213 @@
+#![cfg(feature = "std")]
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    Error, ErrorKind, Hasher,
+    config::{HasherConfig, Variant, Version, validate_threads},
+    input::Salt,
+};
+
+/// Number of hashes timed for each candidate configuration
+const SAMPLES: usize = 5;
+
+/// The result of [`HasherConfig::calibrate`](struct.HasherConfig.html#method.calibrate): the
+/// chosen [`HasherConfig`](struct.HasherConfig.html) and how long hashing with it took on the
+/// machine it was calibrated on. Serializable (with the `serde` feature), so you can calibrate
+/// once per instance type, store the result, and later create
+/// [`Hasher`](../struct.Hasher.html)s from it with
+/// [`Hasher::from_calibration`](../struct.Hasher.html#method.from_calibration)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Calibration {
+    config: HasherConfig,
+    samples: Vec<Duration>,
+    target: Duration,
+}
+
+impl Calibration {
+    /// Read-only access to the chosen [`HasherConfig`](struct.HasherConfig.html)
+    pub fn config(&self) -> &HasherConfig {
+        &self.config
+    }
+    /// Returns the time each of the timed hashes with the chosen configuration took, shortest
+    /// first
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+    /// Returns the target hashing time calibration aimed for
+    pub fn target(&self) -> Duration {
+        self.target
+    }
+    /// Returns the shortest hashing time with the chosen configuration
+    pub fn min(&self) -> Duration {
+        self.samples[0]
+    }
+    /// Returns the median hashing time with the chosen configuration
+    pub fn median(&self) -> Duration {
+        median(&self.samples)
+    }
+    /// Returns the longest hashing time with the chosen configuration
+    pub fn max(&self) -> Duration {
+        self.samples[self.samples.len() - 1]
+    }
+}
+
+pub(crate) fn calibrate(
+    target: Duration,
+    max_memory_size: u32,
+    threads: u32,
+) -> Result<Calibration, Error> {
+    validate_threads(threads)?;
+    let mut calibrator = Calibrator::new(threads);
+    // Memory makes attacks costlier than iterations do, so first find the largest memory size
+    // that fits in the target with a single iteration, then add iterations
+    let min_memory_size = (8 * threads).next_power_of_two();
+    if max_memory_size < min_memory_size {
+        return Err(
+            Error::new(ErrorKind::MemorySizeTooSmallError).add_context(format!(
+                "Threads: {}. Max memory size: {}",
+                threads, max_memory_size
+            )),
+        );
+    }
+    let mut memory_size = 1u32 << (31 - max_memory_size.leading_zeros());
+    let mut samples = calibrator.measure(memory_size, 1)?;
+    while median(&samples) > target && memory_size > min_memory_size {
+        memory_size /= 2;
+        samples = calibrator.measure(memory_size, 1)?;
+    }
+    let mut iterations = 1;
+    if median(&samples) < target {
+        // Hashing time grows about linearly with iterations, so start from that estimate and
+        // double it until it overshoots, then bisect
+        let estimate = target.as_nanos() / median(&samples).as_nanos().max(1);
+        let mut high = (estimate.min(u32::MAX as u128) as u32).max(2);
+        loop {
+            let high_samples = calibrator.measure(memory_size, high)?;
+            if median(&high_samples) > target {
+                break;
+            }
+            iterations = high;
+            samples = high_samples;
+            high = high.saturating_mul(2);
+        }
+        while high - iterations > 1 {
+            let middle = iterations + (high - iterations) / 2;
+            let middle_samples = calibrator.measure(memory_size, middle)?;
+            if median(&middle_samples) > target {
+                high = middle;
+            } else {
+                iterations = middle;
+                samples = middle_samples;
+            }
+        }
+    }
+    calibrator
+        .hasher
+        .configure_iterations(iterations)
+        .configure_memory_size(memory_size);
+    Ok(Calibration {
+        config: calibrator.hasher.config().clone(),
+        samples,
+        target,
+    })
+}
+
+struct Calibrator {
+    hasher: Hasher<'static>,
+}
+
+impl Calibrator {
+    fn new(threads: u32) -> Calibrator {
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_hash_len(32)
+            .configure_lanes(threads)
+            .configure_threads(threads)
+            .configure_variant(Variant::Argon2id)
+            .configure_version(Version::_0x13)
+            .with_salt(Salt::random(16))
+            .with_secret_key(&[0u8; 32][..]);
+        Calibrator { hasher }
+    }
+    /// Returns the sorted hashing times of `SAMPLES` hashes with the given parameters
+    fn measure(&mut self, memory_size: u32, iterations: u32) -> Result<Vec<Duration>, Error> {
+        self.hasher
+            .configure_iterations(iterations)
+            .configure_memory_size(memory_size);
+        let mut samples = Vec::with_capacity(SAMPLES);
+        for _ in 0..SAMPLES {
+            let start = Instant::now();
+            self.hasher.with_password("P@ssw0rd").hash_raw()?;
+            samples.push(start.elapsed());
+        }
+        samples.sort();
+        Ok(samples)
+    }
+}
+
+fn median(samples: &[Duration]) -> Duration {
+    samples[samples.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibrate() {
+        let target = Duration::from_millis(20);
+        let calibration = calibrate(target, 1_000, 1).unwrap();
+        let config = calibration.config();
+        assert!(config.memory_size() <= 512);
+        assert!(config.memory_size().is_power_of_two());
+        assert_eq!(config.lanes(), 1);
+        assert_eq!(config.threads(), 1);
+        assert_eq!(config.salt_len(), 16);
+        assert!(!config.opt_out_of_secret_key());
+        assert_eq!(calibration.samples().len(), SAMPLES);
+        assert!(calibration.min() <= calibration.median());
+        assert!(calibration.median() <= calibration.max());
+        assert_eq!(calibration.target(), target);
+        if config.iterations() > 1 || config.memory_size() > 8 {
+            assert!(calibration.median() <= target);
+        }
+
+        let e = calibrate(target, 4, 1).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::MemorySizeTooSmallError);
+        let e = calibrate(target, 1_000, 0).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ThreadsTooFewError);
+        let e = calibrate(target, u32::MAX, 1 << 29).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ThreadsTooManyError);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Calibration>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Calibration>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<Calibration>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<Calibration>();
+    }
+}
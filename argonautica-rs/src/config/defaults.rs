@@ -1,47 +1,103 @@
+#[cfg(feature = "std")]
 use futures::executor::ThreadPool;
+#[cfg(feature = "std")]
 use num_cpus;
 
-use crate::config::{Backend, Variant, Version};
+#[cfg(feature = "std")]
+use crate::config::BudgetPolicy;
+use crate::config::{Backend, LaneScheduling, Variant, Version};
 
 /// Returns a [`CpuPool`](https://docs.rs/futures-cpupool/0.1.8/futures_cpupool/struct.CpuPool.html)
 /// with threads equal to the number of logical cores on your machine
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn default_cpu_pool() -> ThreadPool {
     ThreadPool::new().expect("Failed to spawn thread pool")
 }
 
-#[cfg(feature = "serde")]
+#[cfg(all(feature = "serde", feature = "std"))]
 pub(crate) fn default_cpu_pool_serde() -> Option<ThreadPool> {
     None
 }
 
 /// Returns the number of logical cores on your machine
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn default_lanes() -> u32 {
     num_cpus::get() as u32
 }
 
+/// Returns `1` (argonautica was built without the `std` feature)
+#[cfg(not(feature = "std"))]
+#[inline(always)]
+pub fn default_lanes() -> u32 {
+    1
+}
+
 /// Returns the number of logical cores on your machine
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn default_threads() -> u32 {
     num_cpus::get() as u32
 }
 
+/// Returns `1` (argonautica was built without the `std` feature)
+#[cfg(not(feature = "std"))]
+#[inline(always)]
+pub fn default_threads() -> u32 {
+    1
+}
+
 /// [`Backend::C`](enum.Backend.html#variant.C)
+#[cfg(feature = "backend-c")]
 pub const DEFAULT_BACKEND: Backend = Backend::C;
 
+/// [`Backend::Rust`](enum.Backend.html#variant.Rust) (argonautica was built without the
+/// `backend-c` feature)
+#[cfg(not(feature = "backend-c"))]
+pub const DEFAULT_BACKEND: Backend = Backend::Rust;
+
+/// [`BudgetPolicy::Wait`](enum.BudgetPolicy.html#variant.Wait)
+#[cfg(feature = "std")]
+pub const DEFAULT_BUDGET_POLICY: BudgetPolicy = BudgetPolicy::Wait;
+
 /// `32_u32`
 pub const DEFAULT_HASH_LEN: u32 = 32;
 
 /// `192_u32`
 pub const DEFAULT_ITERATIONS: u32 = 192;
 
+/// [`LaneScheduling::Threads`](enum.LaneScheduling.html#variant.Threads)
+pub const DEFAULT_LANE_SCHEDULING: LaneScheduling = LaneScheduling::Threads;
+
+/// `false`
+pub const DEFAULT_LENIENT_DECODING: bool = false;
+
+/// `1024_u32`
+pub const DEFAULT_MAX_HASH_LEN: u32 = 1_024;
+
+/// `1024_u32`
+pub const DEFAULT_MAX_ITERATIONS: u32 = 1_024;
+
+/// `256_u32`
+pub const DEFAULT_MAX_LANES: u32 = 256;
+
+/// `2097152_u32` (2 GiB)
+pub const DEFAULT_MAX_MEMORY_SIZE: u32 = 2_097_152;
+
 /// `4096_u32`
 pub const DEFAULT_MEMORY_SIZE: u32 = 4_096;
 
+/// `8_u32`
+pub const DEFAULT_MIN_SALT_LEN: u32 = 8;
+
 /// `false`
 pub const DEFAULT_OPT_OUT_OF_SECRET_KEY: bool = false;
 
+/// `1024_usize`
+#[cfg(feature = "std")]
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1_024;
+
 /// `false`
 pub const DEFAULT_PASSWORD_CLEARING: bool = false;
 
@@ -51,6 +107,16 @@ pub const DEFAULT_SALT_LEN: u32 = 32;
 /// `false`
 pub const DEFAULT_SECRET_KEY_CLEARING: bool = false;
 
+/// `false`
+#[cfg(feature = "std")]
+pub const DEFAULT_SECURE_MEMORY: bool = false;
+
+/// `false`
+pub const DEFAULT_SHADOW_MODE: bool = false;
+
+/// `false`
+pub const DEFAULT_STRICT_LINT: bool = false;
+
 /// [`Variant::Argon2id`](enum.Variant.html#variant.Argon2id)
 pub const DEFAULT_VARIANT: Variant = Variant::Argon2id;
 
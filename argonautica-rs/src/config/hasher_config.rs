@@ -1,12 +1,37 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
 use futures::executor::ThreadPool;
 
+#[cfg(feature = "std")]
+use crate::{allocator::SharedAllocator, executor::SharedExecutor};
+
+#[cfg(feature = "std")]
+use crate::config::{BudgetPolicy, Calibration, calibrate};
+
 use crate::{
     Error, ErrorKind,
-    config::{Backend, Flags, Variant, Version, defaults::*},
+    backend::{implementation, resolve_config},
+    config::{
+        Backend, Flags, Implementation, LaneScheduling, LintKind, LintSeverity, LintWarning,
+        RngCallback, ShadowCallback, Variant, Version, defaults::*,
+    },
 };
 
 const PANIC_WARNING: &str = "Your program will error if you use this configuration";
 
+/// Minimum (memory size, iterations) pairs recommended by the
+/// [OWASP Password Storage Cheat Sheet](https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id)
+const OWASP_MIN_COSTS: [(u32, u32); 5] = [
+    (47_104, 1),
+    (19_456, 2),
+    (12_288, 3),
+    (9_216, 4),
+    (7_168, 5),
+];
+
 /// Read-only configuration for [`Hasher`](../struct.Hasher.html). Can be obtained by calling
 /// the [`config`](../struct.Hasher.html#method.config) method on an instance of
 /// [`Hasher`](../struct.Hasher.html)
@@ -14,7 +39,17 @@ const PANIC_WARNING: &str = "Your program will error if you use this configurati
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct HasherConfig {
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    allocator: Option<SharedAllocator>,
     backend: Backend,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    budget_policy: BudgetPolicy,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    executor: Option<SharedExecutor>,
+    #[cfg(feature = "std")]
     #[cfg_attr(
         feature = "serde",
         serde(
@@ -26,12 +61,34 @@ pub struct HasherConfig {
     thread_pool: Option<ThreadPool>,
     hash_len: u32,
     iterations: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    key_id: Option<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    lane_scheduling: LaneScheduling,
     lanes: u32,
     memory_size: u32,
     opt_out_of_secret_key: bool,
     password_clearing: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    rng: Option<RngCallback>,
+    #[cfg_attr(feature = "serde", serde(default = "default_salt_is_random"))]
+    salt_is_random: bool,
+    #[cfg_attr(feature = "serde", serde(default = "default_salt_len"))]
+    salt_len: u32,
     secret_key_clearing: bool,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    secure_memory: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    shadow_mode: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    shadow_callback: Option<ShadowCallback>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    strict_lint: bool,
     threads: u32,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    timeout: Option<Duration>,
     variant: Variant,
     version: Version,
 }
@@ -41,6 +98,12 @@ impl HasherConfig {
     pub fn backend(&self) -> Backend {
         self.backend
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn budget_policy(&self) -> BudgetPolicy {
+        self.budget_policy
+    }
+    #[cfg(feature = "std")]
     #[allow(missing_docs)]
     pub fn thread_pool(&self) -> Option<ThreadPool> {
         self.thread_pool.clone()
@@ -53,6 +116,16 @@ impl HasherConfig {
     pub fn iterations(&self) -> u32 {
         self.iterations
     }
+    /// Returns the ID of the active key of the
+    /// [`SecretKeyRing`](../input/struct.SecretKeyRing.html) the
+    /// [`Hasher`](../struct.Hasher.html) was provided with, if any
+    pub fn key_id(&self) -> Option<&[u8]> {
+        self.key_id.as_deref()
+    }
+    #[allow(missing_docs)]
+    pub fn lane_scheduling(&self) -> LaneScheduling {
+        self.lane_scheduling
+    }
     #[allow(missing_docs)]
     pub fn lanes(&self) -> u32 {
         self.lanes
@@ -69,14 +142,42 @@ impl HasherConfig {
     pub fn password_clearing(&self) -> bool {
         self.password_clearing
     }
+    /// Returns whether the [`Salt`](../input/struct.Salt.html) the
+    /// [`Hasher`](../struct.Hasher.html) was provided with is random
+    pub fn salt_is_random(&self) -> bool {
+        self.salt_is_random
+    }
+    /// Returns the length (in bytes) of the [`Salt`](../input/struct.Salt.html) the
+    /// [`Hasher`](../struct.Hasher.html) was provided with
+    pub fn salt_len(&self) -> u32 {
+        self.salt_len
+    }
     #[allow(missing_docs)]
     pub fn secret_key_clearing(&self) -> bool {
         self.secret_key_clearing
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn secure_memory(&self) -> bool {
+        self.secure_memory
+    }
+    #[allow(missing_docs)]
+    pub fn shadow_mode(&self) -> bool {
+        self.shadow_mode
+    }
+    #[allow(missing_docs)]
+    pub fn strict_lint(&self) -> bool {
+        self.strict_lint
+    }
     #[allow(missing_docs)]
     pub fn threads(&self) -> u32 {
         self.threads
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
     #[allow(missing_docs)]
     pub fn variant(&self) -> Variant {
         self.variant
@@ -85,21 +186,127 @@ impl HasherConfig {
     pub fn version(&self) -> Version {
         self.version
     }
+    /// Returns the [`Implementation`](enum.Implementation.html) of the Argon2 compression
+    /// function that computes hashes with this configuration on this machine, i.e. that of the
+    /// configured [`Backend`](enum.Backend.html), or of the Rust backend if the C backend is
+    /// configured but the lanes run on a thread pool (see
+    /// [`LaneScheduling`](enum.LaneScheduling.html)). The fastest implementation supported by
+    /// your CPU is selected the first time a backend is used and does not change afterwards
+    pub fn implementation(&self) -> Implementation {
+        implementation(resolve_config(self))
+    }
+    /// Searches for the strongest Argon2id configuration that hashes in at most `target` on
+    /// the current machine, using at most `max_memory_size` kibibytes of memory and `threads`
+    /// threads (and lanes). It first picks the largest power-of-two memory size that fits in
+    /// `target` with a single iteration, then the largest number of iterations that still
+    /// fits, timing several hashes per candidate and comparing their median against
+    /// `target`. If even the smallest memory size with one iteration takes longer than
+    /// `target`, that configuration is returned.
+    ///
+    /// Calibration hashes repeatedly, so expect it to take a few dozen times `target`. The
+    /// returned [`Calibration`](struct.Calibration.html) holds the chosen configuration and
+    /// the timings measured for it; create [`Hasher`](../struct.Hasher.html)s from it with
+    /// [`Hasher::from_calibration`](../struct.Hasher.html#method.from_calibration).
+    /// <i>Requires the `std` feature</i>
+    #[cfg(feature = "std")]
+    pub fn calibrate(
+        target: Duration,
+        max_memory_size: u32,
+        threads: u32,
+    ) -> Result<Calibration, Error> {
+        calibrate(target, max_memory_size, threads)
+    }
+    /// Checks the configuration against the recommendations of
+    /// [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106.html#name-parameter-choice) and the
+    /// [OWASP Password Storage Cheat Sheet](https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id)
+    /// and returns a [`LintWarning`](struct.LintWarning.html) for each one it falls short of,
+    /// most severe first. An empty `Vec` means no weaknesses were found. Unlike the limits
+    /// enforced when hashing (e.g. that the memory size be at least 8 times the number of
+    /// lanes), these are recommendations; to have hashing fail when any of them is not met, see
+    /// [`Hasher::configure_strict_lint`](../struct.Hasher.html#method.configure_strict_lint)
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let mut warn = |kind, severity| warnings.push(LintWarning::new(kind, severity));
+        match self.variant {
+            Variant::Argon2d => warn(LintKind::VariantArgon2d, LintSeverity::High),
+            Variant::Argon2i => warn(LintKind::VariantArgon2i, LintSeverity::Low),
+            Variant::Argon2id => (),
+        }
+        if self.version == Version::_0x10 {
+            warn(LintKind::VersionOutdated, LintSeverity::Medium);
+        }
+        let meets_owasp = OWASP_MIN_COSTS.iter().any(|&(memory_size, iterations)| {
+            self.memory_size >= memory_size && self.iterations >= iterations
+        });
+        if !meets_owasp {
+            // Less work in total than the cheapest OWASP recommendation does in one pass
+            let cost = u64::from(self.memory_size) * u64::from(self.iterations);
+            let min_memory_size = OWASP_MIN_COSTS
+                .iter()
+                .map(|&(memory_size, _)| memory_size)
+                .min()
+                .unwrap_or(0);
+            if cost < u64::from(min_memory_size) {
+                warn(LintKind::CostTooLow, LintSeverity::High);
+            } else {
+                warn(LintKind::CostTooLow, LintSeverity::Medium);
+            }
+        }
+        if !self.salt_is_random {
+            warn(LintKind::DeterministicSalt, LintSeverity::High);
+        }
+        if self.salt_len < 16 {
+            warn(LintKind::SaltTooShort, LintSeverity::Medium);
+        }
+        if self.hash_len < 8 {
+            warn(LintKind::HashLenTooShort, LintSeverity::High);
+        } else if self.hash_len < 16 {
+            warn(LintKind::HashLenTooShort, LintSeverity::Medium);
+        }
+        if self.opt_out_of_secret_key {
+            warn(LintKind::NoSecretKey, LintSeverity::Low);
+        }
+        warnings.sort_by(|a, b| {
+            b.severity()
+                .cmp(&a.severity())
+                .then(a.kind().cmp(&b.kind()))
+        });
+        warnings
+    }
 }
 
 impl HasherConfig {
     pub(crate) fn default() -> HasherConfig {
         HasherConfig {
+            #[cfg(feature = "std")]
+            allocator: None,
             backend: Backend::default(),
+            #[cfg(feature = "std")]
+            budget_policy: DEFAULT_BUDGET_POLICY,
+            #[cfg(feature = "std")]
+            executor: None,
+            #[cfg(feature = "std")]
             thread_pool: None,
             hash_len: DEFAULT_HASH_LEN,
             iterations: DEFAULT_ITERATIONS,
+            key_id: None,
+            lane_scheduling: DEFAULT_LANE_SCHEDULING,
             lanes: default_lanes(),
             memory_size: DEFAULT_MEMORY_SIZE,
             opt_out_of_secret_key: DEFAULT_OPT_OUT_OF_SECRET_KEY,
             password_clearing: DEFAULT_PASSWORD_CLEARING,
+            rng: None,
+            salt_is_random: true,
+            salt_len: DEFAULT_SALT_LEN,
             secret_key_clearing: DEFAULT_SECRET_KEY_CLEARING,
+            #[cfg(feature = "std")]
+            secure_memory: DEFAULT_SECURE_MEMORY,
+            shadow_mode: DEFAULT_SHADOW_MODE,
+            shadow_callback: None,
+            strict_lint: DEFAULT_STRICT_LINT,
             threads: default_threads(),
+            #[cfg(feature = "std")]
+            timeout: None,
             variant: Variant::default(),
             version: Version::default(),
         }
@@ -121,6 +328,27 @@ impl HasherConfig {
         });
         self.backend = backend;
     }
+    #[cfg(feature = "std")]
+    pub(crate) fn set_allocator(&mut self, allocator: SharedAllocator) {
+        self.allocator = Some(allocator);
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn allocator(&self) -> Option<&SharedAllocator> {
+        self.allocator.as_ref()
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn set_budget_policy(&mut self, budget_policy: BudgetPolicy) {
+        self.budget_policy = budget_policy;
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn set_executor(&mut self, executor: SharedExecutor) {
+        self.executor = Some(executor);
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn executor(&self) -> Option<&SharedExecutor> {
+        self.executor.as_ref()
+    }
+    #[cfg(feature = "std")]
     pub(crate) fn set_thread_pool(&mut self, thread_pool: ThreadPool) {
         self.thread_pool = Some(thread_pool);
     }
@@ -136,6 +364,12 @@ impl HasherConfig {
         });
         self.iterations = iterations;
     }
+    pub(crate) fn set_key_id(&mut self, key_id: Option<Vec<u8>>) {
+        self.key_id = key_id;
+    }
+    pub(crate) fn set_lane_scheduling(&mut self, lane_scheduling: LaneScheduling) {
+        self.lane_scheduling = lane_scheduling;
+    }
     pub(crate) fn set_lanes(&mut self, lanes: u32) {
         validate_lanes(lanes).unwrap_or_else(|e| {
             warn!("{}. {}.", e, PANIC_WARNING);
@@ -154,15 +388,51 @@ impl HasherConfig {
     pub(crate) fn set_password_clearing(&mut self, boolean: bool) {
         self.password_clearing = boolean;
     }
+    pub(crate) fn set_rng(&mut self, rng: RngCallback) {
+        self.rng = Some(rng);
+    }
+    pub(crate) fn rng(&self) -> Option<&RngCallback> {
+        self.rng.as_ref()
+    }
+    pub(crate) fn set_salt_is_random(&mut self, boolean: bool) {
+        self.salt_is_random = boolean;
+    }
+    pub(crate) fn set_salt_len(&mut self, salt_len: u32) {
+        self.salt_len = salt_len;
+    }
     pub(crate) fn set_secret_key_clearing(&mut self, boolean: bool) {
         self.secret_key_clearing = boolean;
     }
+    #[cfg(feature = "std")]
+    pub(crate) fn set_secure_memory(&mut self, boolean: bool) {
+        self.secure_memory = boolean;
+    }
+    pub(crate) fn set_shadow_mode(&mut self, boolean: bool) {
+        validate_shadow_mode(boolean).unwrap_or_else(|e| {
+            warn!("{}. {}.", e, PANIC_WARNING);
+        });
+        self.shadow_mode = boolean;
+    }
+    pub(crate) fn set_shadow_callback(&mut self, shadow_callback: ShadowCallback) {
+        self.shadow_callback = Some(shadow_callback);
+    }
+    #[cfg_attr(not(feature = "backend-c"), allow(dead_code))]
+    pub(crate) fn shadow_callback(&self) -> Option<&ShadowCallback> {
+        self.shadow_callback.as_ref()
+    }
+    pub(crate) fn set_strict_lint(&mut self, boolean: bool) {
+        self.strict_lint = boolean;
+    }
     pub(crate) fn set_threads(&mut self, threads: u32) {
         validate_threads(threads).unwrap_or_else(|e| {
             warn!("{}. {}.", e, PANIC_WARNING);
         });
         self.threads = threads;
     }
+    #[cfg(feature = "std")]
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
     pub(crate) fn set_variant(&mut self, variant: Variant) {
         self.variant = variant;
     }
@@ -182,15 +452,35 @@ impl HasherConfig {
         validate_iterations(self.iterations)?;
         validate_lanes(self.lanes)?;
         validate_memory_size(self.lanes, self.memory_size)?;
+        validate_shadow_mode(self.shadow_mode)?;
         validate_threads(self.threads)?;
+        if self.strict_lint {
+            validate_lint(&self.lint())?;
+        }
         Ok(())
     }
 }
 
+#[cfg(feature = "serde")]
+fn default_salt_is_random() -> bool {
+    true
+}
+
+#[cfg(feature = "serde")]
+fn default_salt_len() -> u32 {
+    DEFAULT_SALT_LEN
+}
+
 fn validate_backend(backend: Backend) -> Result<(), Error> {
     match backend {
+        #[cfg(feature = "backend-c")]
         Backend::C => (),
-        Backend::Rust => return Err(Error::new(ErrorKind::BackendUnsupportedError)),
+        #[cfg(not(feature = "backend-c"))]
+        Backend::C => {
+            return Err(Error::new(ErrorKind::BackendUnsupportedError)
+                .add_context(format!("Backend: {:?}", backend)));
+        }
+        Backend::Rust | Backend::Auto => (),
     }
     Ok(())
 }
@@ -225,19 +515,36 @@ fn validate_lanes(lanes: u32) -> Result<(), Error> {
     Ok(())
 }
 
+fn validate_lint(warnings: &[LintWarning]) -> Result<(), Error> {
+    if !warnings.is_empty() {
+        let kinds = warnings
+            .iter()
+            .map(|warning| format!("{:?}", warning.kind()))
+            .collect::<Vec<_>>();
+        return Err(
+            Error::new(ErrorKind::LintError).add_context(format!("Warnings: {}", kinds.join(", ")))
+        );
+    }
+    Ok(())
+}
+
 fn validate_memory_size(lanes: u32, memory_size: u32) -> Result<(), Error> {
     if memory_size < 8 * lanes {
         return Err(Error::new(ErrorKind::MemorySizeTooSmallError)
             .add_context(format!("Lanes: {}. Memory size: {}", lanes, memory_size)));
     }
-    if !(memory_size.is_power_of_two()) {
-        return Err(Error::new(ErrorKind::MemorySizeInvalidError)
-            .add_context(format!("Memory size: {}", memory_size)));
+    Ok(())
+}
+
+fn validate_shadow_mode(shadow_mode: bool) -> Result<(), Error> {
+    if shadow_mode && cfg!(not(feature = "backend-c")) {
+        return Err(Error::new(ErrorKind::BackendUnsupportedError)
+            .add_context("Shadow mode compares the Rust backend against the C backend"));
     }
     Ok(())
 }
 
-fn validate_threads(threads: u32) -> Result<(), Error> {
+pub(crate) fn validate_threads(threads: u32) -> Result<(), Error> {
     if threads == 0 {
         return Err(
             Error::new(ErrorKind::ThreadsTooFewError).add_context(format!("Threads: {}", threads))
@@ -255,6 +562,63 @@ fn validate_threads(threads: u32) -> Result<(), Error> {
 mod tests {
     use super::*;
 
+    fn lint_kinds(config: &HasherConfig) -> Vec<(LintKind, LintSeverity)> {
+        config
+            .lint()
+            .iter()
+            .map(|warning| (warning.kind(), warning.severity()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        let mut config = HasherConfig::default();
+        config.set_memory_size(65_536);
+        config.set_iterations(3);
+        assert!(config.lint().is_empty());
+        config.set_memory_size(32_768);
+        config.set_iterations(2);
+        assert!(config.lint().is_empty());
+
+        config.set_memory_size(4_096);
+        config.set_iterations(192);
+        assert_eq!(
+            lint_kinds(&config),
+            vec![(LintKind::CostTooLow, LintSeverity::Medium)]
+        );
+
+        config.set_memory_size(8);
+        config.set_iterations(1);
+        config.set_lanes(1);
+        config.set_variant(Variant::Argon2d);
+        config.set_version(Version::_0x10);
+        config.set_salt_is_random(false);
+        config.set_salt_len(8);
+        config.set_hash_len(4);
+        config.set_opt_out_of_secret_key(true);
+        assert_eq!(
+            lint_kinds(&config),
+            vec![
+                (LintKind::CostTooLow, LintSeverity::High),
+                (LintKind::DeterministicSalt, LintSeverity::High),
+                (LintKind::HashLenTooShort, LintSeverity::High),
+                (LintKind::VariantArgon2d, LintSeverity::High),
+                (LintKind::SaltTooShort, LintSeverity::Medium),
+                (LintKind::VersionOutdated, LintSeverity::Medium),
+                (LintKind::NoSecretKey, LintSeverity::Low),
+            ]
+        );
+        config.set_variant(Variant::Argon2i);
+        config.set_hash_len(12);
+        assert!(lint_kinds(&config).contains(&(LintKind::HashLenTooShort, LintSeverity::Medium)));
+        assert!(lint_kinds(&config).contains(&(LintKind::VariantArgon2i, LintSeverity::Low)));
+
+        assert!(config.validate().is_ok());
+        config.set_strict_lint(true);
+        let e = config.validate().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::LintError);
+    }
+
     #[test]
     fn test_send() {
         fn assert_send<T: Send>() {}
@@ -0,0 +1,78 @@
+/// Enum representing the implementation of the Argon2 compression function that a
+/// [`Backend`](enum.Backend.html) uses on the current machine. Each backend picks the fastest
+/// implementation your CPU supports at runtime, so a single binary runs everywhere while still
+/// taking advantage of SIMD instructions where they are available. Use
+/// [`HasherConfig::implementation`](struct.HasherConfig.html#method.implementation) to find out
+/// which one is active. The derived ordering follows declaration order and is not a ranking by
+/// speed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub enum Implementation {
+    /// Portable implementation that does not use any SIMD instructions
+    Portable = 1,
+    /// Implementation using SSE2 instructions (x86 and x86_64 only)
+    Sse2 = 2,
+    /// Implementation using SSSE3 instructions (x86 and x86_64 only)
+    Ssse3 = 3,
+    /// Implementation using AVX2 instructions (x86 and x86_64 only)
+    Avx2 = 4,
+    /// Implementation using AVX-512F instructions (x86 and x86_64 only)
+    Avx512F = 5,
+    /// Implementation using NEON instructions (aarch64 only)
+    Neon = 6,
+}
+
+impl Implementation {
+    /// Performs the following mapping:
+    /// * `Implementation::Portable` => `"portable"`<br/>
+    /// * `Implementation::Sse2` => `"sse2"`<br/>
+    /// * `Implementation::Ssse3` => `"ssse3"`<br/>
+    /// * `Implementation::Avx2` => `"avx2"`<br/>
+    /// * `Implementation::Avx512F` => `"avx512f"`<br/>
+    /// * `Implementation::Neon` => `"neon"`
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Implementation::Portable => "portable",
+            Implementation::Sse2 => "sse2",
+            Implementation::Ssse3 => "ssse3",
+            Implementation::Avx2 => "avx2",
+            Implementation::Avx512F => "avx512f",
+            Implementation::Neon => "neon",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Implementation>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Implementation>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<Implementation>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<Implementation>();
+    }
+}
@@ -0,0 +1,71 @@
+use crate::config::defaults::DEFAULT_LANE_SCHEDULING;
+
+impl Default for LaneScheduling {
+    /// Returns [`LaneScheduling::Threads`](enum.LaneScheduling.html#variant.Threads)
+    fn default() -> LaneScheduling {
+        DEFAULT_LANE_SCHEDULING
+    }
+}
+
+/// Enum representing how a hash with more than one thread runs its lanes in parallel.
+///
+/// Argon2 fills each lane of a slice independently, so with `threads > 1` both backends by
+/// default spawn (and join) up to `threads` fresh threads for every slice. That is fine for
+/// the occasional hash, but when many hashes run at once on a thread pool, e.g. through the
+/// non-blocking methods, each of them adds its own threads and the machine ends up
+/// oversubscribed. [`LaneScheduling::Pool`](enum.LaneScheduling.html#variant.Pool) and
+/// [`LaneScheduling::Adaptive`](enum.LaneScheduling.html#variant.Adaptive) instead run the
+/// lanes as tasks on the thread pool the [`Hasher`](../struct.Hasher.html) uses (its
+/// executor, its thread pool or else the global thread pool of the
+/// [`pool`](../pool/index.html) module), with the hashing thread filling lanes as well.
+///
+/// The C implementation always spawns its own threads, so hashes with these two options and
+/// more than one thread run on the Rust backend, which produces identical hashes. <i>Without
+/// the `std` feature, hashing always runs on a single thread and this option has no effect</i>
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum LaneScheduling {
+    /// Spawns up to `threads` threads for every slice
+    Threads,
+    /// Runs each slice on the hashing thread plus up to `threads - 1` tasks on the thread pool
+    Pool,
+    /// Same as [`LaneScheduling::Pool`](enum.LaneScheduling.html#variant.Pool), except that
+    /// it watches how many of its tasks actually got a thread: while the pool has idle
+    /// threads, a hash uses up to `threads` of them; as the pool saturates, it falls back to
+    /// running on the hashing thread alone
+    Adaptive,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<LaneScheduling>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<LaneScheduling>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<LaneScheduling>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<LaneScheduling>();
+    }
+}
@@ -0,0 +1,150 @@
+use core::fmt;
+
+/// Enum representing the weaknesses [`HasherConfig::lint`](struct.HasherConfig.html#method.lint)
+/// looks for. All of them are legal Argon2 configurations, which is why
+/// [`Hasher`](../struct.Hasher.html) accepts them unless it is configured with
+/// [`configure_strict_lint`](../struct.Hasher.html#method.configure_strict_lint).
+/// [`Hasher::fast_but_insecure`](../struct.Hasher.html#method.fast_but_insecure) triggers
+/// most of them
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub enum LintKind {
+    /// Memory size and iterations are below every cost recommended by OWASP
+    CostTooLow,
+
+    /// The salt is deterministic, so equal passwords have equal hashes
+    DeterministicSalt,
+
+    /// The hash is shorter than 16 bytes
+    HashLenTooShort,
+
+    /// There is no secret key
+    NoSecretKey,
+
+    /// The salt is shorter than 16 bytes
+    SaltTooShort,
+
+    /// The variant is [`Argon2d`](enum.Variant.html#variant.Argon2d)
+    VariantArgon2d,
+
+    /// The variant is [`Argon2i`](enum.Variant.html#variant.Argon2i)
+    VariantArgon2i,
+
+    /// The version is [`0x10`](enum.Version.html#variant._0x10)
+    VersionOutdated,
+}
+
+/// Enum representing how serious a [`LintWarning`](struct.LintWarning.html) is
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum LintSeverity {
+    /// Weaker than recommended, but not by much
+    Low,
+
+    /// Noticeably weaker than recommended
+    Medium,
+
+    /// Undermines the protection Argon2 is meant to provide
+    High,
+}
+
+/// A weakness found by [`HasherConfig::lint`](struct.HasherConfig.html#method.lint)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct LintWarning {
+    pub(crate) kind: LintKind,
+    pub(crate) severity: LintSeverity,
+    pub(crate) message: &'static str,
+}
+
+impl LintWarning {
+    #[allow(missing_docs)]
+    pub fn kind(&self) -> LintKind {
+        self.kind
+    }
+    #[allow(missing_docs)]
+    pub fn severity(&self) -> LintSeverity {
+        self.severity
+    }
+    /// Returns a description of the weakness and of the recommendation it falls short of
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.severity, self.message)
+    }
+}
+
+impl LintWarning {
+    pub(crate) fn new(kind: LintKind, severity: LintSeverity) -> LintWarning {
+        let message = match kind {
+            LintKind::CostTooLow => {
+                "Memory size and iterations are below every minimum recommended by OWASP \
+                 (e.g. 19456 kibibytes with 2 iterations, or 7168 kibibytes with 5 iterations)"
+            }
+            LintKind::DeterministicSalt => {
+                "The salt is deterministic. Equal passwords produce equal hashes, which \
+                 allows precomputation attacks. Use a random salt"
+            }
+            LintKind::HashLenTooShort => {
+                "The hash is shorter than 16 bytes. RFC 9106 recommends 32 bytes"
+            }
+            LintKind::NoSecretKey => {
+                "There is no secret key. OWASP recommends a secret key (pepper) stored apart \
+                 from the hashes"
+            }
+            LintKind::SaltTooShort => {
+                "The salt is shorter than 16 bytes. RFC 9106 recommends 16 bytes"
+            }
+            LintKind::VariantArgon2d => {
+                "Argon2d uses data-dependent memory access and is vulnerable to side-channel \
+                 attacks. RFC 9106 recommends Argon2id for password hashing"
+            }
+            LintKind::VariantArgon2i => {
+                "Argon2i is more vulnerable to tradeoff attacks than Argon2id. RFC 9106 \
+                 recommends Argon2id for password hashing"
+            }
+            LintKind::VersionOutdated => {
+                "Version 0x10 predates the fix for a memory-saving attack. RFC 9106 specifies \
+                 version 0x13"
+            }
+        };
+        LintWarning {
+            kind,
+            severity,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<LintWarning>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<LintWarning>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<LintWarning>();
+    }
+}
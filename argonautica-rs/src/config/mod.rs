@@ -1,15 +1,44 @@
 //! Enums and defaults for Argon2 configuration options (e.g. `hash_len`,
 //! [`Variant`](config/enum.Variant.html), [`Version`](config/enum.Version.html), etc.)
 mod backend;
+mod batch_order;
+#[cfg(feature = "std")]
+mod budget_policy;
+mod calibration;
 pub(crate) mod defaults;
 mod flags;
 mod hasher_config;
+mod implementation;
+mod lane_scheduling;
+mod lint;
+mod preset;
+mod rng_callback;
+mod shadow_callback;
 mod variant;
 mod verifier_config;
+mod verifier_limits;
 mod version;
 
-pub(crate) use self::flags::Flags;
+#[cfg(feature = "std")]
+pub use self::budget_policy::BudgetPolicy;
+#[cfg(feature = "std")]
+pub use self::calibration::Calibration;
+#[cfg(feature = "std")]
+pub(crate) use self::calibration::calibrate;
+#[cfg(feature = "std")]
+pub(crate) use self::hasher_config::validate_threads;
 pub use self::{
-    backend::Backend, defaults::*, hasher_config::HasherConfig, variant::Variant,
-    verifier_config::VerifierConfig, version::Version,
+    backend::Backend,
+    batch_order::BatchOrder,
+    defaults::*,
+    hasher_config::HasherConfig,
+    implementation::Implementation,
+    lane_scheduling::LaneScheduling,
+    lint::{LintKind, LintSeverity, LintWarning},
+    preset::Preset,
+    variant::Variant,
+    verifier_config::VerifierConfig,
+    verifier_limits::VerifierLimits,
+    version::Version,
 };
+pub(crate) use self::{flags::Flags, rng_callback::RngCallback, shadow_callback::ShadowCallback};
@@ -0,0 +1,128 @@
+/// Enum representing documented sets of Argon2id parameters, for use with
+/// [`Hasher::from_preset`](../struct.Hasher.html#method.from_preset).
+///
+/// The [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106.html#name-parameter-choice) presets
+/// are the two options recommended by the RFC. The OWASP presets follow the
+/// [OWASP Password Storage Cheat Sheet](https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id),
+/// which lists equally strong profiles that trade memory for iterations; each OWASP preset
+/// uses exactly the iterations and memory size of its profile. All presets use 1 lane except
+/// the RFC 9106 ones, which use 4, a 16-byte salt and a 32-byte hash.
+///
+/// [`memory_footprint`](enum.Preset.html#method.memory_footprint) returns the memory each
+/// hash (or verification) allocates, which is what limits how many can run at once
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub enum Preset {
+    /// RFC 9106 first recommended option: 1 iteration, 4 lanes, 2 GiB of memory. Use it if
+    /// you can afford 2 GiB per hash
+    Rfc9106First,
+
+    /// RFC 9106 second recommended option: 3 iterations, 4 lanes, 64 MiB of memory. Use it
+    /// if memory is constrained
+    Rfc9106Second,
+
+    /// OWASP profile with 1 iteration and 46 MiB of memory
+    OwaspT1,
+
+    /// OWASP profile with 2 iterations and 19 MiB of memory
+    OwaspT2,
+
+    /// OWASP profile with 3 iterations and 12 MiB of memory
+    OwaspT3,
+
+    /// OWASP profile with 4 iterations and 9 MiB of memory
+    OwaspT4,
+
+    /// OWASP profile with 5 iterations and 7 MiB of memory
+    OwaspT5,
+}
+
+impl Preset {
+    /// Returns the hash length in bytes
+    pub fn hash_len(&self) -> u32 {
+        32
+    }
+    /// Returns the number of iterations
+    pub fn iterations(&self) -> u32 {
+        match *self {
+            Preset::Rfc9106First => 1,
+            Preset::Rfc9106Second => 3,
+            Preset::OwaspT1 => 1,
+            Preset::OwaspT2 => 2,
+            Preset::OwaspT3 => 3,
+            Preset::OwaspT4 => 4,
+            Preset::OwaspT5 => 5,
+        }
+    }
+    /// Returns the number of lanes
+    pub fn lanes(&self) -> u32 {
+        match *self {
+            Preset::Rfc9106First | Preset::Rfc9106Second => 4,
+            _ => 1,
+        }
+    }
+    /// Returns the memory size in kibibytes
+    pub fn memory_size(&self) -> u32 {
+        match *self {
+            Preset::Rfc9106First => 2_097_152,
+            Preset::Rfc9106Second => 65_536,
+            Preset::OwaspT1 => 47_104,
+            Preset::OwaspT2 => 19_456,
+            Preset::OwaspT3 => 12_288,
+            Preset::OwaspT4 => 9_216,
+            Preset::OwaspT5 => 7_168,
+        }
+    }
+    /// Returns the number of bytes of memory each hash or verification allocates, i.e.
+    /// the memory size in bytes
+    pub fn memory_footprint(&self) -> u64 {
+        u64::from(self.memory_size()) * 1024
+    }
+    /// Returns the salt length in bytes
+    pub fn salt_len(&self) -> u32 {
+        16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_footprint() {
+        assert_eq!(Preset::Rfc9106First.memory_footprint(), 2 << 30);
+        assert_eq!(Preset::Rfc9106Second.memory_footprint(), 64 << 20);
+        assert_eq!(Preset::OwaspT1.memory_footprint(), 46 << 20);
+        assert_eq!(Preset::OwaspT5.memory_footprint(), 7 << 20);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Preset>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Preset>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<Preset>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<Preset>();
+    }
+}
@@ -0,0 +1,30 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::Error;
+
+impl fmt::Debug for RngCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RngCallback")
+    }
+}
+
+type FillBytes = dyn Fn(&mut [u8]) -> Result<(), Error> + Send + Sync;
+
+/// Callback that fills a buffer with cryptographically-secure random bytes, used to generate
+/// random salts instead of `OsRng`. Cheap to clone so that it can travel with a
+/// [`HasherConfig`](struct.HasherConfig.html) to other threads
+#[derive(Clone)]
+pub(crate) struct RngCallback(Arc<FillBytes>);
+
+impl RngCallback {
+    pub(crate) fn new<F>(callback: F) -> RngCallback
+    where
+        F: Fn(&mut [u8]) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        RngCallback(Arc::new(callback))
+    }
+    pub(crate) fn call(&self, bytes: &mut [u8]) -> Result<(), Error> {
+        (self.0)(bytes)
+    }
+}
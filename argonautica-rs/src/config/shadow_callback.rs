@@ -0,0 +1,30 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::output::ShadowMismatch;
+
+impl fmt::Debug for ShadowCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ShadowCallback")
+    }
+}
+
+/// Callback invoked with every [`ShadowMismatch`](../output/struct.ShadowMismatch.html)
+/// detected in shadow mode. Cheap to clone so that it can travel with a
+/// [`HasherConfig`](struct.HasherConfig.html) to other threads
+#[derive(Clone)]
+#[cfg_attr(not(feature = "backend-c"), allow(dead_code))]
+pub(crate) struct ShadowCallback(Arc<dyn Fn(&ShadowMismatch) + Send + Sync>);
+
+impl ShadowCallback {
+    pub(crate) fn new<F>(callback: F) -> ShadowCallback
+    where
+        F: Fn(&ShadowMismatch) + Send + Sync + 'static,
+    {
+        ShadowCallback(Arc::new(callback))
+    }
+    #[cfg_attr(not(feature = "backend-c"), allow(dead_code))]
+    pub(crate) fn call(&self, mismatch: &ShadowMismatch) {
+        (self.0)(mismatch)
+    }
+}
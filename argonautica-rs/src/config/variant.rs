@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use core::str::FromStr;
 
 use crate::{Error, ErrorKind, config::defaults::DEFAULT_VARIANT};
 
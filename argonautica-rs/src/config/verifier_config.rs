@@ -1,8 +1,15 @@
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
 use futures::executor::ThreadPool;
 
-use crate::config::Backend;
-#[cfg(feature = "serde")]
+#[cfg(feature = "std")]
+use crate::config::BudgetPolicy;
+
+#[cfg(all(feature = "serde", feature = "std"))]
 use crate::config::defaults::default_cpu_pool_serde;
+use crate::config::{Backend, LaneScheduling, VerifierLimits};
 
 /// Read-only configuration for [`Verifier`](../struct.Verifier.html). Can be obtained by calling
 /// the [`config`](../struct.Verifier.html#method.config) method on an instance of
@@ -12,6 +19,16 @@ use crate::config::defaults::default_cpu_pool_serde;
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct VerifierConfig {
     pub(crate) backend: Backend,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) budget_policy: BudgetPolicy,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) lane_scheduling: LaneScheduling,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) lenient_decoding: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) limits: VerifierLimits,
+    #[cfg(feature = "std")]
     #[cfg_attr(
         feature = "serde",
         serde(
@@ -23,7 +40,15 @@ pub struct VerifierConfig {
     pub(crate) thread_pool: Option<ThreadPool>,
     pub(crate) password_clearing: bool,
     pub(crate) secret_key_clearing: bool,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) secure_memory: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) shadow_mode: bool,
     pub(crate) threads: u32,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl VerifierConfig {
@@ -31,6 +56,24 @@ impl VerifierConfig {
     pub fn backend(&self) -> Backend {
         self.backend
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn budget_policy(&self) -> BudgetPolicy {
+        self.budget_policy
+    }
+    #[allow(missing_docs)]
+    pub fn lane_scheduling(&self) -> LaneScheduling {
+        self.lane_scheduling
+    }
+    #[allow(missing_docs)]
+    pub fn lenient_decoding(&self) -> bool {
+        self.lenient_decoding
+    }
+    #[allow(missing_docs)]
+    pub fn limits(&self) -> &VerifierLimits {
+        &self.limits
+    }
+    #[cfg(feature = "std")]
     #[allow(missing_docs)]
     pub fn thread_pool(&self) -> Option<ThreadPool> {
         self.thread_pool.clone()
@@ -43,26 +86,53 @@ impl VerifierConfig {
     pub fn secret_key_clearing(&self) -> bool {
         self.secret_key_clearing
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn secure_memory(&self) -> bool {
+        self.secure_memory
+    }
+    #[allow(missing_docs)]
+    pub fn shadow_mode(&self) -> bool {
+        self.shadow_mode
+    }
     #[allow(missing_docs)]
     pub fn threads(&self) -> u32 {
         self.threads
     }
+    #[cfg(feature = "std")]
+    #[allow(missing_docs)]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 }
 
 impl VerifierConfig {
     pub(crate) fn new(
         backend: Backend,
-        thread_pool: Option<ThreadPool>,
+        lenient_decoding: bool,
+        limits: VerifierLimits,
         password_clearing: bool,
         secret_key_clearing: bool,
+        shadow_mode: bool,
         threads: u32,
     ) -> VerifierConfig {
         VerifierConfig {
             backend,
-            thread_pool,
+            #[cfg(feature = "std")]
+            budget_policy: BudgetPolicy::default(),
+            lane_scheduling: LaneScheduling::default(),
+            lenient_decoding,
+            limits,
+            #[cfg(feature = "std")]
+            thread_pool: None,
             password_clearing,
             secret_key_clearing,
+            #[cfg(feature = "std")]
+            secure_memory: false,
+            shadow_mode,
             threads,
+            #[cfg(feature = "std")]
+            timeout: None,
         }
     }
 }
@@ -0,0 +1,244 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    Error, ErrorKind,
+    config::{
+        Variant, Version,
+        defaults::{
+            DEFAULT_MAX_HASH_LEN, DEFAULT_MAX_ITERATIONS, DEFAULT_MAX_LANES,
+            DEFAULT_MAX_MEMORY_SIZE, DEFAULT_MIN_SALT_LEN,
+        },
+    },
+    output::HashRaw,
+};
+
+impl Default for VerifierLimits {
+    /// Returns [`VerifierLimits`](struct.VerifierLimits.html) with the following limits:
+    /// * `allowed_variants`: All variants
+    /// * `allowed_versions`: All versions
+    /// * `max_hash_len`: `1024`
+    /// * `max_iterations`: `1024`
+    /// * `max_lanes`: `256`
+    /// * `max_memory_size`: `2097152` (2 GiB)
+    /// * `min_salt_len`: `8`
+    fn default() -> VerifierLimits {
+        VerifierLimits {
+            allowed_variants: vec![Variant::Argon2d, Variant::Argon2i, Variant::Argon2id],
+            allowed_versions: vec![Version::_0x10, Version::_0x13],
+            max_hash_len: DEFAULT_MAX_HASH_LEN,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            max_lanes: DEFAULT_MAX_LANES,
+            max_memory_size: DEFAULT_MAX_MEMORY_SIZE,
+            min_salt_len: DEFAULT_MIN_SALT_LEN,
+        }
+    }
+}
+
+/// Read-only limits on the parameters of the hashes a [`Verifier`](../struct.Verifier.html)
+/// accepts. Can be obtained by calling the [`limits`](struct.VerifierConfig.html#method.limits)
+/// method on a [`VerifierConfig`](struct.VerifierConfig.html).
+///
+/// A hash carries its own cost parameters, so a hash that was tampered with (or imported
+/// from elsewhere) could otherwise make [`Verifier`](../struct.Verifier.html) allocate
+/// gigabytes of memory or spin for minutes. Hashes outside these limits are rejected with
+/// [`ErrorKind::HashParamsRejectedError`](../enum.ErrorKind.html#variant.HashParamsRejectedError)
+/// before any memory is allocated for hashing
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct VerifierLimits {
+    pub(crate) allowed_variants: Vec<Variant>,
+    pub(crate) allowed_versions: Vec<Version>,
+    pub(crate) max_hash_len: u32,
+    pub(crate) max_iterations: u32,
+    pub(crate) max_lanes: u32,
+    pub(crate) max_memory_size: u32,
+    pub(crate) min_salt_len: u32,
+}
+
+impl VerifierLimits {
+    #[allow(missing_docs)]
+    pub fn allowed_variants(&self) -> &[Variant] {
+        &self.allowed_variants
+    }
+    #[allow(missing_docs)]
+    pub fn allowed_versions(&self) -> &[Version] {
+        &self.allowed_versions
+    }
+    #[allow(missing_docs)]
+    pub fn max_hash_len(&self) -> u32 {
+        self.max_hash_len
+    }
+    #[allow(missing_docs)]
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+    #[allow(missing_docs)]
+    pub fn max_lanes(&self) -> u32 {
+        self.max_lanes
+    }
+    #[allow(missing_docs)]
+    pub fn max_memory_size(&self) -> u32 {
+        self.max_memory_size
+    }
+    #[allow(missing_docs)]
+    pub fn min_salt_len(&self) -> u32 {
+        self.min_salt_len
+    }
+}
+
+impl VerifierLimits {
+    pub(crate) fn check(&self, hash_raw: &HashRaw) -> Result<(), Error> {
+        if !self.allowed_variants.contains(&hash_raw.variant()) {
+            return Err(rejected(format!(
+                "Variant not allowed. Variant: {}",
+                hash_raw.variant().as_str()
+            )));
+        }
+        if !self.allowed_versions.contains(&hash_raw.version()) {
+            return Err(rejected(format!(
+                "Version not allowed. Version: {}",
+                hash_raw.version().as_str()
+            )));
+        }
+        check_max("Memory size", hash_raw.memory_size(), self.max_memory_size)?;
+        check_max("Iterations", hash_raw.iterations(), self.max_iterations)?;
+        check_max("Lanes", hash_raw.lanes(), self.max_lanes)?;
+        let hash_len = hash_raw.raw_hash_bytes().len();
+        if hash_len > self.max_hash_len as usize {
+            return Err(rejected(format!(
+                "Hash length above limit. Hash length: {}. Limit: {}",
+                hash_len, self.max_hash_len
+            )));
+        }
+        let salt_len = hash_raw.raw_salt_bytes().len();
+        if salt_len < self.min_salt_len as usize {
+            return Err(rejected(format!(
+                "Salt length below limit. Salt length: {}. Limit: {}",
+                salt_len, self.min_salt_len
+            )));
+        }
+        Ok(())
+    }
+    pub(crate) fn set_allowed_variants(&mut self, variants: &[Variant]) {
+        self.allowed_variants = variants.to_vec();
+    }
+    pub(crate) fn set_allowed_versions(&mut self, versions: &[Version]) {
+        self.allowed_versions = versions.to_vec();
+    }
+    pub(crate) fn set_max_hash_len(&mut self, max_hash_len: u32) {
+        self.max_hash_len = max_hash_len;
+    }
+    pub(crate) fn set_max_iterations(&mut self, max_iterations: u32) {
+        self.max_iterations = max_iterations;
+    }
+    pub(crate) fn set_max_lanes(&mut self, max_lanes: u32) {
+        self.max_lanes = max_lanes;
+    }
+    pub(crate) fn set_max_memory_size(&mut self, max_memory_size: u32) {
+        self.max_memory_size = max_memory_size;
+    }
+    pub(crate) fn set_min_salt_len(&mut self, min_salt_len: u32) {
+        self.min_salt_len = min_salt_len;
+    }
+}
+
+fn check_max(name: &str, value: u32, limit: u32) -> Result<(), Error> {
+    if value > limit {
+        return Err(rejected(format!(
+            "{} above limit. {}: {}. Limit: {}",
+            name, name, value, limit
+        )));
+    }
+    Ok(())
+}
+
+fn rejected(context: String) -> Error {
+    Error::new(ErrorKind::HashParamsRejectedError).add_context(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_raw() -> HashRaw {
+        HashRaw {
+            iterations: 3,
+            key_id: None,
+            lanes: 4,
+            memory_size: 4096,
+            omit_version: false,
+            raw_hash_bytes: vec![0u8; 32],
+            raw_salt_bytes: vec![0u8; 16],
+            variant: Variant::Argon2id,
+            version: Version::_0x13,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut limits = VerifierLimits::default();
+        limits.check(&hash_raw()).unwrap();
+
+        let mut hash_raw = hash_raw();
+        hash_raw.memory_size = 4_194_304;
+        let e = limits.check(&hash_raw).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::HashParamsRejectedError);
+        limits.set_max_memory_size(4_194_304);
+        limits.check(&hash_raw).unwrap();
+
+        hash_raw.iterations = 100_000;
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_max_iterations(100_000);
+        limits.check(&hash_raw).unwrap();
+
+        limits.set_max_lanes(2);
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_max_lanes(4);
+
+        limits.set_max_hash_len(16);
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_max_hash_len(32);
+
+        limits.set_min_salt_len(32);
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_min_salt_len(16);
+
+        limits.set_allowed_variants(&[Variant::Argon2i]);
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_allowed_variants(&[Variant::Argon2i, Variant::Argon2id]);
+
+        limits.set_allowed_versions(&[Version::_0x10]);
+        assert!(limits.check(&hash_raw).is_err());
+        limits.set_allowed_versions(&[Version::_0x13]);
+        limits.check(&hash_raw).unwrap();
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<VerifierLimits>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<VerifierLimits>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<VerifierLimits>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<VerifierLimits>();
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(non_local_definitions)]
 
-use std::fmt;
+use alloc::string::String;
+use core::fmt;
 
 use crate::ErrorKind;
 
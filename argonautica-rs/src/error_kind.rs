@@ -14,8 +14,10 @@ pub enum ErrorKind {
     #[fail(display = "Backend encode error. u32 provided could not be encoded into a Backend")]
     BackendEncodeError,
 
-    /// Rust backend not yet supported. Please use the C backend
-    #[fail(display = "Rust backend not yet supported. Please use the C backend")]
+    /// Backend unsupported. The C backend is not available because argonautica was built without the backend-c feature. Please use the Rust backend
+    #[fail(
+        display = "Backend unsupported. The C backend is not available because argonautica was built without the backend-c feature. Please use the Rust backend"
+    )]
     BackendUnsupportedError,
 
     /// Base64 decode error. Bytes provided were invalid base64
@@ -28,6 +30,12 @@ pub enum ErrorKind {
     )]
     Bug,
 
+    /// Cancelled. The future returned by a non-blocking method was dropped before hashing finished
+    #[fail(
+        display = "Cancelled. The future returned by a non-blocking method was dropped before hashing finished"
+    )]
+    CancelledError,
+
     /// Hash decode error. Hash provided was invalid
     #[fail(display = "Hash decode error. Hash provided was invalid")]
     HashDecodeError,
@@ -45,6 +53,12 @@ pub enum ErrorKind {
     #[fail(display = "Hash missing. Attempted to verify without first having provided a hash")]
     HashMissingError,
 
+    /// Hash parameters rejected. The hash's parameters are outside the limits configured on Verifier
+    #[fail(
+        display = "Hash parameters rejected. The hash's parameters are outside the limits configured on Verifier"
+    )]
+    HashParamsRejectedError,
+
     /// Iterations too few. Iterations must be greater than 0
     #[fail(display = "Iterations must be greater than 0")]
     IterationsTooFewError,
@@ -57,12 +71,37 @@ pub enum ErrorKind {
     #[fail(display = "Lanes must be less than 2^24")]
     LanesTooManyError,
 
-    /// C code attempted to allocate memory (using malloc) and failed
-    #[fail(display = "C code attempted to allocate memory (using malloc) and failed")]
+    /// Lint error. Hasher is configured with strict_lint and HasherConfig::lint found weaknesses in its configuration
+    #[fail(
+        display = "Lint error. Hasher is configured with strict_lint and HasherConfig::lint found weaknesses in its configuration"
+    )]
+    LintError,
+
+    /// Memory allocation error. Failed to allocate the memory needed to compute the hash
+    #[fail(
+        display = "Memory allocation error. Failed to allocate the memory needed to compute the hash"
+    )]
     MemoryAllocationError,
 
-    /// Memory size invalid. Memory size must be a power of two
-    #[fail(display = "Memory size invalid. Memory size must be a power of two")]
+    /// Memory budget error. The process-wide memory budget could not cover the memory needed
+    /// to compute the hash
+    #[fail(
+        display = "Memory budget error. The process-wide memory budget could not cover the memory needed to compute the hash"
+    )]
+    MemoryBudgetError,
+
+    /// Memory lock error. Failed to lock the memory needed to compute the hash into RAM, e.g.
+    /// because `RLIMIT_MEMLOCK` is too low
+    #[fail(
+        display = "Memory lock error. Failed to lock the memory needed to compute the hash into RAM"
+    )]
+    MemoryLockError,
+
+    /// Memory size invalid. No longer returned, since any memory size of at least 8 times the
+    /// number of lanes is valid; kept so that existing matches on it continue to compile
+    #[fail(
+        display = "Memory size invalid. No longer returned, since any memory size of at least 8 times the number of lanes is valid"
+    )]
     MemorySizeInvalidError,
 
     /// Memory size too small. Memory size must be at least 8 times the number of lanes
@@ -95,6 +134,18 @@ pub enum ErrorKind {
     #[fail(display = "Password too short. Length in bytes must be greater than 0")]
     PasswordTooShortError,
 
+    /// RNG error. The random number generator configured with Hasher::configure_rng failed to produce random bytes
+    #[fail(
+        display = "RNG error. The random number generator configured with Hasher::configure_rng failed to produce random bytes"
+    )]
+    RngError,
+
+    /// RNG missing. A random salt needs a random number generator, but argonautica was built without the std feature and none was configured with Hasher::configure_rng. Either configure one or provide a deterministic salt
+    #[fail(
+        display = "RNG missing. A random salt needs a random number generator, but argonautica was built without the std feature and none was configured with Hasher::configure_rng. Either configure one or provide a deterministic salt"
+    )]
+    RngMissingError,
+
     /// Salt too long. Length in bytes must be less than 2^32
     #[fail(display = "Salt too long. Length in bytes must be less than 2^32")]
     SaltTooLongError,
@@ -103,6 +154,16 @@ pub enum ErrorKind {
     #[fail(display = "Salt too short. Length in bytes must be at least 8")]
     SaltTooShortError,
 
+    /// Secret key ID invalid. Key IDs must be between 1 and 8 bytes long
+    #[fail(display = "Secret key ID invalid. Key IDs must be between 1 and 8 bytes long")]
+    SecretKeyIdInvalidError,
+
+    /// Secret key ID unknown. There is no secret key with this ID in the SecretKeyRing
+    #[fail(
+        display = "Secret key ID unknown. There is no secret key with this ID in the SecretKeyRing"
+    )]
+    SecretKeyIdUnknownError,
+
     /// Secret key immutable error. You attempted to hash or verify with an immutable secret key and secret_key_clearing set to true, which is not possible because with an immutable secret key argonautica cannot zero out the secret key bytes. To prevent this error, either pass Hasher or Verifier a mutable secret key or set secret_key_clearing to false
     #[fail(
         display = "Secret key immutable error. You attempted to hash or verify with an immutable secret key and secret_key_clearing set to true, which is not possible because with an immutable secret key argonautica cannot zero out the secret key bytes. To prevent this error, either pass Hasher or Verifier a mutable secret key or set secret_key_clearing to false"
@@ -119,10 +180,18 @@ pub enum ErrorKind {
     #[fail(display = "Secret key too long. Length in bytes must be less than 2^32")]
     SecretKeyTooLongError,
 
-    /// C code reported a "Threading failure" error
-    #[fail(display = "C code reported a \"Threading failure\" error")]
+    /// Thread error. Failed to spawn or join one of the threads used to compute the hash
+    #[fail(
+        display = "Thread error. Failed to spawn or join one of the threads used to compute the hash"
+    )]
     ThreadError,
 
+    /// Thread pool full. Too many jobs are waiting for the global thread pool. Try again later or raise its capacity with pool::set_queue_capacity
+    #[fail(
+        display = "Thread pool full. Too many jobs are waiting for the global thread pool. Try again later or raise its capacity with pool::set_queue_capacity"
+    )]
+    ThreadPoolFullError,
+
     /// Threads too few. Threads must be greater than 0
     #[fail(display = "Threads too few. Threads must be greater than 0")]
     ThreadsTooFewError,
@@ -131,6 +200,12 @@ pub enum ErrorKind {
     #[fail(display = "Threads too many. Threads must be less than 2^24")]
     ThreadsTooManyError,
 
+    /// Timeout. Hashing did not finish within the timeout configured on Hasher or Verifier
+    #[fail(
+        display = "Timeout. Hashing did not finish within the timeout configured on Hasher or Verifier"
+    )]
+    TimeoutError,
+
     /// Utf-8 encode error. Bytes provided could not be encoded into utf-8
     #[fail(display = "Utf-8 encode error. Bytes provided could not be encoded into utf-8")]
     Utf8EncodeError,
@@ -0,0 +1,346 @@
+//! The [`Executor`](trait.Executor.html) trait, which lets the non-blocking methods
+//! ([`hash_non_blocking`](../struct.Hasher.html#method.hash_non_blocking),
+//! [`hash_raw_non_blocking`](../struct.Hasher.html#method.hash_raw_non_blocking) and
+//! [`verify_non_blocking`](../struct.Verifier.html#method.verify_non_blocking)) run their
+//! work on the thread pool of your async runtime instead of a separate one, and adapters for
+//! common runtimes.
+//!
+//! Hashing keeps a thread busy for the whole computation, so an executor must run jobs on
+//! threads that are allowed to block, e.g. tokio's blocking pool rather than its worker
+//! threads. The adapters are behind features:
+//! * `runtime-tokio`: [`TokioExecutor`](struct.TokioExecutor.html), which uses
+//!   [`spawn_blocking`](https://docs.rs/tokio/1/tokio/runtime/struct.Handle.html#method.spawn_blocking)
+//! * `runtime-rayon`: [`RayonExecutor`](struct.RayonExecutor.html)
+//! * `runtime-smol`: [`SmolExecutor`](struct.SmolExecutor.html), which uses the
+//!   [blocking](https://docs.rs/blocking) thread pool shared by smol and async-std
+//!
+//! [`ThreadPool`](https://docs.rs/futures/0.3/futures/executor/struct.ThreadPool.html)
+//! implements [`Executor`](trait.Executor.html) as well. If you configure neither an
+//! executor nor a thread pool, the non-blocking methods use the global thread pool of the
+//! [`pool`](../pool/index.html) module
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use futures::{channel::oneshot, executor::ThreadPool};
+
+use crate::{Error, ErrorKind};
+
+/// A unit of work handed to an [`Executor`](trait.Executor.html)
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Trait for thread pools that can run the blocking work of the non-blocking methods. See
+/// the [module documentation](index.html)
+pub trait Executor: Send + Sync + 'static {
+    /// Runs `job` to completion on a thread that may block for as long as the job takes.
+    /// Dropping `job` without running it makes the non-blocking method return an
+    /// [`ErrorKind::ThreadError`](../enum.ErrorKind.html#variant.ThreadError)
+    fn execute(&self, job: Job);
+}
+
+impl Executor for ThreadPool {
+    fn execute(&self, job: Job) {
+        self.spawn_ok(async move { job() });
+    }
+}
+
+/// [`Executor`](trait.Executor.html) that runs jobs on the blocking thread pool of a tokio
+/// runtime
+#[cfg(feature = "runtime-tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioExecutor(tokio::runtime::Handle);
+
+#[cfg(feature = "runtime-tokio")]
+impl TokioExecutor {
+    /// Creates a [`TokioExecutor`](struct.TokioExecutor.html) for the runtime behind `handle`
+    pub fn new(handle: tokio::runtime::Handle) -> TokioExecutor {
+        TokioExecutor(handle)
+    }
+    /// Creates a [`TokioExecutor`](struct.TokioExecutor.html) for the runtime the calling
+    /// thread belongs to, or returns `None` if it is not in a runtime
+    pub fn current() -> Option<TokioExecutor> {
+        tokio::runtime::Handle::try_current()
+            .ok()
+            .map(TokioExecutor)
+    }
+}
+
+#[cfg(feature = "runtime-tokio")]
+impl Executor for TokioExecutor {
+    fn execute(&self, job: Job) {
+        // The job reports its result itself, so its JoinHandle is not needed
+        drop(self.0.spawn_blocking(job));
+    }
+}
+
+/// [`Executor`](trait.Executor.html) that runs jobs on a rayon thread pool
+#[cfg(feature = "runtime-rayon")]
+#[derive(Clone, Debug, Default)]
+pub struct RayonExecutor(Option<Arc<rayon::ThreadPool>>);
+
+#[cfg(feature = "runtime-rayon")]
+impl RayonExecutor {
+    /// Creates a [`RayonExecutor`](struct.RayonExecutor.html) that uses rayon's global thread
+    /// pool
+    pub fn global() -> RayonExecutor {
+        RayonExecutor(None)
+    }
+    /// Creates a [`RayonExecutor`](struct.RayonExecutor.html) that uses `thread_pool`
+    pub fn new(thread_pool: Arc<rayon::ThreadPool>) -> RayonExecutor {
+        RayonExecutor(Some(thread_pool))
+    }
+}
+
+#[cfg(feature = "runtime-rayon")]
+impl Executor for RayonExecutor {
+    fn execute(&self, job: Job) {
+        match self.0 {
+            Some(ref thread_pool) => thread_pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+    }
+}
+
+/// [`Executor`](trait.Executor.html) that runs jobs on the [blocking](https://docs.rs/blocking)
+/// thread pool, which smol and async-std use for blocking work
+#[cfg(feature = "runtime-smol")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SmolExecutor;
+
+#[cfg(feature = "runtime-smol")]
+impl Executor for SmolExecutor {
+    fn execute(&self, job: Job) {
+        blocking::unblock(job).detach();
+    }
+}
+
+/// Cheaply cloneable [`Executor`](trait.Executor.html), so that it can travel with a
+/// [`HasherConfig`](../config/struct.HasherConfig.html) to other threads
+#[derive(Clone)]
+pub(crate) struct SharedExecutor(Arc<dyn Executor>);
+
+impl fmt::Debug for SharedExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedExecutor")
+    }
+}
+
+impl SharedExecutor {
+    pub(crate) fn new<E: Executor>(executor: E) -> SharedExecutor {
+        SharedExecutor(Arc::new(executor))
+    }
+    /// Runs `f` on the executor and waits for its result without blocking
+    pub(crate) async fn run<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.0.execute(Box::new(move || {
+            let _ = sender.send(f());
+        }));
+        receiver.await.map_err(|_| {
+            Error::new(ErrorKind::ThreadError).add_context("Executor dropped the job")
+        })?
+    }
+    /// Calls `f` once for each index in `0..count`, on the calling thread and on up to
+    /// `helpers` jobs on the executor, and returns once every call has finished. Helpers that
+    /// only get a thread after all indices were taken return without doing anything, so this
+    /// never waits for the executor to have a free thread. Returns how many helpers got to
+    /// call `f` at least once
+    pub(crate) fn for_each(
+        &self,
+        count: usize,
+        helpers: usize,
+        f: &(dyn Fn(usize) + Sync),
+    ) -> Result<usize, Error> {
+        // Helpers may outlive this call, but they only call `f` after taking an index, and
+        // this call does not return before the calls for all indices have finished
+        let f = unsafe {
+            std::mem::transmute::<&(dyn Fn(usize) + Sync), &'static (dyn Fn(usize) + Sync)>(f)
+        };
+        let shared = Arc::new(ForEach {
+            arrived: AtomicUsize::new(0),
+            count,
+            f,
+            finished: Mutex::new((0, false)),
+            finished_changed: Condvar::new(),
+            next: AtomicUsize::new(0),
+        });
+        for _ in 0..helpers.min(count.saturating_sub(1)) {
+            let shared = Arc::clone(&shared);
+            self.0.execute(Box::new(move || {
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| shared.work(true)));
+            }));
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| shared.work(false)));
+        let mut finished = shared.finished.lock().unwrap();
+        while finished.0 < count {
+            finished = shared.finished_changed.wait(finished).unwrap();
+        }
+        if let Err(e) = result {
+            panic::resume_unwind(e);
+        }
+        if finished.1 {
+            return Err(Error::new(ErrorKind::ThreadError).add_context("Executor job panicked"));
+        }
+        Ok(shared.arrived.load(Ordering::SeqCst))
+    }
+}
+
+/// State shared between the calling thread and the helpers of
+/// [`SharedExecutor::for_each`](struct.SharedExecutor.html#method.for_each)
+struct ForEach {
+    /// Number of helpers that took at least one index
+    arrived: AtomicUsize,
+    count: usize,
+    f: &'static (dyn Fn(usize) + Sync),
+    /// Number of finished calls and whether any of them panicked
+    finished: Mutex<(usize, bool)>,
+    finished_changed: Condvar,
+    next: AtomicUsize,
+}
+
+impl ForEach {
+    fn work(&self, is_helper: bool) {
+        let mut index = self.next.fetch_add(1, Ordering::SeqCst);
+        if is_helper && index < self.count {
+            self.arrived.fetch_add(1, Ordering::SeqCst);
+        }
+        while index < self.count {
+            let _finish = scopeguard::guard((), |_| {
+                let panicked = std::thread::panicking();
+                // After a panic, nobody may be left to take the remaining indices (e.g. with no
+                // helpers running), so stop handing them out and count them as finished here
+                let skipped = if panicked {
+                    self.count
+                        .saturating_sub(self.next.swap(self.count, Ordering::SeqCst))
+                } else {
+                    0
+                };
+                let mut finished = self.finished.lock().unwrap();
+                finished.0 += 1 + skipped;
+                finished.1 |= panicked;
+                self.finished_changed.notify_all();
+            });
+            (self.f)(index);
+            index = self.next.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Inline;
+
+    impl Executor for Inline {
+        fn execute(&self, job: Job) {
+            job()
+        }
+    }
+
+    #[derive(Debug)]
+    struct Dropping;
+
+    impl Executor for Dropping {
+        fn execute(&self, _: Job) {}
+    }
+
+    #[test]
+    fn test_run() {
+        let executor = SharedExecutor::new(Inline);
+        assert_eq!(block_on(executor.run(|| Ok(1))).unwrap(), 1);
+        let executor = SharedExecutor::new(ThreadPool::new().unwrap());
+        assert_eq!(block_on(executor.run(|| Ok(2))).unwrap(), 2);
+        let e = block_on(executor.run(|| -> Result<(), Error> { Err(Error::new(ErrorKind::Bug)) }))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Bug);
+        let executor = SharedExecutor::new(Dropping);
+        let e = block_on(executor.run(|| Ok(()))).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ThreadError);
+    }
+
+    #[test]
+    fn test_for_each() {
+        let calls = (0..100).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+        let f = |index: usize| {
+            calls[index].fetch_add(1, Ordering::SeqCst);
+        };
+        let executor = SharedExecutor::new(ThreadPool::new().unwrap());
+        let arrived = executor.for_each(calls.len(), 3, &f).unwrap();
+        assert!(arrived <= 3);
+        // Helpers that never run leave all the work to the calling thread
+        let executor = SharedExecutor::new(Dropping);
+        assert_eq!(executor.for_each(calls.len(), 3, &f).unwrap(), 0);
+        assert!(calls.iter().all(|calls| calls.load(Ordering::SeqCst) == 2));
+
+        let executor = SharedExecutor::new(Inline);
+        let e = executor
+            .for_each(2, 1, &|index| {
+                if index == 1 {
+                    panic!("index 1");
+                }
+            })
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ThreadError);
+
+        // A panic on the calling thread with indices left and no helpers running
+        for executor in [SharedExecutor::new(Inline), SharedExecutor::new(Dropping)] {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                executor.for_each(4, 0, &|index| {
+                    if index == 1 {
+                        panic!("index 1");
+                    }
+                })
+            }));
+            assert!(result.is_err());
+        }
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[test]
+    fn test_tokio_executor() {
+        assert!(TokioExecutor::current().is_none());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let executor = SharedExecutor::new(TokioExecutor::new(runtime.handle().clone()));
+        assert_eq!(runtime.block_on(executor.run(|| Ok(3))).unwrap(), 3);
+    }
+
+    #[cfg(feature = "runtime-rayon")]
+    #[test]
+    fn test_rayon_executor() {
+        let executor = SharedExecutor::new(RayonExecutor::global());
+        assert_eq!(block_on(executor.run(|| Ok(4))).unwrap(), 4);
+    }
+
+    #[cfg(feature = "runtime-smol")]
+    #[test]
+    fn test_smol_executor() {
+        let executor = SharedExecutor::new(SmolExecutor);
+        assert_eq!(block_on(executor.run(|| Ok(5))).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SharedExecutor>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SharedExecutor>();
+    }
+}
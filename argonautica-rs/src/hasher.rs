@@ -1,13 +1,29 @@
-use futures::{executor::ThreadPool, task::SpawnExt};
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
+use futures::{Stream, executor::ThreadPool, task::SpawnExt};
+
+#[cfg(feature = "std")]
+use crate::{
+    allocator::{MemoryAllocator, SharedAllocator},
+    batch,
+    cancellation::Cancellation,
+    config::{BatchOrder, BudgetPolicy, Calibration},
+    executor::{Executor, SharedExecutor},
+    memory_budget, pool,
+};
 
 use crate::{
     Error, ErrorKind,
+    backend::resolve_config,
     config::{
-        Backend, HasherConfig, Variant, Version,
-        defaults::{default_cpu_pool, default_lanes},
+        Backend, HasherConfig, LaneScheduling, Preset, RngCallback, ShadowCallback, Variant,
+        Version, defaults::default_lanes,
     },
-    input::{AdditionalData, Container, Password, Salt, SecretKey},
-    output::HashRaw,
+    input::{AdditionalData, Password, Salt, SecretKey, SecretKeyRing},
+    output::{HashRaw, ShadowMismatch},
 };
 
 impl Default for Hasher<'_> {
@@ -15,6 +31,8 @@ impl Default for Hasher<'_> {
     fn default() -> Hasher<'static> {
         Hasher {
             additional_data: None,
+            #[cfg(feature = "std")]
+            cancellation: Cancellation::default(),
             config: HasherConfig::default(),
             password: None,
             salt: Salt::default(),
@@ -29,6 +47,9 @@ impl Default for Hasher<'_> {
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct Hasher<'a> {
     pub(crate) additional_data: Option<AdditionalData>,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub(crate) cancellation: Cancellation,
     pub(crate) config: HasherConfig,
     #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
     pub(crate) password: Option<Password<'a>>,
@@ -48,29 +69,36 @@ impl<'a> Hasher<'a> {
     ///
     /// There is a script in the examples directory that will show you the various configuration
     /// options for your machine that produce hashing times between 300 and 500 milliseconds
-    /// (Don't forget to run it with the `--release` and `--features="simd"` flags). Alternatively,
+    /// (Don't forget to run it with the `--release` flag). Alternatively,
     /// you can clone the repository and run the benchmark suite with
-    /// `cargo bench --features="benches simd" -- inputs`, which will take longer but which runs
+    /// `cargo bench --features="benches" -- inputs`, which will take longer but which runs
     /// many iterations for each configuration scenario; so it provides information about
     /// distributions of running time (e.g. mean, 95% confidence intervals, etc.) as opposed
     /// to just point estimates.
     ///
     /// Here are the default configuration options:
+    /// * `allocator`: None, i.e. the system allocator
     /// * `backend`: [`Backend::C`](config/enum.Backend.html#variant.C)
-    /// * `cpu_pool`: A [`CpuPool`](https://docs.rs/futures-cpupool/0.1.8/futures_cpupool/struct.CpuPool.html) ...
-    ///     * with threads equal to the number of logical cores on your machine
-    ///     * that is lazily created, i.e. created only if / when you call the methods
-    ///       that need it ([`hash_non_blocking`](struct.Hasher.html#method.hash_non_blocking) or
-    ///       [`hash_raw_non_blocking`](struct.Hasher.html#method.hash_raw_non_blocking))
+    /// * `budget_policy`: [`BudgetPolicy::Wait`](config/enum.BudgetPolicy.html#variant.Wait)
+    /// * `cpu_pool`: None, i.e. the non-blocking methods
+    ///   ([`hash_non_blocking`](struct.Hasher.html#method.hash_non_blocking) and
+    ///   [`hash_raw_non_blocking`](struct.Hasher.html#method.hash_raw_non_blocking)) run on
+    ///   the process-wide thread pool described in the [`pool`](pool/index.html) module
+    /// * `executor`: None
     /// * `hash_len`: `32` bytes
     /// * `iterations`: `192`
+    /// * `lane_scheduling`: [`LaneScheduling::Threads`](config/enum.LaneScheduling.html#variant.Threads)
     /// * `lanes`: The number of logical cores on your machine
     /// * `memory_size`: `4096` kibibytes
     /// * `opt_out_of_secret_key`: `false`
     /// * `password_clearing`: `false`
     /// * `salt`: random [`Salt`](input/struct.Salt.html) of length 32 bytes that renews with every hash
     /// * `secret_key_clearing`: `false`
+    /// * `secure_memory`: `false`
+    /// * `shadow_mode`: `false`
+    /// * `strict_lint`: `false`
     /// * `threads`: The number of logical cores on your machine
+    /// * `timeout`: None
     /// * `variant`: [`Variant::Argon2id`](config/enum.Variant.html#variant.Argon2id)
     /// * `version`: [`Version::_0x13`](config/enum.Verion.html#variant._0x13)
     pub fn new() -> Hasher<'static> {
@@ -85,7 +113,9 @@ impl<'a> Hasher<'a> {
     /// early-2014 Macbook Air, this configuration hashes the full text of Shakespear's Hamlet
     /// in approximately 1 millisecond (on average). [MD5](https://github.com/stainless-steel/md5)
     /// does it in about half the time and [sha2](https://github.com/RustCrypto/hashes) with the
-    /// SHA-256 algorithm performs about the same as `argonautica`
+    /// SHA-256 algorithm performs about the same as `argonautica`. Do not use it to hash
+    /// passwords; [`HasherConfig::lint`](config/struct.HasherConfig.html#method.lint) reports
+    /// several high-severity weaknesses in this configuration
     pub fn fast_but_insecure() -> Hasher<'a> {
         fn memory_size(lanes: u32) -> u32 {
             let mut counter = 1;
@@ -112,30 +142,119 @@ impl<'a> Hasher<'a> {
             .with_salt(&[0u8; 8][..]);
         hasher
     }
+    /// Creates a new [`Hasher`](struct.Hasher.html) configured with the hash length,
+    /// iterations, lanes, memory size and (random) salt length of `preset`, using
+    /// [`Variant::Argon2id`](config/enum.Variant.html#variant.Argon2id) and
+    /// [`Version::_0x13`](config/enum.Version.html#variant._0x13). All other configuration
+    /// options are the same as the defaults. See [`Preset`](config/enum.Preset.html) for the
+    /// available presets and the memory each of them needs
+    pub fn from_preset(preset: Preset) -> Hasher<'a> {
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_hash_len(preset.hash_len())
+            .configure_iterations(preset.iterations())
+            .configure_lanes(preset.lanes())
+            .configure_memory_size(preset.memory_size())
+            .configure_variant(Variant::Argon2id)
+            .configure_version(Version::_0x13)
+            .with_salt(Salt::random(preset.salt_len()));
+        hasher
+    }
+    /// Creates a new [`Hasher`](struct.Hasher.html) configured with the hash length,
+    /// iterations, lanes, memory size, threads, variant, version and (random) salt length
+    /// chosen by [`HasherConfig::calibrate`](config/struct.HasherConfig.html#method.calibrate).
+    /// All other configuration options are the same as the defaults
+    #[cfg(feature = "std")]
+    pub fn from_calibration(calibration: &Calibration) -> Hasher<'a> {
+        let config = calibration.config();
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_hash_len(config.hash_len())
+            .configure_iterations(config.iterations())
+            .configure_lanes(config.lanes())
+            .configure_memory_size(config.memory_size())
+            .configure_threads(config.threads())
+            .configure_variant(config.variant())
+            .configure_version(config.version())
+            .with_salt(Salt::random(config.salt_len()));
+        hasher
+    }
+    /// Creates a new [`Hasher`](struct.Hasher.html) with the first option recommended by
+    /// [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106.html#name-parameter-choice):
+    /// Argon2id with 1 iteration, 4 lanes and 2 GiB of memory per hash. Same as
+    /// `Hasher::from_preset(Preset::Rfc9106First)`
+    pub fn rfc9106_first() -> Hasher<'a> {
+        Hasher::from_preset(Preset::Rfc9106First)
+    }
+    /// Creates a new [`Hasher`](struct.Hasher.html) with the second option recommended by
+    /// [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106.html#name-parameter-choice), meant
+    /// for memory-constrained environments: Argon2id with 3 iterations, 4 lanes and 64 MiB of
+    /// memory per hash. Same as `Hasher::from_preset(Preset::Rfc9106Second)`
+    pub fn rfc9106_second() -> Hasher<'a> {
+        Hasher::from_preset(Preset::Rfc9106Second)
+    }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to allocate the working memory
+    /// of each hash from `allocator`, e.g. a
+    /// [`PooledAllocator`](allocator/struct.PooledAllocator.html) that reuses buffers across
+    /// hashes. See the [`allocator`](allocator/index.html) module. The default is the system
+    /// allocator
+    #[cfg(feature = "std")]
+    pub fn configure_allocator<A: MemoryAllocator>(&mut self, allocator: A) -> &mut Hasher<'a> {
+        self.config.set_allocator(SharedAllocator::new(allocator));
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) with a custom backend. The
-    /// default backend is [`Backend::C`](config/enum.Backend.html#variant.C), <i>which is
-    /// currently the only backend supported. A Rust backend is planned, but is not currently
-    /// available. If you configure a [`Hasher`](struct.Hasher.html) with
-    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) it will error when you
+    /// default backend is [`Backend::C`](config/enum.Backend.html#variant.C). The
+    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) backend produces identical
+    /// hashes and does not need a C compiler to build.
+    /// [`Backend::Auto`](config/enum.Backend.html#variant.Auto) picks the C backend unless
+    /// the Rust backend uses a higher SIMD level on the current machine. <i>If argonautica was built without
+    /// the `backend-c` feature, the default backend is
+    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) and configuring
+    /// [`Backend::C`](config/enum.Backend.html#variant.C) will error when you
     /// call [`hash`](struct.Hasher.html#method.hash),
     /// [`hash_raw`](struct.Hasher.html#method.hash_raw) or their non-blocking equivalents</i>
     pub fn configure_backend(&mut self, backend: Backend) -> &mut Hasher<'a> {
         self.config.set_backend(backend);
         self
     }
+    /// Allows you to configure what [`Hasher`](struct.Hasher.html) does when the
+    /// process-wide [memory budget](memory_budget/index.html) cannot cover the memory of a
+    /// hash yet: wait for it, fail right away or wait for a limited time. The default is
+    /// [`BudgetPolicy::Wait`](config/enum.BudgetPolicy.html#variant.Wait). See
+    /// [`BudgetPolicy`](config/enum.BudgetPolicy.html)
+    #[cfg(feature = "std")]
+    pub fn configure_budget_policy(&mut self, budget_policy: BudgetPolicy) -> &mut Hasher<'a> {
+        self.config.set_budget_policy(budget_policy);
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) with a custom
     /// [`CpuPool`](https://docs.rs/futures-cpupool/0.1.8/futures_cpupool/struct.CpuPool.html).
     /// The default [`Hasher`](struct.Hasher.html) does not have a cpu pool, which is
     /// only needed for the [`hash_non_blocking`](struct.Hasher.html#method.hash_non_blocking)
     /// and [`hash_raw_non_blocking`](struct.Hasher.html#method.hash_raw_non_blocking) methods.
-    /// If you call either of these methods without a cpu pool, a default cpu pool will be created
-    /// for you on the fly; so even if you never configure [`Hasher`](struct.Hasher.html) with
-    /// this method you can still use the non-blocking hashing methods.
-    /// The default cpu pool has as many threads as the number of logical cores on your machine
+    /// If you call either of these methods without a cpu pool, they run on the process-wide
+    /// thread pool described in the [`pool`](pool/index.html) module, which has as many
+    /// threads as the number of logical cores on your machine and a bounded queue; so even if
+    /// you never configure [`Hasher`](struct.Hasher.html) with this method you can still use
+    /// the non-blocking hashing methods. A cpu pool configured with this method has no queue
+    /// limit
+    #[cfg(feature = "std")]
     pub fn configure_thread_pool(&mut self, thread_pool: ThreadPool) -> &mut Hasher<'a> {
         self.config.set_thread_pool(thread_pool);
         self
     }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to run the non-blocking hashing methods on an
+    /// [`Executor`](executor/trait.Executor.html), e.g. the blocking thread pool of your async
+    /// runtime (see the [`executor`](executor/index.html) module for the adapters). An executor
+    /// takes precedence over a thread pool configured with
+    /// [`configure_thread_pool`](struct.Hasher.html#method.configure_thread_pool). The default
+    /// [`Hasher`](struct.Hasher.html) does not have an executor
+    #[cfg(feature = "std")]
+    pub fn configure_executor<E: Executor>(&mut self, executor: E) -> &mut Hasher<'a> {
+        self.config.set_executor(SharedExecutor::new(executor));
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) to use a custom hash length
     /// (in number of bytes). The default is `32`.
     ///
@@ -161,6 +280,20 @@ impl<'a> Hasher<'a> {
         self.config.set_lanes(lanes);
         self
     }
+    /// Allows you to configure how [`Hasher`](struct.Hasher.html) runs the lanes of hashes
+    /// with more than one thread. The default is
+    /// [`LaneScheduling::Threads`](config/enum.LaneScheduling.html#variant.Threads), i.e.
+    /// fresh threads for every slice. If you hash many passwords at once, e.g. with the
+    /// non-blocking methods or [`hash_many`](struct.Hasher.html#method.hash_many), consider
+    /// [`LaneScheduling::Adaptive`](config/enum.LaneScheduling.html#variant.Adaptive), which runs
+    /// them on the thread pool instead. See [`LaneScheduling`](config/enum.LaneScheduling.html)
+    pub fn configure_lane_scheduling(
+        &mut self,
+        lane_scheduling: LaneScheduling,
+    ) -> &mut Hasher<'a> {
+        self.config.set_lane_scheduling(lane_scheduling);
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) to use a custom memory size
     /// (in kibibytes). The default is `4096`.
     ///
@@ -184,6 +317,23 @@ impl<'a> Hasher<'a> {
         self.config.set_password_clearing(boolean);
         self
     }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) with a custom random number
+    /// generator for <u>random</u> [`Salt`](input/struct.Salt.html)s. The callback must fill
+    /// the buffer it is given with cryptographically-secure random bytes, or return an
+    /// [`Error`](struct.Error.html) (e.g. of kind
+    /// [`ErrorKind::RngError`](enum.ErrorKind.html#variant.RngError)) if it cannot. By default,
+    /// salts are generated with `OsRng`.
+    ///
+    /// <i>If argonautica was built without the `std` feature, there is no `OsRng`; so
+    /// hashing with a random salt requires this configuration (or a deterministic salt
+    /// provided with [`with_salt`](struct.Hasher.html#method.with_salt))</i>
+    pub fn configure_rng<F>(&mut self, rng: F) -> &mut Hasher<'a>
+    where
+        F: Fn(&mut [u8]) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.config.set_rng(RngCallback::new(rng));
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) to erase the secret key bytes
     /// after each call to [`hash`](struct.Hasher.html#method.hash),
     /// [`hash_raw`](struct.Hasher#method.hash_raw), or their non-blocking equivalents.
@@ -199,16 +349,85 @@ impl<'a> Hasher<'a> {
         self.config.set_secret_key_clearing(boolean);
         self
     }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to protect the working memory of
+    /// each hash, which holds data derived from the password and secret key: it is locked into
+    /// RAM (`mlock`) so that it is never swapped out, excluded from core dumps
+    /// (`MADV_DONTDUMP`, on Linux), and wiped before it is released. The default is `false`.
+    ///
+    /// Locked memory counts towards the `RLIMIT_MEMLOCK` resource limit of the process (see
+    /// `ulimit -l`), which is often only a few MiB, so raise it to cover `memory_size` times
+    /// the number of hashes that run at once. If the memory cannot be locked, hashing returns
+    /// an [`ErrorKind::MemoryLockError`](enum.ErrorKind.html#variant.MemoryLockError), as it
+    /// does on platforms other than Unix
+    #[cfg(feature = "std")]
+    pub fn configure_secure_memory(&mut self, boolean: bool) -> &mut Hasher<'a> {
+        self.config.set_secure_memory(boolean);
+        self
+    }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to run in shadow mode, which is
+    /// meant for migrating from the C backend to the Rust backend safely. The default is
+    /// `false`.
+    ///
+    /// In shadow mode, every hash is computed by both backends, whatever
+    /// [`Backend`](config/enum.Backend.html) is configured, and the result of the C backend is
+    /// the one returned. If the Rust backend disagrees, a
+    /// [`ShadowMismatch`](output/struct.ShadowMismatch.html) is passed to the callback set
+    /// with [`configure_shadow_callback`](struct.Hasher.html#method.configure_shadow_callback),
+    /// or logged at the error level if you have not set one. Expect hashing to take roughly
+    /// twice as long. <i>Shadow mode requires the `backend-c` feature; without it, hashing
+    /// will error</i>
+    pub fn configure_shadow_mode(&mut self, boolean: bool) -> &mut Hasher<'a> {
+        self.config.set_shadow_mode(boolean);
+        self
+    }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) with a callback that is invoked
+    /// whenever shadow mode (see
+    /// [`configure_shadow_mode`](struct.Hasher.html#method.configure_shadow_mode)) detects
+    /// that the Rust backend disagrees with the C backend. By default, mismatches are logged
+    /// at the error level instead. The callback runs on the thread that computed the hash
+    pub fn configure_shadow_callback<F>(&mut self, callback: F) -> &mut Hasher<'a>
+    where
+        F: Fn(&ShadowMismatch) + Send + Sync + 'static,
+    {
+        self.config
+            .set_shadow_callback(ShadowCallback::new(callback));
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) to use a custom number of
     /// threads. The default is the number of physical cores on your machine. If you choose
     /// a number of threads that is greater than the lanes configuration,
-    /// [`Hasher`](struct.Hasher.html) will use the minimum of the two.
+    /// [`Hasher`](struct.Hasher.html) will use the minimum of the two. <i>If argonautica was
+    /// built without the `std` feature, hashing always runs on a single thread and the
+    /// default is `1`</i>.
     ///
     /// See [configuration example](index.html#configuration) for a more details on this parameter
     pub fn configure_threads(&mut self, threads: u32) -> &mut Hasher<'a> {
         self.config.set_threads(threads);
         self
     }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to give up on hashes that take
+    /// longer than `timeout`, returning an
+    /// [`ErrorKind::TimeoutError`](enum.ErrorKind.html#variant.TimeoutError). For the
+    /// non-blocking methods, the timeout starts when they are called, so time spent waiting
+    /// for a thread counts towards it. Hashing stops at the next slice boundary (there are four
+    /// per iteration), so it may overrun the timeout by up to a quarter of an iteration. The
+    /// default is `None`, i.e. no timeout
+    #[cfg(feature = "std")]
+    pub fn configure_timeout(&mut self, timeout: Option<Duration>) -> &mut Hasher<'a> {
+        self.config.set_timeout(timeout);
+        self
+    }
+    /// Allows you to configure [`Hasher`](struct.Hasher.html) to refuse to hash with a
+    /// configuration that [`HasherConfig::lint`](config/struct.HasherConfig.html#method.lint)
+    /// finds weaknesses in. The default is `false`. If you set this option to `true`,
+    /// [`hash`](struct.Hasher.html#method.hash), [`hash_raw`](struct.Hasher.html#method.hash_raw)
+    /// and their non-blocking equivalents return an
+    /// [`ErrorKind::LintError`](enum.ErrorKind.html#variant.LintError) listing the warnings
+    /// instead of hashing
+    pub fn configure_strict_lint(&mut self, boolean: bool) -> &mut Hasher<'a> {
+        self.config.set_strict_lint(boolean);
+        self
+    }
     /// Allows you to configure [`Hasher`](struct.Hasher.html) to use a custom Argon2
     /// variant. The default is [`Variant::Argon2id`](config/enum.Variant.html#variant.Argon2id).
     /// Do <b>not</b> use a different variant unless you have a specific reason to do so.
@@ -249,11 +468,50 @@ impl<'a> Hasher<'a> {
     /// Same as [`hash`](struct.Hasher.html#method.hash) except it returns a
     /// [`Future`](https://docs.rs/futures/0.1.21/futures/future/trait.Future.html)
     /// instead of a [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
+    #[cfg(feature = "std")]
     pub async fn hash_non_blocking(&mut self) -> Result<String, Error> {
         let hash_raw = self.hash_raw_non_blocking().await?;
         let hash = hash_raw.encode_rust();
         Ok(hash)
     }
+    /// Hashes every password of `passwords` with this [`Hasher`](struct.Hasher.html)'s
+    /// configuration, secret key and additional data, and yields each string-encoded hash
+    /// together with the index of its password, in the given [`BatchOrder`](config/enum.BatchOrder.html).
+    /// An error only fails the hash of its own password; the rest of the batch carries on.
+    ///
+    /// The hashes run like those of [`hash_non_blocking`](struct.Hasher.html#method.hash_non_blocking),
+    /// i.e. on the configured executor or thread pool or else on the global thread pool, as
+    /// many at a time as there are logical cores. Each hash uses a single thread, which gives
+    /// the best throughput, unless the batch is too small to occupy every core (going by the
+    /// stream's [`size_hint`](https://docs.rs/futures/0.3/futures/stream/trait.Stream.html#method.size_hint)),
+    /// in which case each hash gets more threads, up to the configured number. To hash the
+    /// items of an iterator, wrap it in
+    /// [`futures::stream::iter`](https://docs.rs/futures/0.3/futures/stream/fn.iter.html); to
+    /// consume the results from blocking code, use
+    /// [`futures::executor::block_on_stream`](https://docs.rs/futures/0.3/futures/executor/fn.block_on_stream.html).
+    /// Dropping the returned stream cancels the hashes in flight
+    #[cfg(feature = "std")]
+    pub fn hash_many<'p, S, P>(
+        &self,
+        passwords: S,
+        order: BatchOrder,
+    ) -> impl Stream<Item = (usize, Result<String, Error>)> + use<'p, S, P>
+    where
+        S: Stream<Item = P>,
+        P: Into<Password<'p>>,
+    {
+        let mut template = self.to_owned();
+        template.password = None;
+        let threads = template.config.threads();
+        batch::run(passwords, order, threads, move |password, threads| {
+            let password: Password = password.into();
+            let mut hasher = template.to_owned();
+            hasher
+                .configure_threads(threads)
+                .with_password(password.to_owned());
+            async move { hasher.hash_non_blocking().await }
+        })
+    }
     /// Like the [`hash`](struct.Hasher.html#method.hash) method, but instead of producing
     /// an string-encoded hash, it produces a [`HashRaw`](output/struct.HashRaw.html) struct
     /// that contains all the components of the string-encoded version, including the raw
@@ -264,21 +522,56 @@ impl<'a> Hasher<'a> {
             hasher.clear();
         });
         hasher.validate()?;
-        hasher.salt.update()?;
-        let hash_raw = match hasher.config.backend() {
+        #[cfg(feature = "std")]
+        {
+            let timeout = hasher.config.timeout();
+            hasher.cancellation.start(timeout);
+            // Skips jobs that were cancelled or timed out while waiting for a thread
+            hasher.cancellation.check()?;
+        }
+        #[cfg(feature = "std")]
+        let _reservation = memory_budget::reserve(
+            u64::from(hasher.config.memory_size()) * 1024,
+            hasher.config.budget_policy(),
+            &hasher.cancellation,
+        )?;
+        match hasher.config.rng().cloned() {
+            Some(rng) => hasher.salt.update_with(|bytes| rng.call(bytes))?,
+            None => hasher.salt.update()?,
+        }
+        if hasher.config.shadow_mode() {
+            #[cfg(feature = "backend-c")]
+            return hasher.hash_raw_shadow();
+            #[cfg(not(feature = "backend-c"))]
+            return Err(Error::new(ErrorKind::BackendUnsupportedError));
+        }
+        let hash_raw = match resolve_config(&hasher.config) {
+            #[cfg(feature = "backend-c")]
             Backend::C => hasher.hash_raw_c()?,
-            Backend::Rust => return Err(Error::new(ErrorKind::BackendUnsupportedError)),
+            #[cfg(not(feature = "backend-c"))]
+            Backend::C => return Err(Error::new(ErrorKind::BackendUnsupportedError)),
+            Backend::Rust => hasher.hash_raw_rust()?,
+            Backend::Auto => unreachable!(),
         };
         Ok(hash_raw)
     }
     /// Same as [`hash_raw`](struct.Hasher.html#method.hash) except it returns a
     /// [`Future`](https://docs.rs/futures/0.1.21/futures/future/trait.Future.html)
     /// instead of a [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
+    #[cfg(feature = "std")]
     pub async fn hash_raw_non_blocking(&mut self) -> Result<HashRaw, Error> {
         let hasher = scopeguard::guard(self, |hasher| {
             hasher.clear();
         });
         let mut hasher = hasher.to_owned();
+        hasher.cancellation = Cancellation::new(hasher.config.timeout());
+        // Stops the hash if the returned future is dropped before it resolves
+        let _cancel_on_drop = scopeguard::guard(hasher.cancellation.clone(), |cancellation| {
+            cancellation.cancel();
+        });
+        if let Some(executor) = hasher.config.executor().cloned() {
+            return executor.run(move || hasher.hash_raw()).await;
+        }
         match hasher.config.thread_pool() {
             Some(cpu_pool) => {
                 cpu_pool
@@ -286,14 +579,7 @@ impl<'a> Hasher<'a> {
                     .expect("Failed to spawn")
                     .await
             }
-            None => {
-                let cpu_pool = default_cpu_pool();
-                hasher.config.set_thread_pool(cpu_pool.clone());
-                cpu_pool
-                    .spawn_with_handle(async move { hasher.hash_raw() })
-                    .expect("Failed to spawn")
-                    .await
-            }
+            None => pool::spawn(move || hasher.hash_raw()).await,
         }
     }
     /// As an extra security measure, if you want to hash without a secret key, which
@@ -316,6 +602,8 @@ impl<'a> Hasher<'a> {
             .map(|secret_key| secret_key.to_owned());
         Hasher {
             additional_data: self.additional_data.clone(),
+            #[cfg(feature = "std")]
+            cancellation: Cancellation::default(),
             config: self.config.clone(),
             password,
             salt: self.salt.clone(),
@@ -363,20 +651,36 @@ impl<'a> Hasher<'a> {
         S: Into<Salt>,
     {
         self.salt = salt.into();
+        self.config.set_salt_is_random(self.salt.is_random());
+        self.config.set_salt_len(self.salt.len() as u32);
         self
     }
     /// Allows you to provide [`Hasher`](struct.Hasher.html) with a secret key that will be used
     /// to create the hash. The secret key will not be included in the hash output, meaning you
     /// must save it somewhere (ideally outside your code) to use later, as the only way to
     /// verify passwords against the hash later is to know the secret key. This library
-    /// encourages the use of a secret key
+    /// encourages the use of a secret key. Replaces any secret key previously provided via
+    /// [`with_secret_key_ring`](struct.Hasher.html#method.with_secret_key_ring)
     pub fn with_secret_key<SK>(&mut self, secret_key: SK) -> &mut Hasher<'a>
     where
         SK: Into<SecretKey<'a>>,
     {
+        self.config.set_key_id(None);
         self.secret_key = Some(secret_key.into());
         self
     }
+    /// Allows you to provide [`Hasher`](struct.Hasher.html) with a
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html) instead of a single secret key.
+    /// [`Hasher`](struct.Hasher.html) hashes with the ring's active key and includes its ID
+    /// as the `keyid` parameter of the string-encoded hash, so that a
+    /// [`Verifier`](struct.Verifier.html) given the same ring can find the right key even
+    /// after you have rotated to a new one. Only the active key is kept
+    pub fn with_secret_key_ring(&mut self, secret_key_ring: SecretKeyRing<'a>) -> &mut Hasher<'a> {
+        let (key_id, secret_key) = secret_key_ring.into_active();
+        self.config.set_key_id(Some(key_id));
+        self.secret_key = Some(secret_key);
+        self
+    }
     /// Read-only access to the [`Hasher`](struct.Hasher.html)'s
     /// [`AdditionalData`](input/struct.AdditionalData.html), if any
     pub fn additional_data(&self) -> Option<&AdditionalData> {
@@ -405,34 +709,16 @@ impl<'a> Hasher<'a> {
 
 impl Hasher<'_> {
     pub(crate) fn clear(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            self.cancellation = Cancellation::default();
+        }
         if self.password.is_some() && self.config.password_clearing() {
-            {
-                let password_mut_ref = self.password.as_mut().unwrap();
-                match password_mut_ref.inner {
-                    Container::Borrowed(_) => (),
-                    Container::BorrowedMut(ref mut bytes) => {
-                        unsafe { ::std::ptr::write_bytes(bytes.as_mut_ptr(), 0, bytes.len()) };
-                    }
-                    Container::Owned(ref mut bytes) => {
-                        unsafe { ::std::ptr::write_bytes(bytes.as_mut_ptr(), 0, bytes.len()) };
-                    }
-                }
-            }
+            self.password.as_mut().unwrap().inner.clear();
             self.password = None;
         }
         if self.secret_key.is_some() && self.config.secret_key_clearing() {
-            {
-                let secret_key_mut_ref = self.secret_key.as_mut().unwrap();
-                match secret_key_mut_ref.inner {
-                    Container::Borrowed(_) => (),
-                    Container::BorrowedMut(ref mut bytes) => {
-                        unsafe { ::std::ptr::write_bytes(bytes.as_mut_ptr(), 0, bytes.len()) };
-                    }
-                    Container::Owned(ref mut bytes) => {
-                        unsafe { ::std::ptr::write_bytes(bytes.as_mut_ptr(), 0, bytes.len()) };
-                    }
-                }
-            }
+            self.secret_key.as_mut().unwrap().inner.clear();
             self.secret_key = None;
         }
     }
@@ -470,6 +756,8 @@ impl Hasher<'_> {
 
 #[cfg(test)]
 mod tests {
+    use alloc::vec::Vec;
+
     use super::*;
     use crate::config::{Variant, Version};
 
@@ -481,8 +769,16 @@ mod tests {
 
     impl Test {
         fn run(&self) {
+            #[cfg(feature = "backend-c")]
+            self.run_with(Backend::C);
+            self.run_with(Backend::Rust);
+            self.run_with(Backend::Auto);
+        }
+
+        fn run_with(&self, backend: Backend) {
             let mut hasher = Hasher::default();
             let raw_hash = hasher
+                .configure_backend(backend)
                 .configure_hash_len(32)
                 .configure_iterations(3)
                 .configure_lanes(4)
@@ -496,10 +792,26 @@ mod tests {
                 .with_secret_key(vec![3; 8])
                 .hash_raw()
                 .unwrap();
-            assert_eq!(raw_hash.raw_hash_bytes(), self.expected.as_slice());
+            assert_eq!(
+                raw_hash.raw_hash_bytes(),
+                self.expected.as_slice(),
+                "backend: {:?}",
+                backend
+            );
         }
     }
 
+    /// The backends a test should run against, i.e. the Rust backend and, if it is built,
+    /// the C backend
+    #[cfg(feature = "std")]
+    fn test_backends() -> Vec<Backend> {
+        vec![
+            Backend::Rust,
+            #[cfg(feature = "backend-c")]
+            Backend::C,
+        ]
+    }
+
     #[test]
     fn test_hasher_0x10_2d() {
         Test {
@@ -623,6 +935,107 @@ mod tests {
         let _ = hasher.with_password("P@ssw0rd").hash().unwrap();
     }
 
+    #[test]
+    fn test_hasher_from_preset() {
+        let presets = [
+            Preset::Rfc9106First,
+            Preset::Rfc9106Second,
+            Preset::OwaspT1,
+            Preset::OwaspT2,
+            Preset::OwaspT3,
+            Preset::OwaspT4,
+            Preset::OwaspT5,
+        ];
+        for preset in presets.iter().cloned() {
+            let hasher = Hasher::from_preset(preset);
+            let config = hasher.config();
+            assert_eq!(config.hash_len(), preset.hash_len());
+            assert_eq!(config.iterations(), preset.iterations());
+            assert_eq!(config.lanes(), preset.lanes());
+            assert_eq!(config.memory_size(), preset.memory_size());
+            assert_eq!(config.salt_len(), preset.salt_len());
+            assert!(config.validate().is_ok());
+            assert!(config.lint().is_empty(), "{:?}", preset);
+        }
+        assert_eq!(Hasher::rfc9106_first().config().memory_size(), 2_097_152);
+        assert_eq!(Hasher::rfc9106_second().config().iterations(), 3);
+
+        let mut hasher = Hasher::from_preset(Preset::OwaspT5);
+        hasher
+            .configure_threads(1)
+            .with_password("P@ssw0rd")
+            .with_secret_key("secret");
+        #[cfg(not(feature = "std"))]
+        hasher.configure_rng(|bytes: &mut [u8]| {
+            bytes.fill(1);
+            Ok(())
+        });
+        let hash = hasher.hash().unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=7168,t=5,p=1$"));
+        #[cfg(feature = "backend-c")]
+        {
+            // Memory sizes that are not a power of two work the same in both backends
+            hasher.configure_backend(Backend::C).with_salt("somesalt");
+            let expected = hasher.hash().unwrap();
+            hasher.configure_backend(Backend::Rust);
+            assert_eq!(hasher.hash().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_hasher_strict_lint() {
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher.configure_strict_lint(true).with_password("P@ssw0rd");
+        let e = hasher.hash().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::LintError);
+        assert!(!hasher.config().salt_is_random());
+
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_iterations(2)
+            .configure_lanes(1)
+            .configure_memory_size(32_768)
+            .configure_strict_lint(true)
+            .configure_threads(1)
+            .with_password("P@ssw0rd")
+            .with_secret_key("secret");
+        #[cfg(not(feature = "std"))]
+        hasher.configure_rng(|bytes: &mut [u8]| {
+            bytes.fill(1);
+            Ok(())
+        });
+        assert!(hasher.config().lint().is_empty());
+        hasher.hash().unwrap();
+    }
+
+    #[test]
+    fn test_hasher_rng() {
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher.salt = Salt::random(16);
+        hasher.configure_rng(|bytes| {
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = i as u8;
+            }
+            Ok(())
+        });
+        let hash_raw = hasher.with_password("P@ssw0rd").hash_raw().unwrap();
+        let expected = (0..16).collect::<Vec<u8>>();
+        assert_eq!(hash_raw.raw_salt_bytes(), &expected[..]);
+
+        hasher.configure_rng(|_| Err(Error::new(ErrorKind::RngError)));
+        let err = hasher.with_password("P@ssw0rd").hash_raw().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::RngError);
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn test_hasher_rng_missing() {
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher.salt = Salt::random(16);
+        let err = hasher.with_password("P@ssw0rd").hash_raw().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::RngMissingError);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_hasher_serialization() {
@@ -660,6 +1073,280 @@ mod tests {
         assert_eq!(hash_raw1, hash_raw2);
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_executor() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        use futures::executor::block_on;
+
+        use crate::{Verifier, executor::Job};
+
+        struct Counting(Arc<AtomicUsize>);
+
+        impl Executor for Counting {
+            fn execute(&self, job: Job) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                std::thread::spawn(job);
+            }
+        }
+
+        let jobs = Arc::new(AtomicUsize::new(0));
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher
+            .configure_executor(Counting(Arc::clone(&jobs)))
+            .configure_thread_pool(ThreadPool::new().unwrap())
+            .with_password("P@ssw0rd");
+        let hash = block_on(hasher.hash_non_blocking()).unwrap();
+        assert_eq!(jobs.load(Ordering::SeqCst), 1);
+
+        let mut verifier = Verifier::default();
+        verifier
+            .configure_executor(Counting(Arc::clone(&jobs)))
+            .with_hash(&hash)
+            .with_password("P@ssw0rd");
+        assert!(block_on(verifier.verify_non_blocking()).unwrap());
+        assert_eq!(jobs.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_allocator() {
+        use std::sync::Arc;
+
+        use crate::allocator::{PooledAllocator, SystemAllocator};
+
+        let allocator = Arc::new(PooledAllocator::with_allocator(SystemAllocator, 1));
+        for backend in test_backends() {
+            let mut hasher = Hasher::fast_but_insecure();
+            hasher
+                .configure_backend(backend)
+                .configure_lanes(4)
+                .configure_memory_size(256)
+                .configure_threads(4)
+                .with_password("P@ssw0rd");
+            let expected = hasher.hash().unwrap();
+            hasher.configure_allocator(Arc::clone(&allocator));
+            for _ in 0..2 {
+                assert_eq!(hasher.hash().unwrap(), expected);
+                assert_eq!(allocator.idle(), 1);
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_secure_memory() {
+        for backend in test_backends() {
+            let mut hasher = Hasher::fast_but_insecure();
+            hasher
+                .configure_backend(backend)
+                .configure_lanes(2)
+                .configure_memory_size(64)
+                .configure_threads(2)
+                .with_password("P@ssw0rd");
+            let expected = hasher.hash().unwrap();
+            hasher.configure_secure_memory(true);
+            match hasher.hash() {
+                Ok(hash) => assert_eq!(hash, expected),
+                // Locking is allowed to fail, e.g. in a container with a low RLIMIT_MEMLOCK
+                Err(error) => assert_eq!(error.kind(), ErrorKind::MemoryLockError),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_protected_secret_key() {
+        let secret_key = match SecretKey::from("secret").protect() {
+            Ok(secret_key) => secret_key,
+            // Locking is allowed to fail, e.g. in a container with a low RLIMIT_MEMLOCK
+            Err(error) => return assert_eq!(error.kind(), ErrorKind::MemoryLockError),
+        };
+        for backend in test_backends() {
+            let mut hasher = Hasher::fast_but_insecure();
+            hasher
+                .configure_backend(backend)
+                .with_password("P@ssw0rd")
+                .with_secret_key("secret");
+            let expected = hasher.hash().unwrap();
+            hasher.with_secret_key(&secret_key);
+            assert_eq!(hasher.hash().unwrap(), expected);
+            hasher.configure_secret_key_clearing(true);
+            let error = hasher.hash().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::SecretKeyImmutableError);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_lane_scheduling() {
+        let hash_with = |lane_scheduling: LaneScheduling, thread_pool: Option<ThreadPool>| {
+            let mut hasher = Hasher::default();
+            hasher
+                .configure_iterations(2)
+                .configure_lane_scheduling(lane_scheduling)
+                .configure_lanes(4)
+                .configure_memory_size(1024)
+                .configure_threads(4)
+                .opt_out_of_secret_key(true)
+                .with_password("P@ssw0rd")
+                .with_salt("somesalt");
+            if let Some(thread_pool) = thread_pool {
+                hasher.configure_thread_pool(thread_pool);
+            }
+            hasher.hash().unwrap()
+        };
+        let expected = hash_with(LaneScheduling::Threads, None);
+        for lane_scheduling in [LaneScheduling::Pool, LaneScheduling::Adaptive] {
+            assert_eq!(hash_with(lane_scheduling, None), expected);
+            let thread_pool = ThreadPool::builder().pool_size(2).create().unwrap();
+            for _ in 0..3 {
+                assert_eq!(
+                    hash_with(lane_scheduling, Some(thread_pool.clone())),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash_many() {
+        use futures::{StreamExt, executor::block_on, stream};
+
+        use crate::Verifier;
+
+        let passwords = vec!["P@ssw0rd0", "P@ssw0rd1", "", "P@ssw0rd3"];
+        let mut hasher = Hasher::fast_but_insecure();
+        hasher.with_secret_key("secret");
+        let results = block_on(
+            hasher
+                .hash_many(stream::iter(passwords.clone()), BatchOrder::Input)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(results.len(), 4);
+        let mut hashes = Vec::new();
+        for (i, (index, result)) in results.into_iter().enumerate() {
+            assert_eq!(index, i);
+            match result {
+                Ok(hash) => hashes.push(hash),
+                Err(e) => {
+                    assert_eq!(index, 2);
+                    assert_eq!(e.kind(), ErrorKind::PasswordTooShortError);
+                    hashes.push("not a hash".to_string());
+                }
+            }
+        }
+
+        let mut verifier = Verifier::default();
+        verifier.with_secret_key("secret");
+        let items = hashes
+            .iter()
+            .zip(vec!["P@ssw0rd0", "P@ssw0rd1", "P@ssw0rd2", "wrong"])
+            .collect::<Vec<_>>();
+        let mut results = block_on(
+            verifier
+                .verify_many(stream::iter(items), BatchOrder::Completion)
+                .collect::<Vec<_>>(),
+        );
+        results.sort_by_key(|(index, _)| *index);
+        assert!(results[0].1.as_ref().unwrap());
+        assert!(results[1].1.as_ref().unwrap());
+        assert_eq!(
+            results[2].1.as_ref().unwrap_err().kind(),
+            ErrorKind::HashDecodeError
+        );
+        assert!(!results[3].1.as_ref().unwrap());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hasher_cancellation() {
+        use std::{
+            sync::{Arc, Mutex},
+            time::Instant,
+        };
+
+        use futures::{FutureExt, executor::block_on};
+
+        use crate::{Verifier, executor::Job};
+
+        fn slow_hasher() -> Hasher<'static> {
+            let mut hasher = Hasher::default();
+            hasher
+                .configure_iterations(100_000)
+                .configure_lanes(1)
+                .configure_memory_size(1024)
+                .configure_threads(1)
+                .opt_out_of_secret_key(true)
+                .with_password("P@ssw0rd")
+                .with_salt("somesaltsomesalt");
+            hasher
+        }
+
+        // Backends stop between slices
+        for backend in test_backends() {
+            let mut hasher = slow_hasher();
+            hasher.configure_backend(backend);
+            hasher.cancellation = Cancellation::new(Some(Duration::from_millis(50)));
+            let e = match backend {
+                #[cfg(feature = "backend-c")]
+                Backend::C => hasher.hash_raw_c().unwrap_err(),
+                _ => hasher.hash_raw_rust().unwrap_err(),
+            };
+            assert_eq!(e.kind(), ErrorKind::TimeoutError, "{:?}", backend);
+
+            hasher.cancellation = Cancellation::new(None);
+            hasher.cancellation.cancel();
+            let e = match backend {
+                #[cfg(feature = "backend-c")]
+                Backend::C => hasher.hash_raw_c().unwrap_err(),
+                _ => hasher.hash_raw_rust().unwrap_err(),
+            };
+            assert_eq!(e.kind(), ErrorKind::CancelledError, "{:?}", backend);
+        }
+
+        // Timeouts
+        let mut hasher = slow_hasher();
+        hasher.configure_timeout(Some(Duration::from_millis(50)));
+        let e = hasher.hash().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TimeoutError);
+        let e = block_on(hasher.hash_non_blocking()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TimeoutError);
+        let mut verifier = Verifier::default();
+        verifier
+            .configure_timeout(Some(Duration::from_millis(50)))
+            .with_hash("$argon2id$v=19$m=1024,t=100000,p=1$c29tZXNhbHRzb21lc2FsdA$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
+            .with_password("P@ssw0rd");
+        verifier.configure_max_iterations(100_000);
+        let e = block_on(verifier.verify_non_blocking()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TimeoutError);
+
+        // Dropping the future skips the job
+        struct Deferred(Arc<Mutex<Vec<Job>>>);
+
+        impl Executor for Deferred {
+            fn execute(&self, job: Job) {
+                self.0.lock().unwrap().push(job);
+            }
+        }
+
+        let jobs = Arc::new(Mutex::new(Vec::new()));
+        let mut hasher = slow_hasher();
+        hasher.configure_executor(Deferred(Arc::clone(&jobs)));
+        let mut future = Box::pin(hasher.hash_raw_non_blocking());
+        assert!(future.as_mut().now_or_never().is_none());
+        drop(future);
+        let job = jobs.lock().unwrap().pop().unwrap();
+        let start = Instant::now();
+        job();
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
     #[test]
     fn test_send() {
         fn assert_send<T: Send>() {}
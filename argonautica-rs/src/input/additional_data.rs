@@ -1,3 +1,5 @@
+use alloc::{string::String, vec::Vec};
+
 use crate::{Error, ErrorKind};
 
 impl From<Vec<u8>> for AdditionalData {
@@ -60,7 +62,7 @@ impl AdditionalData {
     }
     /// Read-only access to the underlying byte buffer as a `&str` if its bytes are valid utf-8
     pub fn to_str(&self) -> Result<&str, Error> {
-        let s = ::std::str::from_utf8(self.as_bytes()).map_err(|_| {
+        let s = ::core::str::from_utf8(self.as_bytes()).map_err(|_| {
             Error::new(ErrorKind::Utf8EncodeError)
                 .add_context(format!("Bytes: {:?}", self.as_bytes()))
         })?;
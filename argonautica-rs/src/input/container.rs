@@ -1,8 +1,23 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use zeroize::Zeroize;
+
+#[cfg(feature = "std")]
+use crate::secure_memory::GuardedBytes;
+
+/// The bytes of a [`Password`](struct.Password.html) or [`SecretKey`](struct.SecretKey.html).
+/// Owned bytes (including those copied by `to_owned`) are zeroized on drop; borrowed bytes
+/// belong to the caller and are only zeroized by [`clear`](#method.clear)
 #[derive(Debug, Eq, PartialEq, Hash)]
 pub(crate) enum Container<'a> {
     Borrowed(&'a [u8]),
     BorrowedMut(&'a mut [u8]),
     Owned(Vec<u8>),
+    /// The bytes of a protected [`SecretKey`](struct.SecretKey.html), shared by all its copies
+    #[cfg(feature = "std")]
+    Protected(Arc<GuardedBytes>),
 }
 
 impl Container<'_> {
@@ -11,6 +26,26 @@ impl Container<'_> {
             Container::Borrowed(bytes) => Container::Owned(bytes.to_vec()),
             Container::BorrowedMut(bytes) => Container::Owned(bytes.to_vec()),
             Container::Owned(bytes) => Container::Owned(bytes.to_vec()),
+            #[cfg(feature = "std")]
+            Container::Protected(bytes) => Container::Protected(Arc::clone(bytes)),
+        }
+    }
+    /// Zeroes out the underlying bytes, unless they are borrowed immutably or protected
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Container::Borrowed(_) => (),
+            #[cfg(feature = "std")]
+            Container::Protected(_) => (),
+            Container::BorrowedMut(bytes) => bytes.zeroize(),
+            Container::Owned(bytes) => bytes.as_mut_slice().zeroize(),
+        }
+    }
+}
+
+impl Drop for Container<'_> {
+    fn drop(&mut self) {
+        if let Container::Owned(ref mut bytes) = *self {
+            bytes.zeroize();
         }
     }
 }
@@ -2,7 +2,8 @@
 //! [`Password`](input/struct.Password.html),
 //! [`Salt`](input/struct.Salt.html),
 //! [`SecretKey`](input/struct.SecretKey.html), and
-//! [`AdditionalData`](input/struct.AdditionalData.html))
+//! [`AdditionalData`](input/struct.AdditionalData.html)), along with
+//! [`SecretKeyRing`](input/struct.SecretKeyRing.html) for rotating secret keys
 //!
 //! All the stucts below can be constructed from
 //! [`Vec<u8>`](https://doc.rust-lang.org/std/vec/struct.Vec.html),
@@ -14,13 +15,26 @@
 //! constructors as well, e.g. [`Salt::random(...)`](struct.Salt.html#method.random), which
 //! produces a [`Salt`](struct.Salt.html) that will create new crytographically-secure,
 //! random bytes after each hash.
+//!
+//! [`Password`](struct.Password.html) and [`SecretKey`](struct.SecretKey.html) can also be
+//! constructed from [`Zeroizing<String>`](https://docs.rs/zeroize) and `Zeroizing<Vec<u8>>`
+//! without copying, or, with the `secrecy` feature, from
+//! [`SecretString`](https://docs.rs/secrecy) and `SecretSlice<u8>`. Whatever bytes these structs
+//! (and [`Salt`](struct.Salt.html)) own, including the copies made by `to_owned`, are zeroized
+//! when they are dropped. Borrowed bytes belong to you and are left alone, unless you ask the
+//! hasher or verifier to clear them after hashing. For a secret key that lives as long as your
+//! process does, [`SecretKey::protect`](struct.SecretKey.html#method.protect) moves a copy onto
+//! locked, guarded pages that are only readable while a hash is running.
 mod additional_data;
 mod container;
 mod password;
 mod salt;
 mod secret_key;
+mod secret_key_ring;
 
 pub(crate) use self::container::Container;
+pub(crate) use self::secret_key_ring::{MAX_KEY_ID_LEN, unknown_key_id};
 pub use self::{
     additional_data::AdditionalData, password::Password, salt::Salt, secret_key::SecretKey,
+    secret_key::Unlocked, secret_key_ring::SecretKeyRing,
 };
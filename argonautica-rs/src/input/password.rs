@@ -1,4 +1,9 @@
-use std::fmt;
+use alloc::{string::String, vec::Vec};
+use core::{fmt, mem};
+
+#[cfg(feature = "secrecy")]
+use secrecy::{ExposeSecret, ExposeSecretMut, SecretSlice, SecretString};
+use zeroize::Zeroizing;
 
 use crate::{Error, ErrorKind, input::Container};
 
@@ -84,12 +89,89 @@ impl<'a> From<Vec<u8>> for Password<'a> {
     }
 }
 
+impl<'a> From<Zeroizing<String>> for Password<'a> {
+    fn from(mut s: Zeroizing<String>) -> Password<'a> {
+        // Moves the bytes out instead of copying them, so that the only copy is the one that
+        // is zeroized when the Password is dropped
+        Password {
+            inner: Container::Owned(mem::take(&mut *s).into_bytes()),
+        }
+    }
+}
+
+impl<'a> From<Zeroizing<Vec<u8>>> for Password<'a> {
+    fn from(mut bytes: Zeroizing<Vec<u8>>) -> Password<'a> {
+        Password {
+            inner: Container::Owned(mem::take(&mut *bytes)),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a SecretString> for Password<'a> {
+    fn from(s: &'a SecretString) -> Password<'a> {
+        Password {
+            inner: Container::Borrowed(s.expose_secret().as_bytes()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a mut SecretString> for Password<'a> {
+    fn from(s: &'a mut SecretString) -> Password<'a> {
+        // Safe, as the only thing that writes to the bytes is clearing them, which leaves
+        // valid UTF-8 behind
+        let bytes = unsafe { s.expose_secret_mut().as_bytes_mut() };
+        Password {
+            inner: Container::BorrowedMut(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<SecretString> for Password<'a> {
+    fn from(s: SecretString) -> Password<'a> {
+        Password {
+            inner: Container::Owned(s.expose_secret().as_bytes().to_vec()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a SecretSlice<u8>> for Password<'a> {
+    fn from(bytes: &'a SecretSlice<u8>) -> Password<'a> {
+        Password {
+            inner: Container::Borrowed(bytes.expose_secret()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a mut SecretSlice<u8>> for Password<'a> {
+    fn from(bytes: &'a mut SecretSlice<u8>) -> Password<'a> {
+        Password {
+            inner: Container::BorrowedMut(bytes.expose_secret_mut()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<SecretSlice<u8>> for Password<'a> {
+    fn from(bytes: SecretSlice<u8>) -> Password<'a> {
+        Password {
+            inner: Container::Owned(bytes.expose_secret().to_vec()),
+        }
+    }
+}
+
 impl<'a> From<&'a Password<'a>> for Password<'a> {
     fn from(sk: &'a Password<'a>) -> Password<'a> {
         let bytes = match sk.inner {
             Container::Borrowed(bytes) => bytes,
             Container::BorrowedMut(ref bytes) => &**bytes,
             Container::Owned(ref bytes) => bytes,
+            #[cfg(feature = "std")]
+            Container::Protected(_) => unreachable!("Only secret keys are protected"),
         };
         Password {
             inner: Container::Borrowed(bytes),
@@ -109,6 +191,8 @@ impl<'a> From<&'a mut Password<'a>> for Password<'a> {
             Container::Owned(ref mut bytes) => Password {
                 inner: Container::BorrowedMut(&mut *bytes),
             },
+            #[cfg(feature = "std")]
+            Container::Protected(_) => unreachable!("Only secret keys are protected"),
         }
     }
 }
@@ -132,6 +216,8 @@ impl Password<'_> {
             Container::Borrowed(bytes) => bytes,
             Container::BorrowedMut(ref bytes) => bytes,
             Container::Owned(ref bytes) => bytes,
+            #[cfg(feature = "std")]
+            Container::Protected(_) => unreachable!("Only secret keys are protected"),
         }
     }
     /// Indicates whether the underlying byte buffer is mutable or not. The underlying byte
@@ -159,7 +245,7 @@ impl Password<'_> {
     }
     /// Read-only access to the underlying byte buffer as a `&str` if its bytes are valid utf-8
     pub fn to_str(&self) -> Result<&str, Error> {
-        let s = ::std::str::from_utf8(self.as_bytes())
+        let s = ::core::str::from_utf8(self.as_bytes())
             .map_err(|_| Error::new(ErrorKind::Utf8EncodeError))?;
         Ok(s)
     }
@@ -177,3 +263,29 @@ impl Password<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_zeroizing() {
+        let s = Zeroizing::new(String::from("P@ssw0rd"));
+        let ptr = s.as_ptr();
+        let password = Password::from(s);
+        assert_eq!(password.as_bytes(), b"P@ssw0rd");
+        assert_eq!(password.as_bytes().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Password>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Password>();
+    }
+}
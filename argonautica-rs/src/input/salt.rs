@@ -1,4 +1,8 @@
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "std")]
 use rand::{TryRngCore, rngs::OsRng};
+use zeroize::Zeroize;
 
 use crate::{Error, ErrorKind};
 
@@ -63,9 +67,10 @@ impl From<&Salt> for Salt {
 ///   constructors. It will be <b>deterministic</b> if it's constructed via any of the various
 ///   `From` implementations
 /// * A <b>random</b> `Salt` will generate new random bytes using a cryptographically-secure
-///   random number generator (`OsRng`) upon each call to `hash`, `hash_raw` or their
-///   non-blocking equivalents. A <b>deterministic</b> `Salt` remain constant upon each of these
-///   calls</i>
+///   random number generator (`OsRng`, or the one configured with
+///   [`configure_rng`](../struct.Hasher.html#method.configure_rng)) upon each call to `hash`,
+///   `hash_raw` or their non-blocking equivalents. A <b>deterministic</b> `Salt` remain
+///   constant upon each of these calls</i>
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
@@ -79,6 +84,14 @@ enum Kind {
     Random(Vec<u8>),
 }
 
+impl Drop for Kind {
+    fn drop(&mut self) {
+        match *self {
+            Kind::Deterministic(ref mut bytes) | Kind::Random(ref mut bytes) => bytes.zeroize(),
+        }
+    }
+}
+
 impl Salt {
     /// Creates a new <u>random</u> `Salt`.
     ///
@@ -111,24 +124,48 @@ impl Salt {
     }
     /// Read-only access to the underlying byte buffer as a `&str` if its bytes are valid utf-8
     pub fn to_str(&self) -> Result<&str, Error> {
-        let s = ::std::str::from_utf8(self.as_bytes()).map_err(|_| {
+        let s = ::core::str::from_utf8(self.as_bytes()).map_err(|_| {
             Error::new(ErrorKind::Utf8EncodeError)
                 .add_context(format!("Bytes: {:?}", self.as_bytes()))
         })?;
         Ok(s)
     }
     /// If you have a <u>random</u> `Salt`, this method will generate new random bytes of the
-    /// length of your `Salt`. If you have a <u>deterministic</u> `Salt`, this method does nothing
+    /// length of your `Salt`. If you have a <u>deterministic</u> `Salt`, this method does nothing.
+    ///
+    /// <i>If argonautica was built without the `std` feature there is no `OsRng`, so this
+    /// returns an error for a <u>random</u> `Salt`; use
+    /// [`update_with`](struct.Salt.html#method.update_with) instead</i>
     pub fn update(&mut self) -> Result<(), Error> {
+        self.update_with(fill_with_os_rng)
+    }
+    /// Same as [`update`](struct.Salt.html#method.update), except that the new random bytes
+    /// are generated by `fill`, which must fill the buffer it is given with
+    /// cryptographically-secure random bytes. Use this to supply your own random number
+    /// generator, e.g. a hardware RNG on a target without an operating system
+    pub fn update_with<F>(&mut self, fill: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), Error>,
+    {
         if let Kind::Random(ref mut bytes) = self.0 {
-            OsRng
-                .try_fill_bytes(bytes)
-                .map_err(|_| Error::new(ErrorKind::OsRngError))?;
+            fill(bytes)?;
         }
         Ok(())
     }
 }
 
+#[cfg(feature = "std")]
+fn fill_with_os_rng(bytes: &mut [u8]) -> Result<(), Error> {
+    OsRng
+        .try_fill_bytes(bytes)
+        .map_err(|_| Error::new(ErrorKind::OsRngError))
+}
+
+#[cfg(not(feature = "std"))]
+fn fill_with_os_rng(_bytes: &mut [u8]) -> Result<(), Error> {
+    Err(Error::new(ErrorKind::RngMissingError))
+}
+
 impl Salt {
     pub(crate) fn validate(&self) -> Result<(), Error> {
         let len = self.len();
@@ -150,6 +187,37 @@ impl Salt {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_update_with() {
+        let mut salt = Salt::random(16);
+        salt.update_with(|bytes| {
+            bytes.copy_from_slice(&[1u8; 16]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(salt.as_bytes(), &[1u8; 16]);
+
+        let error = salt
+            .update_with(|_| Err(Error::new(ErrorKind::RngError)))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::RngError);
+
+        let mut salt = Salt::from("somesalt");
+        salt.update_with(|_| panic!("deterministic salts are never updated"))
+            .unwrap();
+        assert_eq!(salt.as_bytes(), b"somesalt");
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn test_update_without_std() {
+        let mut salt = Salt::random(16);
+        assert_eq!(
+            salt.update().unwrap_err().kind(),
+            ErrorKind::RngMissingError
+        );
+    }
+
     #[test]
     fn test_send() {
         fn assert_send<T: Send>() {}
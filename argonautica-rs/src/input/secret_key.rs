@@ -1,10 +1,19 @@
-use std::fmt;
+use alloc::{string::String, vec::Vec};
+use core::{fmt, mem, ops::Deref};
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 use base64::{
     Engine,
     engine::{GeneralPurpose, general_purpose::STANDARD},
 };
 
+#[cfg(feature = "secrecy")]
+use secrecy::{ExposeSecret, ExposeSecretMut, SecretSlice, SecretString};
+use zeroize::Zeroizing;
+
+#[cfg(feature = "std")]
+use crate::secure_memory::{Access, GuardedBytes};
 use crate::{Error, ErrorKind, input::Container};
 
 impl<'a> From<&'a str> for SecretKey<'a> {
@@ -89,12 +98,93 @@ impl From<Vec<u8>> for SecretKey<'_> {
     }
 }
 
+impl<'a> From<Zeroizing<String>> for SecretKey<'a> {
+    fn from(mut s: Zeroizing<String>) -> SecretKey<'a> {
+        // Moves the bytes out instead of copying them, so that the only copy is the one that
+        // is zeroized when the SecretKey is dropped
+        SecretKey {
+            inner: Container::Owned(mem::take(&mut *s).into_bytes()),
+        }
+    }
+}
+
+impl<'a> From<Zeroizing<Vec<u8>>> for SecretKey<'a> {
+    fn from(mut bytes: Zeroizing<Vec<u8>>) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::Owned(mem::take(&mut *bytes)),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a SecretString> for SecretKey<'a> {
+    fn from(s: &'a SecretString) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::Borrowed(s.expose_secret().as_bytes()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a mut SecretString> for SecretKey<'a> {
+    fn from(s: &'a mut SecretString) -> SecretKey<'a> {
+        // Safe, as the only thing that writes to the bytes is clearing them, which leaves
+        // valid UTF-8 behind
+        let bytes = unsafe { s.expose_secret_mut().as_bytes_mut() };
+        SecretKey {
+            inner: Container::BorrowedMut(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<SecretString> for SecretKey<'a> {
+    fn from(s: SecretString) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::Owned(s.expose_secret().as_bytes().to_vec()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a SecretSlice<u8>> for SecretKey<'a> {
+    fn from(bytes: &'a SecretSlice<u8>) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::Borrowed(bytes.expose_secret()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<&'a mut SecretSlice<u8>> for SecretKey<'a> {
+    fn from(bytes: &'a mut SecretSlice<u8>) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::BorrowedMut(bytes.expose_secret_mut()),
+        }
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl<'a> From<SecretSlice<u8>> for SecretKey<'a> {
+    fn from(bytes: SecretSlice<u8>) -> SecretKey<'a> {
+        SecretKey {
+            inner: Container::Owned(bytes.expose_secret().to_vec()),
+        }
+    }
+}
+
 impl<'a> From<&'a SecretKey<'a>> for SecretKey<'a> {
     fn from(sk: &'a SecretKey<'a>) -> SecretKey<'a> {
         let bytes = match sk.inner {
             Container::Borrowed(bytes) => bytes,
             Container::BorrowedMut(ref bytes) => &**bytes,
             Container::Owned(ref bytes) => bytes,
+            #[cfg(feature = "std")]
+            Container::Protected(ref bytes) => {
+                return SecretKey {
+                    inner: Container::Protected(Arc::clone(bytes)),
+                };
+            }
         };
         SecretKey {
             inner: Container::Borrowed(bytes),
@@ -114,6 +204,10 @@ impl<'a> From<&'a mut SecretKey<'a>> for SecretKey<'a> {
             Container::Owned(ref mut bytes) => SecretKey {
                 inner: Container::BorrowedMut(&mut *bytes),
             },
+            #[cfg(feature = "std")]
+            Container::Protected(ref bytes) => SecretKey {
+                inner: Container::Protected(Arc::clone(bytes)),
+            },
         }
     }
 }
@@ -162,13 +256,19 @@ impl SecretKey<'_> {
             inner: Container::Owned(bytes),
         })
     }
-    /// Read-only access to the underlying byte buffer
-    pub fn as_bytes(&self) -> &[u8] {
-        match self.inner {
-            Container::Borrowed(bytes) => bytes,
-            Container::BorrowedMut(ref bytes) => bytes,
-            Container::Owned(ref bytes) => bytes,
-        }
+    /// Read-only access to the underlying byte buffer. For a
+    /// [protected](struct.SecretKey.html#method.protect) [`SecretKey`](struct.SecretKey.html),
+    /// the bytes are readable only for as long as the returned
+    /// [`Unlocked`](struct.Unlocked.html) (or another one) is alive.
+    ///
+    /// # Panics
+    ///
+    /// This is a convenience wrapper around
+    /// [`try_as_bytes`](struct.SecretKey.html#method.try_as_bytes) that panics if the pages of a
+    /// protected [`SecretKey`](struct.SecretKey.html) could not be made readable. Use
+    /// [`try_as_bytes`](struct.SecretKey.html#method.try_as_bytes) to handle that case instead
+    pub fn as_bytes(&self) -> Unlocked<'_, [u8]> {
+        self.try_as_bytes().unwrap_or_else(|e| panic!("{}", e))
     }
     /// Indicates whether the underlying byte buffer is mutable or not. The underlying byte
     /// buffer is mutable when the [`SecretKey`](struct.SecretKey.html) was constructed
@@ -178,12 +278,47 @@ impl SecretKey<'_> {
     /// (such as a `&str` or a `&[u8]`). The [`SecretKey`](struct.SecretKey.html) must be mutable
     /// in order to hash or verify with the `secret_key_clearing` configuration set to `true`
     pub fn is_mutable(&self) -> bool {
-        !matches!(self.inner, Container::Borrowed(_))
+        match self.inner {
+            Container::Borrowed(_) => false,
+            Container::BorrowedMut(_) | Container::Owned(_) => true,
+            #[cfg(feature = "std")]
+            Container::Protected(_) => false,
+        }
+    }
+    /// Indicates whether the underlying byte buffer is stored on guarded pages, i.e. whether
+    /// the [`SecretKey`](struct.SecretKey.html) was constructed with
+    /// [`protect`](struct.SecretKey.html#method.protect)
+    #[cfg(feature = "std")]
+    pub fn is_protected(&self) -> bool {
+        matches!(self.inner, Container::Protected(_))
     }
     /// Read-only acccess to the underlying byte buffer's length (in number of bytes)
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        self.as_bytes().len()
+        match self.inner {
+            #[cfg(feature = "std")]
+            Container::Protected(ref bytes) => bytes.len(),
+            _ => self.as_bytes().len(),
+        }
+    }
+    /// Copies the underlying byte buffer onto pages of its own, with an inaccessible guard
+    /// page on either side, and returns a new, immutable [`SecretKey`](struct.SecretKey.html)
+    /// backed by them. Use this method for secret keys that live as long as your process does.
+    /// The pages are locked into RAM and excluded from core dumps, and they can only be read
+    /// while a hash is running or while you hold the [`Unlocked`](struct.Unlocked.html)
+    /// returned by [`as_bytes`](struct.SecretKey.html#method.as_bytes). Copies made with
+    /// [`to_owned`](struct.SecretKey.html#method.to_owned) share the same pages, which are
+    /// zeroized and released when the last copy is dropped.
+    ///
+    /// Returns an [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError)
+    /// if the pages could not be locked, e.g. because `RLIMIT_MEMLOCK` is too low, or on
+    /// platforms other than unix
+    #[cfg(feature = "std")]
+    pub fn protect(&self) -> Result<SecretKey<'static>, Error> {
+        let bytes = GuardedBytes::new(&self.try_as_bytes()?)?;
+        Ok(SecretKey {
+            inner: Container::Protected(Arc::new(bytes)),
+        })
     }
     /// Clones the underlying byte buffer and returns a new
     /// [`SecretKey`](struct.SecretKey.html) with a `static` lifetime. Use this method if you
@@ -203,11 +338,94 @@ impl SecretKey<'_> {
     pub fn to_base64_encoded_config(&self, engine: GeneralPurpose) -> String {
         engine.encode(self.as_bytes())
     }
-    /// Read-only access to the underlying byte buffer as a `&str` if its bytes are valid utf-8
-    pub fn to_str(&self) -> Result<&str, Error> {
-        let s = ::std::str::from_utf8(self.as_bytes())
-            .map_err(|_| Error::new(ErrorKind::Utf8EncodeError))?;
-        Ok(s)
+    /// Read-only access to the underlying byte buffer as a `&str` if its bytes are valid utf-8.
+    /// Like [`as_bytes`](struct.SecretKey.html#method.as_bytes), the `&str` of a protected
+    /// [`SecretKey`](struct.SecretKey.html) is readable only while the returned
+    /// [`Unlocked`](struct.Unlocked.html) is alive
+    pub fn to_str(&self) -> Result<Unlocked<'_, str>, Error> {
+        let utf8_error = |_| Error::new(ErrorKind::Utf8EncodeError);
+        let inner = match self.try_as_bytes()?.inner {
+            Inner::Plain(bytes) => Inner::Plain(::core::str::from_utf8(bytes).map_err(utf8_error)?),
+            #[cfg(feature = "std")]
+            Inner::Protected { access, .. } => {
+                ::core::str::from_utf8(access.as_bytes()).map_err(utf8_error)?;
+                Inner::Protected {
+                    access,
+                    // Safe, as the bytes were just checked and are never written to
+                    view: |bytes| unsafe { ::core::str::from_utf8_unchecked(bytes) },
+                }
+            }
+        };
+        Ok(Unlocked { inner })
+    }
+    /// Same as [`as_bytes`](struct.SecretKey.html#method.as_bytes), but returns an
+    /// [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError) instead of
+    /// panicking if the pages of a protected [`SecretKey`](struct.SecretKey.html) could not be
+    /// made readable
+    pub fn try_as_bytes(&self) -> Result<Unlocked<'_, [u8]>, Error> {
+        let value: &[u8] = match self.inner {
+            Container::Borrowed(bytes) => bytes,
+            Container::BorrowedMut(ref bytes) => bytes,
+            Container::Owned(ref bytes) => bytes,
+            #[cfg(feature = "std")]
+            Container::Protected(ref bytes) => {
+                return Ok(Unlocked {
+                    inner: Inner::Protected {
+                        access: bytes.access()?,
+                        view: |bytes| bytes,
+                    },
+                });
+            }
+        };
+        Ok(Unlocked {
+            inner: Inner::Plain(value),
+        })
+    }
+}
+
+/// Read-only access to the bytes of a [`SecretKey`](struct.SecretKey.html) (or to them as a
+/// `&str`), as returned by [`as_bytes`](struct.SecretKey.html#method.as_bytes),
+/// [`try_as_bytes`](struct.SecretKey.html#method.try_as_bytes) and
+/// [`to_str`](struct.SecretKey.html#method.to_str). The bytes of a
+/// [protected](struct.SecretKey.html#method.protect) [`SecretKey`](struct.SecretKey.html) are
+/// inaccessible again once every `Unlocked` is dropped, so hold on to it no longer than you
+/// need to
+pub struct Unlocked<'a, T: ?Sized> {
+    inner: Inner<'a, T>,
+}
+
+enum Inner<'a, T: ?Sized> {
+    Plain(&'a T),
+    /// The bytes of a protected [`SecretKey`], which are only borrowed from `access` (and never
+    /// handed out for longer than `access` lives), as they are inaccessible again once it drops
+    #[cfg(feature = "std")]
+    Protected {
+        access: Access<'a>,
+        /// Views the bytes as a `T`, which they were checked to be
+        view: fn(&[u8]) -> &T,
+    },
+}
+
+impl<T: ?Sized> AsRef<T> for Unlocked<'_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> Deref for Unlocked<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match self.inner {
+            Inner::Plain(value) => value,
+            #[cfg(feature = "std")]
+            Inner::Protected { ref access, view } => view(access.as_bytes()),
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Unlocked<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "****")
     }
 }
 
@@ -220,3 +438,41 @@ impl SecretKey<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_protect() {
+        let secret_key = SecretKey::from("secret");
+        let protected = match secret_key.protect() {
+            Ok(protected) => protected,
+            // Locking is allowed to fail, e.g. in a container with a low RLIMIT_MEMLOCK
+            Err(error) => return assert_eq!(error.kind(), ErrorKind::MemoryLockError),
+        };
+        assert!(protected.is_protected());
+        assert!(!protected.is_mutable());
+        assert_eq!(protected.len(), 6);
+        let owned = protected.to_owned();
+        assert!(owned.is_protected());
+        drop(protected);
+        let bytes = owned.try_as_bytes().unwrap();
+        assert_eq!(&*owned.to_str().unwrap(), "secret");
+        assert_eq!(&*bytes, b"secret");
+        assert_eq!(owned.to_base64_encoded(), secret_key.to_base64_encoded());
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SecretKey>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SecretKey>();
+    }
+}
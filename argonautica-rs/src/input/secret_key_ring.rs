@@ -0,0 +1,197 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+
+use crate::{Error, ErrorKind, input::SecretKey};
+
+/// The [PHC string format](https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md#argon2-encoding)
+/// limits key IDs to 8 bytes
+pub(crate) const MAX_KEY_ID_LEN: usize = 8;
+
+/// Collection of [`SecretKey`](struct.SecretKey.html)s indexed by key ID, one of which is
+/// the active key. Use it to rotate secret keys without invalidating existing hashes.
+///
+/// A [`Hasher`](../struct.Hasher.html) given a [`SecretKeyRing`](struct.SecretKeyRing.html)
+/// hashes with the active key and records its ID in the `keyid` parameter of the
+/// string-encoded hash. A [`Verifier`](../struct.Verifier.html) given a
+/// [`SecretKeyRing`](struct.SecretKeyRing.html) looks up the key named by the hash it verifies
+/// against, so hashes created with keys that have since been retired (i.e. keys that are
+/// still in the ring but no longer active) continue to verify. Key IDs are between 1 and 8
+/// bytes long
+#[derive(Debug, Eq, PartialEq)]
+pub struct SecretKeyRing<'a> {
+    active_key_id: Vec<u8>,
+    keys: BTreeMap<Vec<u8>, SecretKey<'a>>,
+}
+
+impl<'a> SecretKeyRing<'a> {
+    /// Creates a new [`SecretKeyRing`](struct.SecretKeyRing.html) whose only (and therefore
+    /// active) key is `active_key`
+    pub fn new<K, SK>(active_key_id: K, active_key: SK) -> Result<SecretKeyRing<'a>, Error>
+    where
+        K: AsRef<[u8]>,
+        SK: Into<SecretKey<'a>>,
+    {
+        let active_key_id = active_key_id.as_ref();
+        validate_key_id(active_key_id)?;
+        let mut keys = BTreeMap::new();
+        keys.insert(active_key_id.to_vec(), active_key.into());
+        Ok(SecretKeyRing {
+            active_key_id: active_key_id.to_vec(),
+            keys,
+        })
+    }
+    /// Adds a key to the [`SecretKeyRing`](struct.SecretKeyRing.html), replacing any existing
+    /// key with the same ID. The active key does not change; call
+    /// [`set_active_key_id`](struct.SecretKeyRing.html#method.set_active_key_id) to start
+    /// hashing with the new key
+    pub fn insert<K, SK>(
+        &mut self,
+        key_id: K,
+        secret_key: SK,
+    ) -> Result<&mut SecretKeyRing<'a>, Error>
+    where
+        K: AsRef<[u8]>,
+        SK: Into<SecretKey<'a>>,
+    {
+        let key_id = key_id.as_ref();
+        validate_key_id(key_id)?;
+        self.keys.insert(key_id.to_vec(), secret_key.into());
+        Ok(self)
+    }
+    /// Makes the key with the given ID the active key, retiring the previously active one.
+    /// Returns an error if there is no key with that ID in the
+    /// [`SecretKeyRing`](struct.SecretKeyRing.html)
+    pub fn set_active_key_id<K>(&mut self, key_id: K) -> Result<&mut SecretKeyRing<'a>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_id = key_id.as_ref();
+        if !self.keys.contains_key(key_id) {
+            return Err(unknown_key_id(key_id));
+        }
+        self.active_key_id = key_id.to_vec();
+        Ok(self)
+    }
+    /// Read-only access to the active key
+    pub fn active_key(&self) -> &SecretKey<'a> {
+        &self.keys[&self.active_key_id]
+    }
+    /// Read-only access to the ID of the active key
+    pub fn active_key_id(&self) -> &[u8] {
+        &self.active_key_id
+    }
+    /// Read-only access to the key with the given ID, if any
+    pub fn get<K>(&self, key_id: K) -> Option<&SecretKey<'a>>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.keys.get(key_id.as_ref())
+    }
+    /// Indicates whether the key with the given ID is in the
+    /// [`SecretKeyRing`](struct.SecretKeyRing.html) but is not the active key
+    pub fn is_retired<K>(&self, key_id: K) -> bool
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_id = key_id.as_ref();
+        self.keys.contains_key(key_id) && key_id != &self.active_key_id[..]
+    }
+    /// Returns an iterator over the IDs of all keys, active and retired, in ascending order
+    pub fn key_ids(&self) -> impl Iterator<Item = &[u8]> {
+        self.keys.keys().map(|key_id| &key_id[..])
+    }
+    /// Returns the number of keys, active and retired
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    /// Clones the underlying keys and returns a new
+    /// [`SecretKeyRing`](struct.SecretKeyRing.html) with a `static` lifetime. Use this method
+    /// if you would like to move a [`SecretKeyRing`](struct.SecretKeyRing.html) to another
+    /// thread
+    pub fn to_owned(&self) -> SecretKeyRing<'static> {
+        SecretKeyRing {
+            active_key_id: self.active_key_id.clone(),
+            keys: self
+                .keys
+                .iter()
+                .map(|(key_id, secret_key)| (key_id.clone(), secret_key.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> SecretKeyRing<'a> {
+    pub(crate) fn into_active(mut self) -> (Vec<u8>, SecretKey<'a>) {
+        let secret_key = self.keys.remove(&self.active_key_id).unwrap(); // Safe; see set_active_key_id
+        (self.active_key_id, secret_key)
+    }
+}
+
+pub(crate) fn unknown_key_id(key_id: &[u8]) -> Error {
+    Error::new(ErrorKind::SecretKeyIdUnknownError)
+        .add_context(format!("Key ID: {}", STANDARD_NO_PAD.encode(key_id)))
+}
+
+fn validate_key_id(key_id: &[u8]) -> Result<(), Error> {
+    if key_id.is_empty() || key_id.len() > MAX_KEY_ID_LEN {
+        return Err(Error::new(ErrorKind::SecretKeyIdInvalidError)
+            .add_context(format!("Length: {}", key_id.len())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_key_ring() {
+        let mut ring = SecretKeyRing::new("2024", "old secret").unwrap();
+        assert_eq!(ring.active_key_id(), b"2024");
+        assert_eq!(&*ring.active_key().as_bytes(), b"old secret");
+        assert!(!ring.is_retired("2024"));
+
+        ring.insert("2025", "new secret").unwrap();
+        assert_eq!(ring.active_key_id(), b"2024");
+        ring.set_active_key_id("2025").unwrap();
+        assert_eq!(&*ring.active_key().as_bytes(), b"new secret");
+        assert!(ring.is_retired("2024"));
+        assert!(!ring.is_retired("2025"));
+        assert!(!ring.is_retired("2026"));
+        assert_eq!(&*ring.get("2024").unwrap().as_bytes(), b"old secret");
+        assert!(ring.get("2026").is_none());
+        assert_eq!(
+            ring.key_ids().collect::<Vec<_>>(),
+            vec![&b"2024"[..], &b"2025"[..]]
+        );
+        assert_eq!(ring.len(), 2);
+        let owned = ring.to_owned();
+        assert_eq!(owned.active_key_id(), ring.active_key_id());
+        assert_eq!(&*owned.get("2024").unwrap().as_bytes(), b"old secret");
+
+        let e = ring.set_active_key_id("2026").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::SecretKeyIdUnknownError);
+        let e = ring.insert("", "secret").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::SecretKeyIdInvalidError);
+        let e = SecretKeyRing::new("123456789", "secret").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::SecretKeyIdInvalidError);
+
+        let (key_id, secret_key) = ring.into_active();
+        assert_eq!(key_id, b"2025");
+        assert_eq!(&*secret_key.as_bytes(), b"new secret");
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SecretKeyRing>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SecretKeyRing>();
+    }
+}
@@ -40,6 +40,7 @@
 //!
 //! use argonautica::Hasher;
 //!
+//! # #[cfg(feature = "std")]
 //! fn main() {
 //!     let mut hasher = Hasher::default();
 //!     let hash = hasher
@@ -54,6 +55,8 @@
 //!     println!("{}", &hash);
 //!     // 👆 prints a hash, which will be random since the default Hasher uses a random salt
 //! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //! # Verifying
 //!
@@ -69,7 +72,7 @@
 //! fn main() {
 //!     let mut verifier = Verifier::default();
 //!     let is_valid = verifier
-//!         .with_hash("
+//!         .with_hash("\
 //!             $argon2id$v=19$m=4096,t=192,p=4$\
 //!             o2y5PU86Vt+sr93N7YUGgC7AMpTKpTQCk4tNGUPZMY4$\
 //!             yzP/ukZRPIbZg6PvgnUUobUMbApfF9RH6NagL9L4Xr4\
@@ -109,18 +112,21 @@
 //!
 //! use argonautica::Hasher;
 //! use argonautica::config::{Backend, Variant, Version};
+//! # #[cfg(feature = "std")]
 //! use futures::executor::ThreadPool;
 //!
+//! # #[cfg(feature = "std")]
 //! fn main() {
 //!     let mut hasher = Hasher::default();
 //!     hasher
-//!         .configure_backend(Backend::C) // Default is `Backend::C`
-//!         // 👆 argonautica was designed to support multiple backends (meaning multiple
-//!         // implementations of the underlying Argon2 algorithm). Currently only the C backend
-//!         // is supported, which uses the canonical Argon2 library written in C to actually
-//!         // do the work. In the future hopefully a Rust backend will also be supported, but,
-//!         // for the moment, you must use `Backend::C`, which is the default. Using
-//!         // `Backend::Rust` will result in an error (again, for the moment).
+//!         .configure_backend(Backend::default()) // Default is `Backend::C`
+//!         // 👆 argonautica supports multiple backends (meaning multiple implementations of
+//!         // the underlying Argon2 algorithm). The C backend, which is the default, uses the
+//!         // canonical Argon2 library written in C to actually do the work. The Rust backend
+//!         // (`Backend::Rust`) is a pure Rust implementation that produces identical hashes and
+//!         // does not require a C compiler, which is handy when cross-compiling. If you build
+//!         // argonautica without default features (i.e. without the `backend-c` feature),
+//!         // `Backend::Rust` becomes the default and `Backend::C` will result in an error.
 //!         .configure_thread_pool(ThreadPool::builder().pool_size(2).create().unwrap())
 //!         // 👆 There are two non-blocking methods on `Hasher` that perform computation on
 //!         // a separate thread and return a `Future` instead of a `Result` (`hash_non_blocking`
@@ -172,7 +178,8 @@
 //!         // probably tweak this parameter (and the iterations parameter) in order to increase
 //!         // the time it takes to hash to the maximum you can reasonably allow for your use-case
 //!         // (e.g. to probably about 300-500 milliseconds for the use-case of hashing user
-//!         // passwords for a website)
+//!         // passwords for a website). Alternatively, start from one of the RFC 9106 or OWASP
+//!         // presets, e.g. `Hasher::from_preset(Preset::OwaspT2)` (see `config::Preset`)
 //!         .configure_password_clearing(false) // Default is `false`
 //!         // 👆 It is possible to have the underlying bytes of the password you provided
 //!         // to `Hasher` be erased after each call to `hash`, `hash_raw` or their non-blocking
@@ -233,18 +240,22 @@
 //!         "$argon2id$v=19$m=4096,t=192,p=2$c29tZXNhbHQ$sw41ZsxebJmOJ6vSHe6BGQ",
 //!     );
 //! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //! # Installation
 //!
 //! <b>argonautica</b> should be relatively straightforward to include in your Rust project:
 //! * Place `extern crate argonautica;` in your code (typically in either `lib.rs` or `main.rs`)
 //! * In the `[dependencies]` section of your `Cargo.toml`, place ...
-//!     * ... if you're building for your own machine ...
-//!         * `argonautica = { version = "0.2", features = ["simd"] }`, or
-//!         * `argonautica = { version = "0.2", features = ["serde", "simd"] }`
-//!     * ... if you're building for a different machine ...
-//!         * `argonautica = "0.2"`, or
-//!         * `argonautica = { version = "0.2", features = ["serde"] }`
+//!     * `argonautica = "0.2"`, or
+//!     * `argonautica = { version = "0.2", features = ["serde"] }`
+//!
+//! There is no need to build separately for machines with and without SIMD support: the C
+//! backend is compiled with both a portable and several SIMD implementations of the Argon2
+//! compression function and picks the fastest one your CPU supports at runtime. You can find out
+//! which one is in use with
+//! [`HasherConfig::implementation`](config/struct.HasherConfig.html#method.implementation).
 //!
 //! That said, <b>argonautica</b> uses [cc](https://github.com/alexcrichton/cc-rs) and
 //! [bindgen](https://github.com/rust-lang-nursery/rust-bindgen) to compile the canonical
@@ -257,6 +268,25 @@
 //!     * Arch linux: `pacman -S clang`
 //!     * Windows: Download a pre-built binary [here](http://releases.llvm.org/download.html)
 //!
+//! If you cannot build C code (e.g. when cross-compiling), use
+//! `argonautica = { version = "0.2", default-features = false, features = ["std"] }` instead,
+//! which leaves out the C backend and uses the pure Rust one. Leaving out the `std` feature as
+//! well, i.e. `argonautica = { version = "0.2", default-features = false }`, makes
+//! <b>argonautica</b> usable on `no_std` targets with an allocator, such as embedded devices.
+//! Without `std`, hashing runs on a single thread, the non-blocking methods and the `utils`
+//! module are unavailable, and random salts need a random number generator configured with
+//! [`configure_rng`](struct.Hasher.html#method.configure_rng) (or a salt you provide yourself).
+//!
+//! If you use the non-blocking methods from an async runtime, the `runtime-tokio`,
+//! `runtime-rayon` and `runtime-smol` features add adapters that run them on the runtime's
+//! blocking thread pool (see the [`executor`](executor/index.html) module), e.g.
+//! `argonautica = { version = "0.2", features = ["runtime-tokio"] }`.
+//!
+//! If you keep passwords and secret keys in the types of the
+//! [secrecy](https://crates.io/crates/secrecy) crate, the `secrecy` feature lets you pass them
+//! to <b>argonautica</b> directly, e.g.
+//! `argonautica = { version = "0.2", features = ["secrecy"] }`.
+//!
 //! <b>argonautica</b> runs on stable Rust version 1.32.0 or greater.
 //!
 //! # License
@@ -275,40 +305,63 @@
     unused_variables
 )]
 #![doc(html_root_url = "https://docs.rs/argonautica/0.2.1")]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[macro_use]
+extern crate alloc;
 extern crate base64;
 #[macro_use]
 extern crate bitflags;
-#[cfg(feature = "development")]
 extern crate blake2_rfc;
 #[macro_use]
 extern crate failure;
+#[cfg(feature = "std")]
 extern crate futures;
+#[cfg(feature = "backend-c")]
 extern crate libc;
 #[macro_use]
 extern crate log;
-extern crate nom;
+#[cfg(feature = "std")]
 extern crate num_cpus;
 extern crate rand;
 extern crate scopeguard;
+#[cfg(feature = "secrecy")]
+extern crate secrecy;
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde;
 #[cfg(all(test, feature = "serde"))]
 extern crate serde_json;
+extern crate zeroize;
 
+#[cfg(feature = "std")]
+pub mod allocator;
 mod backend;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod cancellation;
 mod error;
 mod error_kind;
+#[cfg(feature = "backend-c")]
 mod ffi;
 mod hasher;
+#[cfg(feature = "std")]
+mod secure_memory;
 mod verifier;
 
 pub mod config;
 pub use error::Error;
 pub use error_kind::ErrorKind;
+#[cfg(feature = "std")]
+pub mod executor;
 pub use hasher::Hasher;
 pub mod input;
+#[cfg(feature = "std")]
+pub mod memory_budget;
 pub mod output;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
 pub mod utils;
 pub use verifier::Verifier;
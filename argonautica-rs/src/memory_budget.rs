@@ -0,0 +1,283 @@
+//! The process-wide memory budget that every hash must reserve its memory from before
+//! allocating it.
+//!
+//! Each hash allocates `memory_size` kibibytes for as long as it runs, so a burst of logins
+//! against a memory-hard configuration can easily ask for more memory than the machine (or
+//! the container) has. With a budget set via [`set_limit`](fn.set_limit.html),
+//! [`Hasher`](../struct.Hasher.html) and [`Verifier`](../struct.Verifier.html) reserve the
+//! memory of each hash from the budget first and release it when the hash is done. A hash
+//! that does not fit into what is left of the budget waits, fails right away or gives up
+//! after a while, depending on its
+//! [`BudgetPolicy`](../config/enum.BudgetPolicy.html). Note that waiting blocks the thread
+//! running the hash, i.e. a thread of the thread pool for the non-blocking methods.
+//!
+//! There is no limit by default, but the memory in use is tracked either way. Use
+//! [`metrics`](fn.metrics.html) to monitor it
+use std::{
+    fmt,
+    sync::{
+        Condvar, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{Error, ErrorKind, cancellation::Cancellation, config::BudgetPolicy};
+
+/// How often a waiting hash checks whether it was cancelled or timed out
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static GLOBAL_BUDGET: Budget = Budget::new();
+
+/// A snapshot of the state of the memory budget, as returned by
+/// [`metrics`](fn.metrics.html)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct BudgetMetrics {
+    in_use: u64,
+    limit: Option<u64>,
+    peak: u64,
+    rejected: u64,
+    waiting: usize,
+}
+
+impl BudgetMetrics {
+    /// Returns the number of bytes reserved by the hashes currently running
+    pub fn in_use(&self) -> u64 {
+        self.in_use
+    }
+    /// Returns the size of the budget in bytes, or `None` if there is no limit
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+    /// Returns the largest number of bytes reserved at once, since the process started
+    pub fn peak(&self) -> u64 {
+        self.peak
+    }
+    /// Returns the number of hashes that failed because the budget could not cover their
+    /// memory, since the process started
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+    /// Returns the number of hashes currently waiting for memory
+    pub fn waiting(&self) -> usize {
+        self.waiting
+    }
+}
+
+/// Returns the current [`BudgetMetrics`](struct.BudgetMetrics.html) of the memory budget
+pub fn metrics() -> BudgetMetrics {
+    GLOBAL_BUDGET.metrics()
+}
+
+/// Sets the size of the memory budget in bytes, or removes the limit if `limit` is `None`
+/// (the default). Can be called at any time; hashes already running keep their memory, and
+/// hashes waiting for memory are re-evaluated against the new limit
+pub fn set_limit(limit: Option<u64>) {
+    GLOBAL_BUDGET.set_limit(limit);
+}
+
+/// Reserves `bytes` from the global budget, waiting for other hashes to release memory as
+/// `policy` allows. Returns an
+/// [`ErrorKind::MemoryBudgetError`](../enum.ErrorKind.html#variant.MemoryBudgetError) if the
+/// budget cannot cover `bytes` in time, or the error of `cancellation` if the hash stops while
+/// waiting
+pub(crate) fn reserve(
+    bytes: u64,
+    policy: BudgetPolicy,
+    cancellation: &Cancellation,
+) -> Result<Reservation, Error> {
+    GLOBAL_BUDGET.reserve(bytes, policy, cancellation)
+}
+
+struct Budget {
+    /// Size of the budget in bytes, `u64::MAX` meaning no limit
+    limit: AtomicU64,
+    rejected: AtomicU64,
+    state: Mutex<State>,
+    state_changed: Condvar,
+}
+
+impl Budget {
+    const fn new() -> Budget {
+        Budget {
+            limit: AtomicU64::new(u64::MAX),
+            rejected: AtomicU64::new(0),
+            state: Mutex::new(State {
+                in_use: 0,
+                peak: 0,
+                waiting: 0,
+            }),
+            state_changed: Condvar::new(),
+        }
+    }
+    fn limit(&self) -> Option<u64> {
+        match self.limit.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            limit => Some(limit),
+        }
+    }
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn metrics(&self) -> BudgetMetrics {
+        let state = self.lock();
+        BudgetMetrics {
+            in_use: state.in_use,
+            limit: self.limit(),
+            peak: state.peak,
+            rejected: self.rejected.load(Ordering::Relaxed),
+            waiting: state.waiting,
+        }
+    }
+    fn reject(&self, bytes: u64, reason: &str) -> Error {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        Error::new(ErrorKind::MemoryBudgetError).add_context(format!(
+            "{}. Bytes: {}. Limit: {:?}",
+            reason,
+            bytes,
+            self.limit()
+        ))
+    }
+    fn release(&self, bytes: u64) {
+        let mut state = self.lock();
+        state.in_use -= bytes;
+        self.state_changed.notify_all();
+    }
+    fn reserve(
+        &'static self,
+        bytes: u64,
+        policy: BudgetPolicy,
+        cancellation: &Cancellation,
+    ) -> Result<Reservation, Error> {
+        let deadline = match policy {
+            BudgetPolicy::Timeout(timeout) => Instant::now().checked_add(timeout),
+            _ => None,
+        };
+        let mut state = self.lock();
+        loop {
+            let limit = self.limit.load(Ordering::Relaxed);
+            if bytes <= limit.saturating_sub(state.in_use) {
+                state.in_use += bytes;
+                state.peak = state.peak.max(state.in_use);
+                return Ok(Reservation {
+                    budget: self,
+                    bytes,
+                });
+            }
+            if bytes > limit {
+                return Err(self.reject(bytes, "Hash needs more memory than the whole budget"));
+            }
+            let wait = match (policy, deadline) {
+                (BudgetPolicy::FailFast, _) => {
+                    return Err(self.reject(bytes, "Not enough memory left in the budget"));
+                }
+                (BudgetPolicy::Timeout(_), Some(deadline)) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(self.reject(bytes, "Timed out waiting for memory"));
+                    }
+                    POLL_INTERVAL.min(deadline - now)
+                }
+                _ => POLL_INTERVAL,
+            };
+            cancellation.check()?;
+            state.waiting += 1;
+            state = self
+                .state_changed
+                .wait_timeout(state, wait)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+            state.waiting -= 1;
+        }
+    }
+    fn set_limit(&self, limit: Option<u64>) {
+        self.limit
+            .store(limit.unwrap_or(u64::MAX), Ordering::Relaxed);
+        let _state = self.lock();
+        self.state_changed.notify_all();
+    }
+}
+
+struct State {
+    in_use: u64,
+    peak: u64,
+    waiting: usize,
+}
+
+/// Memory reserved from the budget, released when dropped
+#[must_use]
+pub(crate) struct Reservation {
+    budget: &'static Budget,
+    bytes: u64,
+}
+
+impl fmt::Debug for Reservation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Reservation")
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        static BUDGET: Budget = Budget::new();
+        BUDGET.set_limit(Some(100));
+        let cancellation = Cancellation::default();
+        let reservation = BUDGET
+            .reserve(60, BudgetPolicy::FailFast, &cancellation)
+            .unwrap();
+        assert_eq!(BUDGET.metrics().in_use(), 60);
+
+        let policies = [
+            BudgetPolicy::FailFast,
+            BudgetPolicy::Timeout(Duration::from_millis(20)),
+        ];
+        for policy in policies {
+            let error = BUDGET.reserve(60, policy, &cancellation).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::MemoryBudgetError);
+        }
+        let error = BUDGET
+            .reserve(101, BudgetPolicy::Wait, &cancellation)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MemoryBudgetError);
+        let mut timed_out = Cancellation::default();
+        timed_out.start(Some(Duration::from_millis(20)));
+        let error = BUDGET
+            .reserve(60, BudgetPolicy::Wait, &timed_out)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimeoutError);
+
+        let waiter = std::thread::spawn(move || {
+            BUDGET
+                .reserve(60, BudgetPolicy::Wait, &Cancellation::default())
+                .map(|_| ())
+        });
+        while BUDGET.metrics().waiting() == 0 {
+            std::thread::yield_now();
+        }
+        drop(reservation);
+        waiter.join().unwrap().unwrap();
+        let metrics = BUDGET.metrics();
+        assert_eq!(metrics.in_use(), 0);
+        assert_eq!(metrics.limit(), Some(100));
+        assert_eq!(metrics.peak(), 60);
+        assert_eq!(metrics.rejected(), 3);
+        assert_eq!(metrics.waiting(), 0);
+
+        BUDGET.set_limit(None);
+        assert_eq!(BUDGET.metrics().limit(), None);
+    }
+}
@@ -1,9 +1,10 @@
-use std::str::FromStr;
+use alloc::{string::String, vec::Vec};
+use core::{cmp::Ordering, hash, hint::black_box, str::FromStr};
 
 use crate::{
     Error,
-    backend::decode_rust,
-    config::{Variant, Version},
+    backend::{decode_rust, decode_rust_lenient},
+    config::{HasherConfig, Variant, Version},
 };
 
 impl FromStr for HashRaw {
@@ -12,12 +13,62 @@ impl FromStr for HashRaw {
     type Err = Error;
 
     /// Takes a regular string-encoded hash and converts it into an instance
-    /// of [`HashRaw`](struct.HashRaw.html)
+    /// of [`HashRaw`](struct.HashRaw.html). Only canonical
+    /// [PHC strings](https://github.com/P-H-C/phc-string-format) are accepted; see
+    /// [`from_str_lenient`](struct.HashRaw.html#method.from_str_lenient) for hashes written
+    /// by other encoders
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         decode_rust(s)
     }
 }
 
+impl PartialEq for HashRaw {
+    /// Same as [`constant_time_eq`](struct.HashRaw.html#method.constant_time_eq)
+    fn eq(&self, other: &HashRaw) -> bool {
+        self.constant_time_eq(other)
+    }
+}
+
+impl Eq for HashRaw {}
+
+impl PartialOrd for HashRaw {
+    fn partial_cmp(&self, other: &HashRaw) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HashRaw {
+    /// Orders by each field in turn, comparing the raw hash bytes in constant time, i.e. in
+    /// time that does not depend on where (or whether) they differ
+    fn cmp(&self, other: &HashRaw) -> Ordering {
+        let hash_order = constant_time_cmp(&self.raw_hash_bytes, &other.raw_hash_bytes);
+        self.iterations
+            .cmp(&other.iterations)
+            .then(self.key_id.cmp(&other.key_id))
+            .then(self.lanes.cmp(&other.lanes))
+            .then(self.memory_size.cmp(&other.memory_size))
+            .then(self.omit_version.cmp(&other.omit_version))
+            .then(hash_order)
+            .then(self.raw_salt_bytes.cmp(&other.raw_salt_bytes))
+            .then(self.variant.cmp(&other.variant))
+            .then(self.version.cmp(&other.version))
+    }
+}
+
+impl hash::Hash for HashRaw {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.iterations.hash(state);
+        self.key_id.hash(state);
+        self.lanes.hash(state);
+        self.memory_size.hash(state);
+        self.omit_version.hash(state);
+        self.raw_hash_bytes.hash(state);
+        self.raw_salt_bytes.hash(state);
+        self.variant.hash(state);
+        self.version.hash(state);
+    }
+}
+
 /// Struct representing raw hash output.
 ///
 /// You typically won't need this struct if all you'd like to produce is a string-encoded hash,
@@ -31,13 +82,21 @@ impl FromStr for HashRaw {
 ///   `let hash_raw = hash_str.parse::<HashRaw>()?;`, or
 /// * Obtaining a `HashRaw` directly by calling [`hash_raw`](../struct.Hasher.html#method.hash_raw)
 ///   on a [`Hasher`](../struct.Hasher.html) (or its non-blocking equivalent)
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+///
+/// Comparing two [`HashRaw`](struct.HashRaw.html)s with `==` (see
+/// [`constant_time_eq`](struct.HashRaw.html#method.constant_time_eq)) or ordering them with
+/// `<`, `>`, `cmp`, etc. takes the same time wherever their raw hash bytes differ
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct HashRaw {
     pub(crate) iterations: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) key_id: Option<Vec<u8>>,
     pub(crate) lanes: u32,
     pub(crate) memory_size: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) omit_version: bool,
     pub(crate) raw_hash_bytes: Vec<u8>,
     pub(crate) raw_salt_bytes: Vec<u8>,
     pub(crate) variant: Variant,
@@ -45,6 +104,31 @@ pub struct HashRaw {
 }
 
 impl HashRaw {
+    /// Returns `true` if both [`HashRaw`](struct.HashRaw.html)s are identical, comparing the
+    /// raw hash bytes in constant time, i.e. in time that does not depend on where (or
+    /// whether) they differ. Use this, or `==`, which does the same, rather than comparing
+    /// [`raw_hash_bytes`](struct.HashRaw.html#method.raw_hash_bytes) yourself, so as not to
+    /// leak how much of a hash an attacker has guessed through timing. The remaining fields
+    /// (e.g. the salt and the parameters) are not secret and are compared as usual
+    pub fn constant_time_eq(&self, other: &HashRaw) -> bool {
+        let params_eq = self.iterations == other.iterations
+            && self.key_id == other.key_id
+            && self.lanes == other.lanes
+            && self.memory_size == other.memory_size
+            && self.omit_version == other.omit_version
+            && self.raw_salt_bytes == other.raw_salt_bytes
+            && self.variant == other.variant
+            && self.version == other.version;
+        let hash_eq = constant_time_eq(&self.raw_hash_bytes, &other.raw_hash_bytes);
+        params_eq & hash_eq
+    }
+    /// Same as parsing a string-encoded hash via `hash_str.parse::<HashRaw>()` except that
+    /// it also accepts non-canonical encodings, e.g. surrounding whitespace, base64 `=`
+    /// padding, leading zeros, parameters in an unusual order and unknown parameters (which
+    /// are ignored). Use this for legacy data written by other encoders
+    pub fn from_str_lenient(s: &str) -> Result<HashRaw, Error> {
+        decode_rust_lenient(s)
+    }
     /// Converts the [`HashRaw`](struct.HashRaw.html) to a string-encoded hash
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
@@ -54,14 +138,45 @@ impl HashRaw {
     pub fn iterations(&self) -> u32 {
         self.iterations
     }
+    /// Read-only access to the ID of the secret key that was used to produce this hash, if it
+    /// was produced with a [`SecretKeyRing`](../input/struct.SecretKeyRing.html)
+    pub fn key_id(&self) -> Option<&[u8]> {
+        self.key_id.as_deref()
+    }
     /// Obtain the lanes configuration that was used to produce this hash
     pub fn lanes(&self) -> u32 {
         self.lanes
     }
+    /// Returns `true` if this hash was not produced with the given
+    /// [`HasherConfig`](../config/struct.HasherConfig.html), i.e. if its variant, version,
+    /// memory size, iterations, lanes, hash length, salt length or key ID differ from those of
+    /// the configuration. Use it with the configuration of the
+    /// [`Hasher`](../struct.Hasher.html) you currently create hashes with to find stored
+    /// hashes that should be recreated, e.g. via
+    /// [`verify_and_upgrade`](../struct.Verifier.html#method.verify_and_upgrade).
+    /// Hashes that exceed the configuration (e.g. use more memory) also need a rehash
+    pub fn needs_rehash(&self, config: &HasherConfig) -> bool {
+        self.variant != config.variant()
+            || self.version != config.version()
+            || self.memory_size != config.memory_size()
+            || self.iterations != config.iterations()
+            || self.lanes != config.lanes()
+            || self.raw_hash_bytes.len() != config.hash_len() as usize
+            || self.raw_salt_bytes.len() != config.salt_len() as usize
+            || self.key_id() != config.key_id()
+    }
     /// Obtain the memory size configuration that was used to produce this hash
     pub fn memory_size(&self) -> u32 {
         self.memory_size
     }
+    /// Whether this hash was parsed from a legacy string-encoded hash without a `$v=`
+    /// segment, such as those produced by early releases of the Argon2 reference
+    /// implementation. Such hashes always use [`Version::_0x10`](../config/enum.Version.html#variant._0x10)
+    /// and are converted back into string-encoded hashes without a `$v=` segment, so that
+    /// they round-trip unchanged
+    pub fn omits_version(&self) -> bool {
+        self.omit_version
+    }
     /// Read-only access to the raw hash bytes
     pub fn raw_hash_bytes(&self) -> &[u8] {
         &self.raw_hash_bytes
@@ -80,9 +195,123 @@ impl HashRaw {
     }
 }
 
+/// Compares two byte slices in time that depends only on their lengths, which are public
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |diff, (x, y)| black_box(diff | (x ^ y)));
+    diff == 0
+}
+
+/// Orders two byte slices lexicographically in time that depends only on their lengths, which
+/// are public
+pub(crate) fn constant_time_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    // Walks backwards so that the first differing byte is the last one to overwrite `order`
+    let order = a.iter().zip(b).rev().fold(0i16, |order, (x, y)| {
+        let diff = i16::from(*x) - i16::from(*y);
+        // All ones if `diff` is non-zero, zero otherwise
+        let mask = (diff | -diff) >> 15;
+        black_box((order & !mask) | (diff & mask))
+    });
+    order.cmp(&0).then(a.len().cmp(&b.len()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Hasher, input::SecretKeyRing};
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"hash", b"hash"));
+        assert!(!constant_time_eq(b"hash", b"hasH"));
+        assert!(!constant_time_eq(b"hash", b"Hash"));
+        assert!(!constant_time_eq(b"hash", b"hash "));
+
+        let hash_raw = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHRzb21lc2FsdA$v6V587B9qbKraulhK/6vFUq93BGWugdzgRhtyap9tDM"
+            .parse::<HashRaw>()
+            .unwrap();
+        assert!(hash_raw.constant_time_eq(&hash_raw.clone()));
+        assert_eq!(hash_raw, hash_raw.clone());
+        for i in &[0, 31] {
+            let mut other = hash_raw.clone();
+            other.raw_hash_bytes[*i] ^= 1;
+            assert!(!hash_raw.constant_time_eq(&other));
+            assert_ne!(hash_raw, other);
+        }
+        let mut other = hash_raw.clone();
+        other.iterations += 1;
+        assert!(!hash_raw.constant_time_eq(&other));
+        let mut other = hash_raw.clone();
+        other.raw_salt_bytes[0] ^= 1;
+        assert!(!hash_raw.constant_time_eq(&other));
+    }
+
+    #[test]
+    fn test_constant_time_cmp() {
+        for (a, b) in &[
+            (&b""[..], &b""[..]),
+            (b"hash", b"hash"),
+            (b"hash", b"hasH"),
+            (b"Hash", b"hash"),
+            (b"hash", b"hash "),
+            (b"\x00\xff", b"\xff\x00"),
+            (b"ab", b"b"),
+        ] {
+            assert_eq!(constant_time_cmp(a, b), a.cmp(b));
+            assert_eq!(constant_time_cmp(b, a), b.cmp(a));
+        }
+
+        let hash_raw = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHRzb21lc2FsdA$v6V587B9qbKraulhK/6vFUq93BGWugdzgRhtyap9tDM"
+            .parse::<HashRaw>()
+            .unwrap();
+        assert_eq!(hash_raw.cmp(&hash_raw.clone()), Ordering::Equal);
+        for i in &[0, 31] {
+            let mut other = hash_raw.clone();
+            other.raw_hash_bytes[*i] = other.raw_hash_bytes[*i].wrapping_add(1);
+            assert_eq!(
+                hash_raw.cmp(&other),
+                hash_raw.raw_hash_bytes.cmp(&other.raw_hash_bytes)
+            );
+            assert_eq!(hash_raw.partial_cmp(&other), Some(hash_raw.cmp(&other)));
+        }
+        let mut other = hash_raw.clone();
+        other.iterations += 1;
+        other.raw_hash_bytes[0] = other.raw_hash_bytes[0].wrapping_sub(1);
+        assert!(hash_raw < other);
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        fn hasher() -> Hasher<'static> {
+            let mut hasher = Hasher::default();
+            hasher
+                .configure_iterations(1)
+                .configure_lanes(1)
+                .configure_memory_size(32)
+                .configure_threads(1)
+                .opt_out_of_secret_key(true)
+                .with_password("P@ssw0rd")
+                .with_salt("somesaltsomesalt");
+            hasher
+        }
+        let hash_raw = hasher().hash_raw().unwrap();
+        assert!(!hash_raw.needs_rehash(hasher().config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_variant(Variant::Argon2i).config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_version(Version::_0x10).config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_memory_size(64).config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_iterations(2).config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_lanes(2).config()));
+        assert!(hash_raw.needs_rehash(hasher().configure_hash_len(16).config()));
+        assert!(hash_raw.needs_rehash(hasher().with_salt("somesalt").config()));
+        let secret_key_ring = SecretKeyRing::new("2025", "secret").unwrap();
+        assert!(hash_raw.needs_rehash(hasher().with_secret_key_ring(secret_key_ring).config()));
+    }
 
     #[test]
     fn test_send() {
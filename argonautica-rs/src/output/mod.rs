@@ -1,4 +1,8 @@
-//! Struct representing raw hash output.
+//! Structs representing hash output, i.e. raw hashes, the outcome of verifying with an
+//! upgrade, and shadow mode mismatches.
 mod hash_raw;
+mod shadow_mismatch;
+mod verification;
 
-pub use self::hash_raw::HashRaw;
+pub(crate) use self::hash_raw::constant_time_eq;
+pub use self::{hash_raw::HashRaw, shadow_mismatch::ShadowMismatch, verification::Verification};
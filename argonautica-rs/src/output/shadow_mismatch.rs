@@ -0,0 +1,59 @@
+use crate::{Error, config::Implementation, output::HashRaw};
+
+/// Struct describing a disagreement between the C and Rust backends, as detected in shadow mode
+/// (see [`configure_shadow_mode`](../struct.Hasher.html#method.configure_shadow_mode)).
+///
+/// In shadow mode every hash is computed by both backends. The C result is the one returned to
+/// the caller; if the Rust backend produced different bytes or an error, a
+/// [`ShadowMismatch`](struct.ShadowMismatch.html) is handed to the callback configured via
+/// [`configure_shadow_callback`](../struct.Hasher.html#method.configure_shadow_callback), or
+/// logged at the error level if there is none.
+///
+/// Note that a [`ShadowMismatch`](struct.ShadowMismatch.html) contains the raw hash and salt
+/// bytes. Treat it as you would a stored hash and avoid writing it to logs verbatim
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShadowMismatch {
+    pub(crate) c_hash_raw: HashRaw,
+    pub(crate) c_implementation: Implementation,
+    pub(crate) rust_result: Result<HashRaw, Error>,
+    pub(crate) rust_implementation: Implementation,
+}
+
+impl ShadowMismatch {
+    /// Read-only access to the [`HashRaw`](struct.HashRaw.html) produced by the C backend, which
+    /// is the one that was returned to the caller
+    pub fn c_hash_raw(&self) -> &HashRaw {
+        &self.c_hash_raw
+    }
+    /// Obtain the [`Implementation`](../config/enum.Implementation.html) the C backend used
+    pub fn c_implementation(&self) -> Implementation {
+        self.c_implementation
+    }
+    /// Read-only access to what the Rust backend produced: either a
+    /// [`HashRaw`](struct.HashRaw.html) that differs from the C one or an
+    /// [`Error`](../struct.Error.html)
+    pub fn rust_result(&self) -> Result<&HashRaw, &Error> {
+        self.rust_result.as_ref()
+    }
+    /// Obtain the [`Implementation`](../config/enum.Implementation.html) the Rust backend used
+    pub fn rust_implementation(&self) -> Implementation {
+        self.rust_implementation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<ShadowMismatch>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<ShadowMismatch>();
+    }
+}
@@ -0,0 +1,64 @@
+use alloc::string::String;
+
+/// Enum representing the outcome of
+/// [`verify_and_upgrade`](../struct.Verifier.html#method.verify_and_upgrade)
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum Verification {
+    /// The password does not match the hash
+    Invalid,
+    /// The password matches the hash, which was produced with the target configuration
+    Valid,
+    /// The password matches the hash, which was <b>not</b> produced with the target
+    /// configuration. Contains a new string-encoded hash of the password produced with the
+    /// target [`Hasher`](../struct.Hasher.html); store it in place of the old one
+    Upgraded(String),
+}
+
+impl Verification {
+    /// Returns `true` if the password matches the hash, whether or not it was upgraded
+    pub fn is_valid(&self) -> bool {
+        !matches!(*self, Verification::Invalid)
+    }
+    /// Returns the new string-encoded hash if the hash was upgraded
+    pub fn upgraded_hash(&self) -> Option<&str> {
+        match *self {
+            Verification::Upgraded(ref hash) => Some(hash),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Verification>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Verification>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        use serde;
+        fn assert_serialize<T: serde::Serialize>() {}
+        assert_serialize::<Verification>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        use serde;
+        fn assert_deserialize<'de, T: serde::Deserialize<'de>>() {}
+        assert_deserialize::<Verification>();
+    }
+}
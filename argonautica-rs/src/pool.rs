@@ -0,0 +1,243 @@
+//! The process-wide thread pool that runs
+//! [`hash_non_blocking`](../struct.Hasher.html#method.hash_non_blocking),
+//! [`hash_raw_non_blocking`](../struct.Hasher.html#method.hash_raw_non_blocking) and
+//! [`verify_non_blocking`](../struct.Verifier.html#method.verify_non_blocking) for
+//! [`Hasher`](../struct.Hasher.html)s and [`Verifier`](../struct.Verifier.html)s that were
+//! not configured with a thread pool of their own.
+//!
+//! The pool is created the first time it is needed, with as many threads as there are logical
+//! cores on your machine, and lives for the rest of the process. Its queue is bounded: when
+//! more jobs are waiting for a thread than the queue capacity allows, the non-blocking
+//! methods return an
+//! [`ErrorKind::ThreadPoolFullError`](../enum.ErrorKind.html#variant.ThreadPoolFullError)
+//! instead of queueing yet another job, so that a burst of logins degrades into fast
+//! failures rather than unbounded latency and memory use. Use [`metrics`](fn.metrics.html)
+//! to monitor how full the queue is
+use std::sync::{
+    OnceLock,
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use futures::{executor::ThreadPool, task::SpawnExt};
+
+use crate::{
+    Error, ErrorKind,
+    config::{DEFAULT_QUEUE_CAPACITY, default_cpu_pool},
+};
+
+static GLOBAL_POOL: OnceLock<GlobalPool> = OnceLock::new();
+static QUEUE_CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_QUEUE_CAPACITY);
+
+/// A snapshot of the state of the global thread pool, as returned by
+/// [`metrics`](fn.metrics.html)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct PoolMetrics {
+    queue_capacity: usize,
+    queue_depth: usize,
+    rejected: u64,
+    running: usize,
+}
+
+impl PoolMetrics {
+    /// Returns the maximum number of jobs that may wait for a thread
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+    /// Returns the number of jobs waiting for a thread
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth
+    }
+    /// Returns the number of jobs rejected because the queue was full, since the process
+    /// started
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+    /// Returns the number of jobs currently running
+    pub fn running(&self) -> usize {
+        self.running
+    }
+}
+
+/// Returns the current [`PoolMetrics`](struct.PoolMetrics.html) of the global thread pool.
+/// Does not create the pool if it does not exist yet
+pub fn metrics() -> PoolMetrics {
+    let queue_capacity = QUEUE_CAPACITY.load(Ordering::Relaxed);
+    match GLOBAL_POOL.get() {
+        Some(pool) => pool.metrics(queue_capacity),
+        None => PoolMetrics {
+            queue_capacity,
+            ..PoolMetrics::default()
+        },
+    }
+}
+
+/// Sets the maximum number of jobs that may wait for a thread of the global thread pool. The
+/// default is `1024`. Can be called at any time; jobs already queued are not affected
+pub fn set_queue_capacity(queue_capacity: usize) {
+    QUEUE_CAPACITY.store(queue_capacity, Ordering::Relaxed);
+}
+
+struct GlobalPool {
+    /// Jobs accepted and not yet finished, i.e. both queued and running
+    in_flight: AtomicUsize,
+    rejected: AtomicU64,
+    running: AtomicUsize,
+    thread_pool: ThreadPool,
+}
+
+impl GlobalPool {
+    fn new(thread_pool: ThreadPool) -> GlobalPool {
+        GlobalPool {
+            in_flight: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+            running: AtomicUsize::new(0),
+            thread_pool,
+        }
+    }
+    fn metrics(&self, queue_capacity: usize) -> PoolMetrics {
+        let running = self.running.load(Ordering::Relaxed);
+        PoolMetrics {
+            queue_capacity,
+            queue_depth: self
+                .in_flight
+                .load(Ordering::Relaxed)
+                .saturating_sub(running),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            running,
+        }
+    }
+}
+
+/// Runs `f` on the global thread pool, or returns an
+/// [`ErrorKind::ThreadPoolFullError`](../enum.ErrorKind.html#variant.ThreadPoolFullError)
+/// without running it if the queue is full
+pub(crate) async fn spawn<F, T>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_on(global_pool(), QUEUE_CAPACITY.load(Ordering::Relaxed), f).await
+}
+
+/// Returns the thread pool of the global thread pool, for tasks that bypass its queue limit
+/// because they never wait for a thread (see
+/// [`LaneScheduling`](../config/enum.LaneScheduling.html))
+pub(crate) fn thread_pool() -> ThreadPool {
+    global_pool().thread_pool.clone()
+}
+
+fn global_pool() -> &'static GlobalPool {
+    GLOBAL_POOL.get_or_init(|| GlobalPool::new(default_cpu_pool()))
+}
+
+async fn spawn_on<F, T>(pool: &'static GlobalPool, queue_capacity: usize, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let in_flight = pool.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+    // Dropped when the job finishes or, if the returned future is dropped first, when the
+    // job is discarded without having run
+    let in_flight_guard = scopeguard::guard((), move |_| {
+        pool.in_flight.fetch_sub(1, Ordering::SeqCst);
+    });
+    let queue_depth = in_flight.saturating_sub(pool.running.load(Ordering::SeqCst) + 1);
+    if queue_depth >= queue_capacity {
+        pool.rejected.fetch_add(1, Ordering::Relaxed);
+        return Err(
+            Error::new(ErrorKind::ThreadPoolFullError).add_context(format!(
+                "Queue depth: {}. Queue capacity: {}",
+                queue_depth, queue_capacity
+            )),
+        );
+    }
+    pool.thread_pool
+        .spawn_with_handle(async move {
+            let _in_flight_guard = in_flight_guard;
+            pool.running.fetch_add(1, Ordering::SeqCst);
+            let _running_guard = scopeguard::guard((), |_| {
+                pool.running.fetch_sub(1, Ordering::SeqCst);
+            });
+            f()
+        })
+        .expect("Failed to spawn")
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_spawn() {
+        assert_eq!(block_on(spawn(|| Ok(1))).unwrap(), 1);
+        let e = block_on(spawn(|| -> Result<(), Error> {
+            Err(Error::new(ErrorKind::Bug))
+        }))
+        .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Bug);
+        assert_eq!(metrics().queue_capacity(), DEFAULT_QUEUE_CAPACITY);
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let thread_pool = ThreadPool::builder().pool_size(1).create().unwrap();
+        let pool: &'static GlobalPool = Box::leak(Box::new(GlobalPool::new(thread_pool)));
+
+        // Occupy the pool's only thread and queue one job behind it
+        let barrier = Arc::new(Barrier::new(2));
+        let blocker = {
+            let barrier = Arc::clone(&barrier);
+            let future = spawn_on(pool, 1, move || {
+                barrier.wait();
+                Ok(())
+            });
+            std::thread::spawn(move || block_on(future))
+        };
+        while pool.metrics(1).running() < 1 {
+            std::thread::yield_now();
+        }
+        let queued = std::thread::spawn(move || block_on(spawn_on(pool, 1, || Ok(()))));
+        while pool.metrics(1).queue_depth() < 1 {
+            std::thread::yield_now();
+        }
+
+        let e = block_on(spawn_on(pool, 1, || Ok(()))).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ThreadPoolFullError);
+        let metrics = pool.metrics(1);
+        assert_eq!(metrics.queue_capacity(), 1);
+        assert_eq!(metrics.queue_depth(), 1);
+        assert_eq!(metrics.rejected(), 1);
+        assert_eq!(metrics.running(), 1);
+
+        barrier.wait();
+        blocker.join().unwrap().unwrap();
+        queued.join().unwrap().unwrap();
+        assert_eq!(
+            pool.metrics(1),
+            PoolMetrics {
+                queue_capacity: 1,
+                rejected: 1,
+                ..PoolMetrics::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<PoolMetrics>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<PoolMetrics>();
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ptr::{self, NonNull},
+    sync::{
+        Mutex, PoisonError,
+        atomic::{self, Ordering},
+    },
+};
+
+use crate::{Error, ErrorKind, allocator::SharedAllocator};
+
+/// Returns the allocator for the working memory of hashes in secure-memory mode that were not
+/// configured with an allocator of their own. Its memory is mapped separately from everything
+/// else, so that unlocking it never unlocks a page shared with other (locked) memory
+pub(crate) fn default_allocator() -> SharedAllocator {
+    #[cfg(target_os = "linux")]
+    return SharedAllocator::new(crate::allocator::HugePageAllocator);
+    #[cfg(not(target_os = "linux"))]
+    return SharedAllocator::new(crate::allocator::SystemAllocator);
+}
+
+/// Locks `size` bytes at `ptr` into RAM and, where supported, excludes them from core dumps.
+/// Returns an [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError)
+/// if the memory could not be locked, e.g. because `RLIMIT_MEMLOCK` is too low
+#[cfg(unix)]
+pub(crate) fn lock(ptr: NonNull<u8>, size: usize) -> Result<(), Error> {
+    let addr = ptr.as_ptr() as *mut libc::c_void;
+    if unsafe { libc::mlock(addr, size) } != 0 {
+        let error = ::std::io::Error::last_os_error();
+        return Err(Error::new(ErrorKind::MemoryLockError).add_context(format!(
+            "{}. Bytes: {}. RLIMIT_MEMLOCK: {}",
+            error,
+            size,
+            memlock_limit()
+        )));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if unsafe { libc::madvise(addr, size, libc::MADV_DONTDUMP) } != 0 {
+            let error = ::std::io::Error::last_os_error();
+            unsafe { libc::munlock(addr, size) };
+            return Err(Error::new(ErrorKind::MemoryLockError).add_context(format!(
+                "Failed to exclude memory from core dumps: {}",
+                error
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns an [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError),
+/// since locking memory is not supported on this platform
+#[cfg(not(unix))]
+pub(crate) fn lock(_ptr: NonNull<u8>, _size: usize) -> Result<(), Error> {
+    Err(Error::new(ErrorKind::MemoryLockError)
+        .add_context("Secure memory is not supported on this platform"))
+}
+
+/// Zeroes `size` bytes at `ptr` and undoes [`lock`], so that the memory can be released
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `size` bytes and must have been locked with [`lock`]
+pub(crate) unsafe fn unlock(ptr: NonNull<u8>, size: usize) {
+    unsafe { wipe(ptr, size) };
+    #[cfg(unix)]
+    {
+        let addr = ptr.as_ptr() as *mut libc::c_void;
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::madvise(addr, size, libc::MADV_DODUMP)
+        };
+        unsafe { libc::munlock(addr, size) };
+    }
+}
+
+/// Zeroes `size` bytes at `ptr` in a way the compiler does not optimize away, even though the
+/// memory is about to be released
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `size` bytes
+pub(crate) unsafe fn wipe(ptr: NonNull<u8>, size: usize) {
+    unsafe { ptr::write_bytes(ptr.as_ptr(), 0, size) };
+    atomic::compiler_fence(Ordering::SeqCst);
+    // Keeps the writes above from being elided as dead stores
+    let _ = unsafe { ptr::read_volatile(ptr.as_ptr()) };
+}
+
+/// Allocates `size` bytes from `allocator` and locks them if `secure`
+pub(crate) fn allocate(
+    allocator: &SharedAllocator,
+    size: usize,
+    secure: bool,
+) -> Result<NonNull<u8>, Error> {
+    let ptr = allocator.allocate(size).ok_or_else(|| {
+        Error::new(ErrorKind::MemoryAllocationError).add_context(format!("Bytes: {}", size))
+    })?;
+    if secure && let Err(error) = lock(ptr, size) {
+        unsafe { allocator.deallocate(ptr, size) };
+        return Err(error);
+    }
+    Ok(ptr)
+}
+
+/// A copy of a long-lived secret, such as the bytes of a protected
+/// [`SecretKey`](../input/struct.SecretKey.html), stored on pages of its own with an
+/// inaccessible guard page on either side. The pages are locked into RAM, excluded from core
+/// dumps and inaccessible (`PROT_NONE`) except while someone holds an [`Access`] to them
+pub(crate) struct GuardedBytes {
+    /// Start of the mapping, i.e. of the leading guard page
+    map: NonNull<u8>,
+    map_len: usize,
+    /// Start of the pages holding the secret, one page after `map`
+    pages: NonNull<u8>,
+    pages_len: usize,
+    /// Start of the secret, which is placed at the end of its pages so that reading past it
+    /// runs into the trailing guard page
+    ptr: NonNull<u8>,
+    len: usize,
+    /// Number of live [`Access`]es; the pages are readable while it is non-zero
+    readers: Mutex<usize>,
+}
+
+// Safe, as the bytes are never written to after construction and access to their protection
+// is synchronized through `readers`
+unsafe impl Send for GuardedBytes {}
+unsafe impl Sync for GuardedBytes {}
+
+impl GuardedBytes {
+    /// Copies `bytes` onto guarded pages. Returns an
+    /// [`ErrorKind::MemoryAllocationError`](../enum.ErrorKind.html#variant.MemoryAllocationError)
+    /// if the pages could not be mapped, or an
+    /// [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError) if they
+    /// could not be locked (or guarded pages are not supported on this platform)
+    #[cfg(unix)]
+    pub(crate) fn new(bytes: &[u8]) -> Result<GuardedBytes, Error> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let pages_len = bytes.len().div_ceil(page_size).max(1) * page_size;
+        let map_len = pages_len + 2 * page_size;
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return Err(Error::new(ErrorKind::MemoryAllocationError)
+                .add_context(format!("Bytes: {}", map_len)));
+        }
+        let map = NonNull::new(map as *mut u8).unwrap();
+        let pages = unsafe { NonNull::new_unchecked(map.as_ptr().add(page_size)) };
+        let unmap = || unsafe { libc::munmap(map.as_ptr() as *mut libc::c_void, map_len) };
+        if let Err(error) = protect(pages, pages_len, libc::PROT_READ | libc::PROT_WRITE) {
+            unmap();
+            return Err(error);
+        }
+        if let Err(error) = lock(pages, pages_len) {
+            unmap();
+            return Err(error);
+        }
+        let ptr = unsafe { NonNull::new_unchecked(pages.as_ptr().add(pages_len - bytes.len())) };
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.as_ptr(), bytes.len()) };
+        if let Err(error) = protect(pages, pages_len, libc::PROT_NONE) {
+            unsafe { unlock(pages, pages_len) };
+            unmap();
+            return Err(error);
+        }
+        Ok(GuardedBytes {
+            map,
+            map_len,
+            pages,
+            pages_len,
+            ptr,
+            len: bytes.len(),
+            readers: Mutex::new(0),
+        })
+    }
+
+    /// Returns an [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError),
+    /// since guarded pages are not supported on this platform
+    #[cfg(not(unix))]
+    pub(crate) fn new(_bytes: &[u8]) -> Result<GuardedBytes, Error> {
+        Err(Error::new(ErrorKind::MemoryLockError)
+            .add_context("Guarded pages are not supported on this platform"))
+    }
+
+    /// Makes the bytes readable until the returned [`Access`] (and every other [`Access`]
+    /// alive at the same time) is dropped. Returns an
+    /// [`ErrorKind::MemoryLockError`](../enum.ErrorKind.html#variant.MemoryLockError) if the
+    /// protection of the pages could not be changed
+    pub(crate) fn access(&self) -> Result<Access<'_>, Error> {
+        let mut readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        if *readers == 0 {
+            #[cfg(unix)]
+            protect(self.pages, self.pages_len, libc::PROT_READ)?;
+        }
+        *readers += 1;
+        Ok(Access { bytes: self })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl fmt::Debug for GuardedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GuardedBytes")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Eq for GuardedBytes {}
+
+impl Hash for GuardedBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Only the length, which equal bytes share, so that hashing never has to unprotect them
+        self.len.hash(state);
+    }
+}
+
+impl PartialEq for GuardedBytes {
+    /// Compares the bytes, treating them as different if either could not be unprotected
+    fn eq(&self, other: &GuardedBytes) -> bool {
+        if ptr::eq(self, other) {
+            return true;
+        }
+        match (self.access(), other.access()) {
+            (Ok(a), Ok(b)) => a.as_bytes() == b.as_bytes(),
+            _ => false,
+        }
+    }
+}
+
+impl Drop for GuardedBytes {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            let _ = protect(
+                self.pages,
+                self.pages_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+            );
+            unlock(self.pages, self.pages_len);
+            libc::munmap(self.map.as_ptr() as *mut libc::c_void, self.map_len);
+        }
+    }
+}
+
+/// Read access to the bytes of a [`GuardedBytes`], which protects them again when the last
+/// access is dropped
+pub(crate) struct Access<'a> {
+    bytes: &'a GuardedBytes,
+}
+
+impl Access<'_> {
+    /// Returns the bytes, which are readable for as long as `self` is alive
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        unsafe { ::std::slice::from_raw_parts(self.bytes.ptr.as_ptr(), self.bytes.len) }
+    }
+}
+
+impl fmt::Debug for Access<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Access")
+            .field("len", &self.bytes.len)
+            .finish()
+    }
+}
+
+impl Drop for Access<'_> {
+    fn drop(&mut self) {
+        let mut readers = self
+            .bytes
+            .readers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *readers -= 1;
+        if *readers == 0 {
+            // Leaves the bytes readable, which is still safe, rather than panicking in a
+            // destructor
+            #[cfg(unix)]
+            if let Err(e) = protect(self.bytes.pages, self.bytes.pages_len, libc::PROT_NONE) {
+                warn!("{}", e);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn protect(ptr: NonNull<u8>, size: usize, protection: libc::c_int) -> Result<(), Error> {
+    if unsafe { libc::mprotect(ptr.as_ptr() as *mut libc::c_void, size, protection) } != 0 {
+        return Err(Error::new(ErrorKind::MemoryLockError).add_context(format!(
+            "Failed to change the protection of memory: {}",
+            ::std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn memlock_limit() -> String {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0 {
+        return "unknown".to_string();
+    }
+    match limit.rlim_cur {
+        libc::RLIM_INFINITY => "unlimited".to_string(),
+        bytes => format!("{} bytes", bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate() {
+        let allocator = default_allocator();
+        let size = 64 * 1024;
+        match allocate(&allocator, size, true) {
+            Ok(ptr) => unsafe {
+                ptr::write_bytes(ptr.as_ptr(), 0xff, size);
+                unlock(ptr, size);
+                let bytes = ::std::slice::from_raw_parts(ptr.as_ptr(), size);
+                assert!(bytes.iter().all(|&byte| byte == 0));
+                allocator.deallocate(ptr, size);
+            },
+            // Locking is allowed to fail, e.g. in a container with a low RLIMIT_MEMLOCK
+            Err(error) => assert_eq!(error.kind(), ErrorKind::MemoryLockError),
+        }
+    }
+}
@@ -1,11 +1,29 @@
-use futures::{executor::ThreadPool, task::SpawnExt};
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::{sync::Mutex, time::Duration};
+
+#[cfg(feature = "std")]
+use futures::{Stream, executor::ThreadPool, task::SpawnExt};
+
+#[cfg(feature = "std")]
+use crate::{
+    allocator::{MemoryAllocator, SharedAllocator},
+    batch,
+    cancellation::Cancellation,
+    config::{BatchOrder, BudgetPolicy},
+    executor::{Executor, SharedExecutor},
+    pool,
+};
 
 use crate::{
     Error, ErrorKind, Hasher,
-    backend::decode_rust,
-    config::{Backend, VerifierConfig, default_cpu_pool},
-    input::{AdditionalData, Password, SecretKey},
-    output::HashRaw,
+    backend::{decode_rust, decode_rust_lenient},
+    config::{
+        Backend, DEFAULT_LENIENT_DECODING, HasherConfig, LaneScheduling, Variant, VerifierConfig,
+        VerifierLimits, Version,
+    },
+    input::{AdditionalData, Password, Salt, SecretKey, SecretKeyRing, unknown_key_id},
+    output::{HashRaw, ShadowMismatch, Verification, constant_time_eq},
 };
 
 #[derive(Clone, Debug)]
@@ -25,6 +43,9 @@ impl<'a> Default for Verifier<'a> {
         Verifier {
             hash: Hash::default(),
             hasher: Hasher::default(),
+            lenient_decoding: DEFAULT_LENIENT_DECODING,
+            limits: VerifierLimits::default(),
+            secret_key_ring: None,
         }
     }
 }
@@ -36,43 +57,157 @@ impl<'a> Default for Verifier<'a> {
 pub struct Verifier<'a> {
     hash: Hash,
     hasher: Hasher<'a>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    lenient_decoding: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    limits: VerifierLimits,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    secret_key_ring: Option<SecretKeyRing<'a>>,
 }
 
 impl<'a> Verifier<'a> {
     /// Creates a new [`Verifier`](struct.Verifier.html) with the following configuration:
+    /// * `allocator`: None, i.e. the system allocator
     /// * `backend`: [`Backend::C`](config/enum.Backend.html#variant.C)
-    /// * `cpu_pool`: A [`CpuPool`](https://docs.rs/futures-cpupool/0.1.8/futures_cpupool/struct.CpuPool.html) ...
-    ///     * with threads equal to the number of logical cores on your machine
-    ///     * that is lazily created, i.e. created only if / when you call the method that
-    ///       needs it ([`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking))
+    /// * `budget_policy`: [`BudgetPolicy::Wait`](config/enum.BudgetPolicy.html#variant.Wait)
+    /// * `cpu_pool`: None, i.e. [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking)
+    ///   runs on the process-wide thread pool described in the [`pool`](pool/index.html) module
+    /// * `executor`: None
+    /// * `lane_scheduling`: [`LaneScheduling::Threads`](config/enum.LaneScheduling.html#variant.Threads)
+    /// * `lenient_decoding`: `false`
+    /// * `limits`: See [`VerifierLimits`](config/struct.VerifierLimits.html#impl-Default)
     /// * `password_clearing`: `false`
     /// * `secret_key_clearing`: `false`
+    /// * `secure_memory`: `false`
+    /// * `shadow_mode`: `false`
     /// * `threads`: The number of logical cores on your machine
+    /// * `timeout`: None
     pub fn new() -> Verifier<'a> {
         Verifier::default()
     }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to allocate the working
+    /// memory of each verification from `allocator`. See
+    /// [`Hasher::configure_allocator`](struct.Hasher.html#method.configure_allocator)
+    #[cfg(feature = "std")]
+    pub fn configure_allocator<A: MemoryAllocator>(&mut self, allocator: A) -> &mut Verifier<'a> {
+        self.hasher
+            .config
+            .set_allocator(SharedAllocator::new(allocator));
+        self
+    }
     /// Allows you to configure [`Verifier`](struct.Verifier.html) with a custom backend. The
-    /// default backend is [`Backend::C`](config/enum.Backend.html#variant.C), <i>which is
-    /// currently the only backend supported. A Rust backend is planned, but is not currently
-    /// available. If you configure a [`Verifier`](struct.Verifier.html) with
-    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) it will error</i>
+    /// default backend is [`Backend::C`](config/enum.Backend.html#variant.C). The
+    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) backend produces identical
+    /// results and does not need a C compiler to build.
+    /// [`Backend::Auto`](config/enum.Backend.html#variant.Auto) picks the C backend unless
+    /// the Rust backend uses a higher SIMD level on the current machine. <i>If argonautica was built without
+    /// the `backend-c` feature, the default backend is
+    /// [`Backend::Rust`](config/enum.Backend.html#variant.Rust) and configuring
+    /// [`Backend::C`](config/enum.Backend.html#variant.C) will error</i>
     pub fn configure_backend(&mut self, backend: Backend) -> &mut Verifier<'a> {
         self.hasher.config.set_backend(backend);
         self
     }
+    /// Allows you to configure what [`Verifier`](struct.Verifier.html) does when the
+    /// process-wide [memory budget](memory_budget/index.html) cannot cover the memory of a
+    /// verification yet. See
+    /// [`Hasher::configure_budget_policy`](struct.Hasher.html#method.configure_budget_policy)
+    #[cfg(feature = "std")]
+    pub fn configure_budget_policy(&mut self, budget_policy: BudgetPolicy) -> &mut Verifier<'a> {
+        self.hasher.config.set_budget_policy(budget_policy);
+        self
+    }
     /// Allows you to configure [`Verifier`](struct.Verifier.html) with a custom
     /// [`CpuPool`](https://docs.rs/futures-cpupool/0.1.8/futures_cpupool/struct.CpuPool.html).
     /// The default [`Verifier`](struct.Verifier.html) does not have a cpu pool, which is
     /// only needed for the [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking)
     /// method. If you call [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking)
-    /// without a cpu pool, a default cpu pool will be created for you on the fly; so even
-    /// if you never configure [`Verifier`](struct.Verifier.html) with this method you can still
-    /// use the [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking) method.
-    /// The default cpu pool has as many threads as the number of logical cores on your machine
+    /// without a cpu pool, it runs on the process-wide thread pool described in the
+    /// [`pool`](pool/index.html) module, which has as many threads as the number of logical
+    /// cores on your machine and a bounded queue; so even if you never configure
+    /// [`Verifier`](struct.Verifier.html) with this method you can still use the
+    /// [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking) method. A cpu
+    /// pool configured with this method has no queue limit
+    #[cfg(feature = "std")]
     pub fn configure_thread_pool(&mut self, thread_pool: ThreadPool) -> &mut Verifier<'a> {
         self.hasher.config.set_thread_pool(thread_pool);
         self
     }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to run [`verify_non_blocking`](struct.Verifier.html#method.verify_non_blocking) on an
+    /// [`Executor`](executor/trait.Executor.html), e.g. the blocking thread pool of your async
+    /// runtime (see the [`executor`](executor/index.html) module for the adapters). An executor
+    /// takes precedence over a thread pool configured with
+    /// [`configure_thread_pool`](struct.Verifier.html#method.configure_thread_pool). The default
+    /// [`Verifier`](struct.Verifier.html) does not have an executor
+    #[cfg(feature = "std")]
+    pub fn configure_executor<E: Executor>(&mut self, executor: E) -> &mut Verifier<'a> {
+        self.hasher
+            .config
+            .set_executor(SharedExecutor::new(executor));
+        self
+    }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to accept string-encoded
+    /// hashes that are not canonical [PHC strings](https://github.com/P-H-C/phc-string-format),
+    /// e.g. hashes with surrounding whitespace, base64 `=` padding, leading zeros, parameters
+    /// in an unusual order or unknown parameters (which are ignored). The default is `false`,
+    /// in which case such hashes fail to verify with an
+    /// [`ErrorKind::HashDecodeError`](enum.ErrorKind.html#variant.HashDecodeError).
+    /// Turn this on if you store hashes that were written by other encoders.
+    /// See [`HashRaw::from_str_lenient`](output/struct.HashRaw.html#method.from_str_lenient)
+    pub fn configure_lenient_decoding(&mut self, boolean: bool) -> &mut Verifier<'a> {
+        self.lenient_decoding = boolean;
+        self
+    }
+    /// Allows you to configure which variants [`Verifier`](struct.Verifier.html) accepts.
+    /// Hashes of any other variant fail to verify with an
+    /// [`ErrorKind::HashParamsRejectedError`](enum.ErrorKind.html#variant.HashParamsRejectedError).
+    /// The default is all variants
+    pub fn configure_allowed_variants(&mut self, variants: &[Variant]) -> &mut Verifier<'a> {
+        self.limits.set_allowed_variants(variants);
+        self
+    }
+    /// Allows you to configure which versions [`Verifier`](struct.Verifier.html) accepts.
+    /// Hashes of any other version fail to verify with an
+    /// [`ErrorKind::HashParamsRejectedError`](enum.ErrorKind.html#variant.HashParamsRejectedError).
+    /// The default is all versions
+    pub fn configure_allowed_versions(&mut self, versions: &[Version]) -> &mut Verifier<'a> {
+        self.limits.set_allowed_versions(versions);
+        self
+    }
+    /// Allows you to configure the longest hash (in bytes) [`Verifier`](struct.Verifier.html)
+    /// accepts. The default is `1024`
+    pub fn configure_max_hash_len(&mut self, max_hash_len: u32) -> &mut Verifier<'a> {
+        self.limits.set_max_hash_len(max_hash_len);
+        self
+    }
+    /// Allows you to configure the largest number of iterations
+    /// [`Verifier`](struct.Verifier.html) accepts in a hash. The default is `1024`
+    pub fn configure_max_iterations(&mut self, max_iterations: u32) -> &mut Verifier<'a> {
+        self.limits.set_max_iterations(max_iterations);
+        self
+    }
+    /// Allows you to configure the largest number of lanes [`Verifier`](struct.Verifier.html)
+    /// accepts in a hash. The default is `256`
+    pub fn configure_max_lanes(&mut self, max_lanes: u32) -> &mut Verifier<'a> {
+        self.limits.set_max_lanes(max_lanes);
+        self
+    }
+    /// Allows you to configure the largest memory size (in kibibytes)
+    /// [`Verifier`](struct.Verifier.html) accepts in a hash. Hashes above it fail to verify
+    /// with an
+    /// [`ErrorKind::HashParamsRejectedError`](enum.ErrorKind.html#variant.HashParamsRejectedError)
+    /// before any memory is allocated. The default is `2097152` (2 GiB); lower it to the
+    /// largest memory size you actually hash with
+    pub fn configure_max_memory_size(&mut self, max_memory_size: u32) -> &mut Verifier<'a> {
+        self.limits.set_max_memory_size(max_memory_size);
+        self
+    }
+    /// Allows you to configure the shortest salt (in bytes) [`Verifier`](struct.Verifier.html)
+    /// accepts. The default is `8`
+    pub fn configure_min_salt_len(&mut self, min_salt_len: u32) -> &mut Verifier<'a> {
+        self.limits.set_min_salt_len(min_salt_len);
+        self
+    }
     /// Allows you to configure [`Verifier`](struct.Verifier.html) to erase the password bytes
     /// after each call to [`verify`](struct.Verifier.html#method.verify)
     /// or its non-blocking equivalent. The default is to <b>not</b> clear out the password
@@ -97,6 +232,43 @@ impl<'a> Verifier<'a> {
         self.hasher.config.set_secret_key_clearing(boolean);
         self
     }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to lock its working memory
+    /// into RAM, exclude it from core dumps and wipe it before it is released. See
+    /// [`Hasher::configure_secure_memory`](struct.Hasher.html#method.configure_secure_memory)
+    #[cfg(feature = "std")]
+    pub fn configure_secure_memory(&mut self, boolean: bool) -> &mut Verifier<'a> {
+        self.hasher.config.set_secure_memory(boolean);
+        self
+    }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to run in shadow mode, i.e.
+    /// to compute every verification with both the C and the Rust backends, trusting the C
+    /// backend and reporting any disagreement from the Rust backend. The default is `false`.
+    /// See [`Hasher::configure_shadow_mode`](struct.Hasher.html#method.configure_shadow_mode)
+    /// for details
+    pub fn configure_shadow_mode(&mut self, boolean: bool) -> &mut Verifier<'a> {
+        self.hasher.config.set_shadow_mode(boolean);
+        self
+    }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) with a callback that is
+    /// invoked whenever shadow mode detects that the Rust backend disagrees with the C backend.
+    /// By default, mismatches are logged at the error level instead
+    pub fn configure_shadow_callback<F>(&mut self, callback: F) -> &mut Verifier<'a>
+    where
+        F: Fn(&ShadowMismatch) + Send + Sync + 'static,
+    {
+        self.hasher.configure_shadow_callback(callback);
+        self
+    }
+    /// Allows you to configure how [`Verifier`](struct.Verifier.html) runs the lanes of hashes
+    /// with more than one thread. See
+    /// [`Hasher::configure_lane_scheduling`](struct.Hasher.html#method.configure_lane_scheduling)
+    pub fn configure_lane_scheduling(
+        &mut self,
+        lane_scheduling: LaneScheduling,
+    ) -> &mut Verifier<'a> {
+        self.hasher.config.set_lane_scheduling(lane_scheduling);
+        self
+    }
     /// Allows you to configure [`Verifier`](struct.Verifier.html) to use a custom number of
     /// threads. The default is the number of physical cores on your machine. If you choose
     /// a number of threads that is greater than the lanes configuration of your hash,
@@ -105,6 +277,16 @@ impl<'a> Verifier<'a> {
         self.hasher.config.set_threads(threads);
         self
     }
+    /// Allows you to configure [`Verifier`](struct.Verifier.html) to give up on
+    /// verifications that take longer than `timeout`, returning an
+    /// [`ErrorKind::TimeoutError`](enum.ErrorKind.html#variant.TimeoutError). See
+    /// [`Hasher::configure_timeout`](struct.Hasher.html#method.configure_timeout). The
+    /// default is `None`, i.e. no timeout
+    #[cfg(feature = "std")]
+    pub fn configure_timeout(&mut self, timeout: Option<Duration>) -> &mut Verifier<'a> {
+        self.hasher.config.set_timeout(timeout);
+        self
+    }
     /// Clones the [`Verifier`](struct.Verifier.html), returning a new
     /// [`Verifier`](struct.Verifier.html) with a `static` lifetime. Use this method if you
     /// would like to move a [`Verifier`](struct.Verifier.html) to another thread
@@ -112,6 +294,12 @@ impl<'a> Verifier<'a> {
         Verifier {
             hash: self.hash.clone(),
             hasher: self.hasher.to_owned(),
+            lenient_decoding: self.lenient_decoding,
+            limits: self.limits.clone(),
+            secret_key_ring: self
+                .secret_key_ring
+                .as_ref()
+                .map(|secret_key_ring| secret_key_ring.to_owned()),
         }
     }
     /// <b><u>The primary method (blocking version)</u></b>
@@ -124,50 +312,56 @@ impl<'a> Verifier<'a> {
     /// * [`AdditionalData`](input/struct.AdditionalData.html) (if required),
     ///
     /// call this method to verify that the password matches the hash or
-    /// [`HashRaw`](output/struct.HashRaw.html)
+    /// [`HashRaw`](output/struct.HashRaw.html). The raw hash bytes are compared in constant
+    /// time
     pub fn verify(&mut self) -> Result<bool, Error> {
-        match self.hash {
-            Hash::Encoded(ref s) => {
-                let hash_raw = decode_rust(s)?;
-                self.hasher
-                    .config
-                    .set_hash_len(hash_raw.raw_hash_bytes().len() as u32);
-                self.hasher.config.set_iterations(hash_raw.iterations());
-                self.hasher.config.set_lanes(hash_raw.lanes());
-                self.hasher.config.set_memory_size(hash_raw.memory_size());
-                self.hasher.config.set_opt_out_of_secret_key(true);
-                self.hasher.config.set_variant(hash_raw.variant());
-                self.hasher.config.set_version(hash_raw.version());
-                self.hasher.salt = hash_raw.raw_salt_bytes().into();
-                let hash_raw2 = self.hasher.hash_raw()?;
-                let is_valid = hash_raw.raw_hash_bytes() == hash_raw2.raw_hash_bytes();
-                Ok(is_valid)
-            }
-            Hash::Raw(ref hash_raw) => {
-                self.hasher
-                    .config
-                    .set_hash_len(hash_raw.raw_hash_bytes().len() as u32);
-                self.hasher.config.set_iterations(hash_raw.iterations());
-                self.hasher.config.set_lanes(hash_raw.lanes());
-                self.hasher.config.set_memory_size(hash_raw.memory_size());
-                self.hasher.config.set_opt_out_of_secret_key(true);
-                self.hasher.config.set_variant(hash_raw.variant());
-                self.hasher.config.set_version(hash_raw.version());
-                self.hasher.salt = hash_raw.raw_salt_bytes().into();
-                let hash_raw2 = self.hasher.hash_raw()?;
-                let is_valid = hash_raw.raw_hash_bytes() == hash_raw2.raw_hash_bytes();
-                Ok(is_valid)
+        let hash_raw = match self.hash {
+            Hash::Encoded(ref s) => self.decode(s)?,
+            Hash::Raw(ref hash_raw) => hash_raw.clone(),
+            Hash::None => return Err(Error::new(ErrorKind::HashMissingError)),
+        };
+        self.limits.check(&hash_raw)?;
+        self.hasher
+            .config
+            .set_hash_len(hash_raw.raw_hash_bytes().len() as u32);
+        self.hasher.config.set_iterations(hash_raw.iterations());
+        self.hasher.config.set_lanes(hash_raw.lanes());
+        self.hasher.config.set_memory_size(hash_raw.memory_size());
+        self.hasher.config.set_opt_out_of_secret_key(true);
+        self.hasher.config.set_variant(hash_raw.variant());
+        self.hasher.config.set_version(hash_raw.version());
+        self.hasher.salt = hash_raw.raw_salt_bytes().into();
+        let hash_raw2 = match self.secret_key_from_ring(&hash_raw)? {
+            Some(secret_key) => {
+                // Hashes with a key ID from the ring, leaving any secret key provided via
+                // with_secret_key in place for hashes without one
+                let secret_key = self.hasher.secret_key.replace(secret_key);
+                let result = self.hasher.hash_raw();
+                self.hasher.secret_key = secret_key;
+                result?
             }
-            Hash::None => Err(Error::new(ErrorKind::HashMissingError)),
-        }
+            None => self.hasher.hash_raw()?,
+        };
+        let is_valid = constant_time_eq(hash_raw.raw_hash_bytes(), hash_raw2.raw_hash_bytes());
+        Ok(is_valid)
     }
     /// <b><u>The primary method (non-blocking version)</u></b>
     ///
     /// Same as [`verify`](struct.Verifier.html#method.verify) except it returns a
     /// [`Future`](https://docs.rs/futures/0.1.21/futures/future/trait.Future.html)
     /// instead of a [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
+    #[cfg(feature = "std")]
     pub async fn verify_non_blocking(&mut self) -> Result<bool, Error> {
         let mut verifier = self.to_owned();
+        verifier.hasher.cancellation = Cancellation::new(verifier.hasher.config.timeout());
+        // Stops the hash if the returned future is dropped before it resolves
+        let _cancel_on_drop =
+            scopeguard::guard(verifier.hasher.cancellation.clone(), |cancellation| {
+                cancellation.cancel();
+            });
+        if let Some(executor) = verifier.hasher.config.executor().cloned() {
+            return executor.run(move || verifier.verify()).await;
+        }
         match verifier.hasher.config.thread_pool() {
             Some(cpu_pool) => {
                 cpu_pool
@@ -175,15 +369,95 @@ impl<'a> Verifier<'a> {
                     .expect("Failed to spawn")
                     .await
             }
-            None => {
-                let cpu_pool = default_cpu_pool();
-                verifier.hasher.config.set_thread_pool(cpu_pool.clone());
-                cpu_pool
-                    .spawn_with_handle(async move { verifier.verify() })
-                    .expect("Failed to spawn")
-                    .await
+            None => pool::spawn(move || verifier.verify()).await,
+        }
+    }
+    /// Verifies every `(hash, password)` pair of `items` with this
+    /// [`Verifier`](struct.Verifier.html)'s configuration, secret key (or key ring) and
+    /// additional data, and yields whether each password matched together with the index of
+    /// its pair, in the given [`BatchOrder`](config/enum.BatchOrder.html). An error only fails
+    /// its own pair; the rest of the batch carries on. Scheduling works like that of
+    /// [`Hasher::hash_many`](struct.Hasher.html#method.hash_many)
+    #[cfg(feature = "std")]
+    pub fn verify_many<'p, S, H, P>(
+        &self,
+        items: S,
+        order: BatchOrder,
+    ) -> impl Stream<Item = (usize, Result<bool, Error>)> + use<'p, S, H, P>
+    where
+        S: Stream<Item = (H, P)>,
+        H: AsRef<str>,
+        P: Into<Password<'p>>,
+    {
+        let mut template = self.to_owned();
+        template.hasher.password = None;
+        let threads = template.hasher.config.threads();
+        batch::run(items, order, threads, move |(hash, password), threads| {
+            let password: Password = password.into();
+            let mut verifier = template.to_owned();
+            verifier
+                .configure_threads(threads)
+                .with_hash(hash)
+                .with_password(password.to_owned());
+            async move { verifier.verify_non_blocking().await }
+        })
+    }
+    /// Same as [`verify`](struct.Verifier.html#method.verify), except that if the password
+    /// matches a hash that was not produced with the configuration of `hasher` (see
+    /// [`HashRaw::needs_rehash`](output/struct.HashRaw.html#method.needs_rehash)), it also
+    /// hashes the password with `hasher` and returns the new string-encoded hash as
+    /// [`Verification::Upgraded`](output/enum.Verification.html#variant.Upgraded). Store it in
+    /// place of the old hash to move users to your current configuration as they log in.
+    ///
+    /// `hasher` should be configured the way you create new hashes, including its secret key
+    /// (or [`SecretKeyRing`](input/struct.SecretKeyRing.html)); the password is taken from
+    /// this [`Verifier`](struct.Verifier.html). Any password `hasher` was provided with is
+    /// left in place
+    pub fn verify_and_upgrade(&mut self, hasher: &mut Hasher) -> Result<Verification, Error> {
+        let hash_raw = self
+            .hash_raw()?
+            .ok_or_else(|| Error::new(ErrorKind::HashMissingError))?;
+        if !hash_raw.needs_rehash(hasher.config()) {
+            let is_valid = self.verify()?;
+            return Ok(if is_valid {
+                Verification::Valid
+            } else {
+                Verification::Invalid
+            });
+        }
+        // verify may clear the password; so hash with a copy, which is cleared once done
+        let password = self.hasher.password.as_ref().map(|p| p.to_owned());
+        let mut password = scopeguard::guard(password, |password| {
+            if let Some(mut password) = password {
+                password.inner.clear();
             }
+        });
+        if !self.verify()? {
+            return Ok(Verification::Invalid);
         }
+        let previous = core::mem::replace(&mut hasher.password, password.take());
+        let result = hasher.hash();
+        *password = core::mem::replace(&mut hasher.password, previous);
+        Ok(Verification::Upgraded(result?))
+    }
+    /// Runs a full verification of the [`Verifier`](struct.Verifier.html)'s password against
+    /// a dummy hash with the parameters, salt length and key ID of `config`, and returns
+    /// `false`. Call it when a login names an account that does not exist, passing the
+    /// configuration of the [`Hasher`](struct.Hasher.html) you create hashes with, so that
+    /// the failed login takes as long as one with a wrong password and does not reveal
+    /// whether the account exists.
+    ///
+    /// Provide the password and secret key (or
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html)) as you would for
+    /// [`verify`](struct.Verifier.html#method.verify). The dummy hash is created once and
+    /// reused for as long as `config` does not change. Errors are returned just like
+    /// [`verify`](struct.Verifier.html#method.verify) would return them for a real hash
+    pub fn verify_dummy(&mut self, config: &HasherConfig) -> Result<bool, Error> {
+        let dummy_hash_raw = dummy_hash_raw(config)?;
+        let hash = core::mem::replace(&mut self.hash, Hash::Raw(dummy_hash_raw));
+        let result = self.verify();
+        self.hash = hash;
+        result.map(|_| false)
     }
     /// Allows you to provide [`Verifier`](struct.Verifier.html) with the additional data
     /// that was originally used to create the hash. Normally hashes are not created with
@@ -199,7 +473,8 @@ impl<'a> Verifier<'a> {
     /// against (in the form of a string-encoded hash like those produced by the
     /// [`hash`](struct.Hasher.html#method.hash) or
     /// [`hash_non_blocking`](struct.Hasher.html#method.hash_non_blocking)
-    /// methods on [`Hasher`](struct.Hasher.html))
+    /// methods on [`Hasher`](struct.Hasher.html)). Legacy hashes without a `$v=` segment
+    /// are treated as [`Version::_0x10`](config/enum.Version.html#variant._0x10)
     pub fn with_hash<S>(&mut self, hash: S) -> &mut Verifier<'a>
     where
         S: AsRef<str>,
@@ -226,7 +501,9 @@ impl<'a> Verifier<'a> {
         self
     }
     /// Allows you to provide [`Verifier`](struct.Verifier.html) with the secret key
-    /// that was initially used to create the hash
+    /// that was initially used to create the hash. If you have also provided a
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html), this key is only used for hashes
+    /// without a `keyid` parameter, e.g. hashes created before you started rotating keys
     pub fn with_secret_key<SK>(&mut self, secret_key: SK) -> &mut Verifier<'a>
     where
         SK: Into<SecretKey<'a>>,
@@ -234,6 +511,20 @@ impl<'a> Verifier<'a> {
         self.hasher.secret_key = Some(secret_key.into());
         self
     }
+    /// Allows you to provide [`Verifier`](struct.Verifier.html) with a
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html). When the hash has a `keyid`
+    /// parameter, [`verify`](struct.Verifier.html#method.verify) uses the key with that ID,
+    /// returning an [`ErrorKind::SecretKeyIdUnknownError`](enum.ErrorKind.html#variant.SecretKeyIdUnknownError)
+    /// if the ring does not contain it. Call
+    /// [`uses_retired_secret_key`](struct.Verifier.html#method.uses_retired_secret_key) to find
+    /// out whether the hash should be recreated with the active key
+    pub fn with_secret_key_ring(
+        &mut self,
+        secret_key_ring: SecretKeyRing<'a>,
+    ) -> &mut Verifier<'a> {
+        self.secret_key_ring = Some(secret_key_ring);
+        self
+    }
     /// Read-only access to the [`Verifier`](struct.Verifier.html)'s
     /// [`AdditionalData`](input/struct.AdditionalData.html), if any
     pub fn additional_data(&self) -> Option<&AdditionalData> {
@@ -242,13 +533,24 @@ impl<'a> Verifier<'a> {
     /// Read-only access to the [`Verifier`](struct.Verifier.html)'s
     /// [`VerifierConfig`](config/struct.VerifierConfig.html)
     pub fn config(&self) -> VerifierConfig {
-        VerifierConfig::new(
+        let mut config = VerifierConfig::new(
             /* backend */ self.hasher.config.backend(),
-            /* cpu_pool */ self.hasher.config.thread_pool(),
+            /* lenient_decoding */ self.lenient_decoding,
+            /* limits */ self.limits.clone(),
             /* password_clearing */ self.hasher.config.password_clearing(),
             /* secret_key_clearing */ self.hasher.config.secret_key_clearing(),
+            /* shadow_mode */ self.hasher.config.shadow_mode(),
             /* threads */ self.hasher.config.threads(),
-        )
+        );
+        config.lane_scheduling = self.hasher.config.lane_scheduling();
+        #[cfg(feature = "std")]
+        {
+            config.budget_policy = self.hasher.config.budget_policy();
+            config.secure_memory = self.hasher.config.secure_memory();
+            config.thread_pool = self.hasher.config.thread_pool();
+            config.timeout = self.hasher.config.timeout();
+        }
+        config
     }
     /// Returns the [`Verifier`](struct.Verifier.html)'s string-encoded hash, if any
     pub fn hash(&self) -> Option<String> {
@@ -262,7 +564,7 @@ impl<'a> Verifier<'a> {
     /// if any
     pub fn hash_raw(&self) -> Result<Option<HashRaw>, Error> {
         match self.hash {
-            Hash::Encoded(ref s) => Ok(Some(decode_rust(s)?)),
+            Hash::Encoded(ref s) => Ok(Some(self.decode(s)?)),
             Hash::Raw(ref hash_raw) => Ok(Some(hash_raw.clone())),
             Hash::None => Ok(None),
         }
@@ -277,6 +579,90 @@ impl<'a> Verifier<'a> {
     pub fn secret_key(&self) -> Option<&SecretKey<'a>> {
         self.hasher.secret_key()
     }
+    /// Read-only access to the [`Verifier`](struct.Verifier.html)'s
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html), if any
+    pub fn secret_key_ring(&self) -> Option<&SecretKeyRing<'a>> {
+        self.secret_key_ring.as_ref()
+    }
+    /// Returns `true` if the [`Verifier`](struct.Verifier.html) has a
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html) and its hash was not created with
+    /// the ring's active key, i.e. if the hash has no `keyid` parameter or names a key that
+    /// is not the active one. After a successful
+    /// [`verify`](struct.Verifier.html#method.verify), use this to decide whether to rehash
+    /// the password with the active key. Returns `false` if there is no
+    /// [`SecretKeyRing`](input/struct.SecretKeyRing.html)
+    pub fn uses_retired_secret_key(&self) -> Result<bool, Error> {
+        let secret_key_ring = match self.secret_key_ring {
+            Some(ref secret_key_ring) => secret_key_ring,
+            None => return Ok(false),
+        };
+        let hash_raw = self
+            .hash_raw()?
+            .ok_or_else(|| Error::new(ErrorKind::HashMissingError))?;
+        Ok(hash_raw.key_id() != Some(secret_key_ring.active_key_id()))
+    }
+}
+
+impl Verifier<'_> {
+    fn decode(&self, hash: &str) -> Result<HashRaw, Error> {
+        if self.lenient_decoding {
+            decode_rust_lenient(hash)
+        } else {
+            decode_rust(hash)
+        }
+    }
+    fn secret_key_from_ring(
+        &self,
+        hash_raw: &HashRaw,
+    ) -> Result<Option<SecretKey<'static>>, Error> {
+        match (hash_raw.key_id(), self.secret_key_ring.as_ref()) {
+            (Some(key_id), Some(secret_key_ring)) => match secret_key_ring.get(key_id) {
+                Some(secret_key) => Ok(Some(secret_key.to_owned())),
+                None => Err(unknown_key_id(key_id)),
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Returns a hash that no password matches, with the parameters of `config`, reusing the
+/// previous one if `config` has not changed
+fn dummy_hash_raw(config: &HasherConfig) -> Result<HashRaw, Error> {
+    #[cfg(feature = "std")]
+    {
+        static DUMMY_HASH_RAW: Mutex<Option<HashRaw>> = Mutex::new(None);
+        let mut dummy_hash_raw = DUMMY_HASH_RAW.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref hash_raw) = *dummy_hash_raw
+            && !hash_raw.needs_rehash(config)
+        {
+            return Ok(hash_raw.clone());
+        }
+        let hash_raw = new_dummy_hash_raw(config)?;
+        *dummy_hash_raw = Some(hash_raw.clone());
+        Ok(hash_raw)
+    }
+    #[cfg(not(feature = "std"))]
+    new_dummy_hash_raw(config)
+}
+
+fn new_dummy_hash_raw(config: &HasherConfig) -> Result<HashRaw, Error> {
+    // Without the std feature there is no OsRng; the salt of a hash that is never stored
+    // need not be random, so it is left as zeros
+    #[allow(unused_mut)]
+    let mut salt = Salt::random(config.salt_len());
+    #[cfg(feature = "std")]
+    salt.update()?;
+    Ok(HashRaw {
+        iterations: config.iterations(),
+        key_id: config.key_id().map(|key_id| key_id.to_vec()),
+        lanes: config.lanes(),
+        memory_size: config.memory_size(),
+        omit_version: false,
+        raw_hash_bytes: vec![0u8; config.hash_len() as usize],
+        raw_salt_bytes: salt.as_bytes().to_vec(),
+        variant: config.variant(),
+        version: config.version(),
+    })
 }
 
 #[cfg(test)]
@@ -341,6 +727,289 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_verify_and_upgrade() {
+        fn hasher(iterations: u32) -> Hasher<'static> {
+            let mut hasher = Hasher::default();
+            hasher
+                .configure_iterations(iterations)
+                .configure_lanes(1)
+                .configure_memory_size(32)
+                .configure_threads(1)
+                .with_salt("somesaltsomesalt")
+                .with_secret_key("secret");
+            hasher
+        }
+        let old_hash = hasher(1).with_password("P@ssw0rd").hash().unwrap();
+
+        let mut verifier = Verifier::default();
+        verifier.with_hash(&old_hash).with_secret_key("secret");
+        verifier.with_password("wrong");
+        let verification = verifier.verify_and_upgrade(&mut hasher(2)).unwrap();
+        assert_eq!(verification, Verification::Invalid);
+        assert!(!verification.is_valid());
+
+        verifier.with_password("P@ssw0rd");
+        let verification = verifier.verify_and_upgrade(&mut hasher(1)).unwrap();
+        assert_eq!(verification, Verification::Valid);
+        assert_eq!(verification.upgraded_hash(), None);
+
+        // The password is cleared afterwards if the verifier is configured to do so
+        let mut target = hasher(2);
+        target.with_password("other password");
+        verifier
+            .configure_password_clearing(true)
+            .with_password(String::from("P@ssw0rd"));
+        let verification = verifier.verify_and_upgrade(&mut target).unwrap();
+        assert!(verification.is_valid());
+        assert!(verifier.password().is_none());
+        assert_eq!(target.password().unwrap().as_bytes(), b"other password");
+
+        let new_hash = verification.upgraded_hash().unwrap();
+        let new_hash_raw = new_hash.parse::<HashRaw>().unwrap();
+        assert_eq!(new_hash_raw.iterations(), 2);
+        assert!(!new_hash_raw.needs_rehash(target.config()));
+        let mut verifier = Verifier::default();
+        let is_valid = verifier
+            .with_hash(new_hash)
+            .with_password("P@ssw0rd")
+            .with_secret_key("secret")
+            .verify()
+            .unwrap();
+        assert!(is_valid);
+    }
+
+    #[test]
+    fn test_verify_dummy() {
+        let mut hasher = Hasher::default();
+        hasher
+            .configure_iterations(1)
+            .configure_lanes(1)
+            .configure_memory_size(32)
+            .configure_threads(1)
+            .with_salt("somesaltsomesalt")
+            .with_secret_key("secret");
+        let hash = hasher.with_password("P@ssw0rd").hash().unwrap();
+
+        let mut verifier = Verifier::default();
+        verifier
+            .with_hash(&hash)
+            .with_password("P@ssw0rd")
+            .with_secret_key("secret");
+        assert!(!verifier.verify_dummy(hasher.config()).unwrap());
+        assert_eq!(verifier.hash(), Some(hash));
+        assert!(verifier.verify().unwrap());
+
+        let dummy = dummy_hash_raw(hasher.config()).unwrap();
+        assert!(!dummy.needs_rehash(hasher.config()));
+        #[cfg(feature = "std")]
+        assert_eq!(dummy_hash_raw(hasher.config()).unwrap(), dummy);
+        hasher.configure_iterations(2);
+        assert!(
+            !dummy_hash_raw(hasher.config())
+                .unwrap()
+                .needs_rehash(hasher.config())
+        );
+
+        // Same errors as a real verification
+        let mut verifier = Verifier::default();
+        let e = verifier.verify_dummy(hasher.config()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::PasswordMissingError);
+        hasher.with_secret_key_ring(SecretKeyRing::new("2025", "secret").unwrap());
+        let e = verifier
+            .with_password("P@ssw0rd")
+            .with_secret_key_ring(SecretKeyRing::new("2024", "secret").unwrap())
+            .verify_dummy(hasher.config())
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::SecretKeyIdUnknownError);
+    }
+
+    #[test]
+    fn test_verifier_secret_key_ring() {
+        fn hash(secret_key_ring: SecretKeyRing) -> String {
+            let mut hasher = Hasher::default();
+            hasher
+                .configure_iterations(1)
+                .configure_lanes(1)
+                .configure_memory_size(32)
+                .configure_threads(1)
+                .with_password("P@ssw0rd")
+                .with_salt("somesaltsomesalt")
+                .with_secret_key_ring(secret_key_ring);
+            assert!(hasher.config().key_id().is_some());
+            hasher.hash().unwrap()
+        }
+        fn verify(hash: &str, secret_key_ring: SecretKeyRing) -> (Result<bool, Error>, bool) {
+            let mut verifier = Verifier::default();
+            verifier
+                .with_hash(hash)
+                .with_password("P@ssw0rd")
+                .with_secret_key("legacy secret")
+                .with_secret_key_ring(secret_key_ring);
+            let result = verifier.to_owned().verify();
+            (result, verifier.uses_retired_secret_key().unwrap())
+        }
+
+        let mut secret_key_ring = SecretKeyRing::new("2024", "old secret").unwrap();
+        let old_hash = hash(SecretKeyRing::new("2024", "old secret").unwrap());
+        assert!(old_hash.contains(",keyid=MjAyNA$"));
+        assert_eq!(
+            verify(&old_hash, secret_key_ring.to_owned()),
+            (Ok(true), false)
+        );
+
+        // Rotate to a new key
+        secret_key_ring.insert("2025", "new secret").unwrap();
+        secret_key_ring.set_active_key_id("2025").unwrap();
+        let new_hash = hash(secret_key_ring.to_owned());
+        assert!(new_hash.contains(",keyid=MjAyNQ$"));
+        assert_eq!(
+            verify(&old_hash, secret_key_ring.to_owned()),
+            (Ok(true), true)
+        );
+        assert_eq!(
+            verify(&new_hash, secret_key_ring.to_owned()),
+            (Ok(true), false)
+        );
+
+        // Hashes without a key ID use the secret key provided via with_secret_key
+        let mut hasher = Hasher::default();
+        let legacy_hash = hasher
+            .configure_iterations(1)
+            .configure_lanes(1)
+            .configure_memory_size(32)
+            .configure_threads(1)
+            .with_password("P@ssw0rd")
+            .with_salt("somesaltsomesalt")
+            .with_secret_key_ring(SecretKeyRing::new("2024", "old secret").unwrap())
+            .with_secret_key("legacy secret")
+            .hash()
+            .unwrap();
+        assert!(!legacy_hash.contains("keyid"));
+        assert_eq!(
+            verify(&legacy_hash, secret_key_ring.to_owned()),
+            (Ok(true), true)
+        );
+
+        // Unknown key IDs are an error rather than a failed verification
+        let unknown_ring = SecretKeyRing::new("2023", "old secret").unwrap();
+        let (result, retired) = verify(&old_hash, unknown_ring);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            ErrorKind::SecretKeyIdUnknownError
+        );
+        assert!(retired);
+    }
+
+    #[test]
+    fn test_verifier_legacy() {
+        // Test vector from the Argon2 reference implementation (version 0x10)
+        let hash =
+            "$argon2i$m=65536,t=2,p=1$c29tZXNhbHQ$9sTbSlTio3Biev89thdrlKKiCaYsjjYVJxGAL3swxpQ";
+        let hash_raw = hash.parse::<HashRaw>().unwrap();
+        assert_eq!(hash_raw.to_string(), hash);
+        for &backend in &[Backend::default(), Backend::Rust] {
+            let mut verifier = Verifier::default();
+            verifier
+                .configure_backend(backend)
+                .configure_threads(1)
+                .with_hash(hash)
+                .with_password("password");
+            assert!(verifier.verify().unwrap());
+            assert_eq!(verifier.hash_raw().unwrap(), Some(hash_raw.clone()));
+
+            verifier.with_password("passw0rd");
+            assert!(!verifier.verify().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_verifier_lenient_decoding() {
+        let mut hasher = Hasher::default();
+        let hash = hasher
+            .configure_iterations(1)
+            .configure_lanes(1)
+            .configure_memory_size(32)
+            .configure_threads(1)
+            .opt_out_of_secret_key(true)
+            .with_password("P@ssw0rd")
+            .with_salt("somesaltsomesalt")
+            .hash()
+            .unwrap();
+        // Re-encode the parameters in a different order and pad the base64
+        let hash = format!(" {}=\n", hash.replace("m=32,t=1,p=1", "p=1,t=1,m=32"));
+
+        let mut verifier = Verifier::default();
+        verifier.with_hash(&hash).with_password("P@ssw0rd");
+        let e = verifier.verify().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::HashDecodeError);
+        assert!(!verifier.config().lenient_decoding());
+
+        verifier.configure_lenient_decoding(true);
+        assert!(verifier.config().lenient_decoding());
+        assert!(verifier.verify().unwrap());
+        assert!(verifier.to_owned().verify().unwrap());
+        assert_eq!(
+            verifier.hash_raw().unwrap(),
+            Some(HashRaw::from_str_lenient(&hash).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_verifier_limits() {
+        // Would need 4 GiB of memory and 100000 passes if it were hashed
+        let hash = "$argon2id$v=19$m=4194304,t=100000,p=1$c29tZXNhbHRzb21lc2FsdA$iWh06vD8Fy27wf9npn6FXWiCX4K6pW6Ue1Bnzz07Z8A";
+        let mut verifier = Verifier::default();
+        verifier.with_hash(hash).with_password("P@ssw0rd");
+        let e = verifier.verify().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::HashParamsRejectedError);
+        verifier.with_hash_raw(&decode_rust(hash).unwrap());
+        let e = verifier.verify().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::HashParamsRejectedError);
+
+        let mut hasher = Hasher::default();
+        let hash = hasher
+            .configure_iterations(2)
+            .configure_lanes(1)
+            .configure_memory_size(32)
+            .configure_threads(1)
+            .configure_variant(Variant::Argon2i)
+            .opt_out_of_secret_key(true)
+            .with_password("P@ssw0rd")
+            .with_salt("somesaltsomesalt")
+            .hash()
+            .unwrap();
+        let mut verifier = Verifier::default();
+        verifier
+            .configure_max_memory_size(32)
+            .configure_max_iterations(2)
+            .configure_max_lanes(1)
+            .configure_max_hash_len(32)
+            .configure_min_salt_len(16)
+            .with_hash(&hash)
+            .with_password("P@ssw0rd");
+        assert!(verifier.verify().unwrap());
+        assert_eq!(verifier.config().limits().max_memory_size(), 32);
+
+        let reject = |verifier: &mut Verifier| {
+            let e = verifier.verify().unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::HashParamsRejectedError);
+        };
+        reject(verifier.to_owned().configure_max_memory_size(16));
+        reject(verifier.to_owned().configure_max_iterations(1));
+        reject(verifier.to_owned().configure_min_salt_len(32));
+        reject(
+            verifier
+                .to_owned()
+                .configure_allowed_variants(&[Variant::Argon2id]),
+        );
+        reject(
+            verifier
+                .to_owned()
+                .configure_allowed_versions(&[Version::_0x10]),
+        );
+    }
+
     #[test]
     fn test_send() {
         fn assert_send<T: Send>() {}
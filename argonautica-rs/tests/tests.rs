@@ -12,7 +12,7 @@ use std::{
 
 use argonautica::{
     Hasher, Verifier,
-    config::{Variant, Version},
+    config::{Backend, Variant, Version},
 };
 use rand::{
     distr::{Alphanumeric, SampleString},
@@ -203,6 +203,16 @@ fn test(input: &Input) {
     }
     let hash3 = hasher.hash_raw().unwrap().raw_hash_bytes().to_vec();
 
+    // Hash Rust (with the Rust backend)
+    hasher
+        .configure_backend(Backend::Rust)
+        .configure_password_clearing(false)
+        .configure_secret_key_clearing(false)
+        .with_password(args[1])
+        .with_secret_key(args[3]);
+    let encoded4 = hasher.hash().unwrap();
+    let hash4 = hasher.hash_raw().unwrap().raw_hash_bytes().to_vec();
+
     // Verify Rust
     let mut verifier = Verifier::default();
     verifier
@@ -238,20 +248,24 @@ fn test(input: &Input) {
     println!("{}", &encoded1);
     println!("{}", &encoded2);
     println!("{}", &encoded3);
+    println!("{}", &encoded4);
     println!("{:?}", &hash1);
     println!("{:?}", &hash2);
     println!("{:?}", &hash3);
+    println!("{:?}", &hash4);
     println!();
 
     // Compare results
     if (encoded1 != encoded2)
         || (encoded2 != encoded3)
+        || (encoded3 != encoded4)
         || (hash1 != hash2)
         || (hash2 != hash3)
+        || (hash3 != hash4)
     {
         panic!(
-            "\nCompare failed:\n{:#?}\n{}\n{}\n{}\n{:?}\n{:?}\n{:?}\n",
-            &input, &encoded1, &encoded2, &encoded3, &hash1, &hash2, &hash3,
+            "\nCompare failed:\n{:#?}\n{}\n{}\n{}\n{}\n{:?}\n{:?}\n{:?}\n{:?}\n",
+            &input, &encoded1, &encoded2, &encoded3, &encoded4, &hash1, &hash2, &hash3, &hash4,
         );
     }
 }
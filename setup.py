@@ -57,7 +57,6 @@ setup(
         'argonautica-c/Cargo.toml',
         binding=Binding.NoBinding,
         debug=False,
-        features=["simd"],
         native=True,
         rust_version=">=1.26.0",
     )],